@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         alpha_blending: false,
         ..Default::default()
     };
-    let call = renderer.create_draw_call(params);
+    let call = renderer.create_draw_call(params)?;
 
     // Create the text renderer
     let mut text =
@@ -69,12 +69,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         // Render the glyphs into the draw call
         text.compose_draw_call(&mut renderer);
 
-        // Render the OpenGL draw calls
-        renderer.render(window.width, window.height);
-
-        // Swap the buffers and wait for the window to refresh (this
-        // is referring to vsync, and will usually take ~16ms at max)
-        window.swap_buffers(Some(&renderer));
+        // Render the OpenGL draw calls, swap the buffers and wait for
+        // the window to refresh (this is referring to vsync, and will
+        // usually take ~16ms at max)
+        window.present(&mut renderer);
     }
     Ok(())
 }
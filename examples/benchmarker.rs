@@ -57,7 +57,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         alpha_blending: false,
         ..Default::default()
     };
-    let call = renderer.create_draw_call(params);
+    let call = renderer.create_draw_call(params)?;
 
     let mut should_quit = false;
     let mut quad_count = 1;
@@ -163,8 +163,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         // Spinny sprites
         for i in 0..quad_count {
             let f = i as f32 / quad_count as f32;
-            let x = (f * 3.1415 * 8.0 + time).cos() * 150.0 * f.max(0.3) + 270.0;
-            let y = (f * 3.1415 * 8.0 + time).sin() * 150.0 * f.max(0.3) + 190.0;
+            let x = (f * std::f32::consts::PI * 8.0 + time).cos() * 150.0 * f.max(0.3) + 270.0;
+            let y = (f * std::f32::consts::PI * 8.0 + time).sin() * 150.0 * f.max(0.3) + 190.0;
             renderer.draw_quad(
                 (x, y, x + 100.0, y + 100.0),
                 (0.0, 0.0, 1.0, 1.0),
@@ -303,7 +303,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 #[cfg(feature = "glutin")]
-fn set_cursor_over_text(window: &Window, over_text: bool) {
+fn set_cursor_over_text(window: &mut Window, over_text: bool) {
     use fae::window::glutin::MouseCursor;
     window.set_cursor(if over_text {
         MouseCursor::Text
@@ -323,7 +323,7 @@ fn set_cursor_over_text(window: &mut Window, over_text: bool) {
 }
 
 #[cfg(not(any(feature = "glutin", feature = "glfw")))]
-fn set_cursor_over_text(_window: &Window, _over_text: bool) {}
+fn set_cursor_over_text(_window: &mut Window, _over_text: bool) {}
 
 #[cfg(feature = "flame")]
 fn dump_profiling_data() {
@@ -340,6 +340,7 @@ use std::cell::Cell;
 struct Timer {
     start: Cell<Instant>,
     duration: Cell<Duration>,
+    #[cfg_attr(not(feature = "flame"), allow(dead_code))]
     name: String,
 }
 
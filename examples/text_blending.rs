@@ -0,0 +1,61 @@
+//! A visual test for the blending of anti-aliased text: the edges of
+//! the glyphs should blend smoothly into the mid-gray and colored
+//! backgrounds, without dark fringes. The lower line in each row is
+//! drawn at half opacity, and should look faded, not opaque.
+#![windows_subsystem = "windows"]
+
+use fae::{
+    renderer::Renderer,
+    text::{Alignment, TextRenderer},
+    window::{Window, WindowSettings},
+};
+use std::error::Error;
+use std::fs;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut window = Window::create(&WindowSettings::default())?;
+    let mut renderer = Renderer::new(window.opengl21);
+    let background = *renderer.white_draw_call();
+    let mut text =
+        TextRenderer::create(fs::read("examples/res/FiraSans.ttf")?, false, &mut renderer)?;
+    let translucent = text.bake_text(
+        "The quick brown fox jumps over the lazy dog",
+        24.0,
+        Alignment::Left,
+        None,
+        &mut renderer,
+    )?;
+
+    let backgrounds = [
+        (0.5, 0.5, 0.5, 1.0),
+        (0.8, 0.2, 0.2, 1.0),
+        (0.2, 0.6, 0.3, 1.0),
+        (0.2, 0.3, 0.8, 1.0),
+    ];
+    while window.refresh() {
+        text.update_dpi_factor(window.dpi_factor);
+        let row_height = window.height / backgrounds.len() as f32;
+        for (i, &color) in backgrounds.iter().enumerate() {
+            let y = i as f32 * row_height;
+            renderer.draw_quad_tinted(
+                (0.0, y, window.width, y + row_height),
+                color,
+                (0.0, 0.0, 0.0),
+                0.5,
+                &background,
+            );
+            text.draw_text(
+                "The quick brown fox jumps over the lazy dog",
+                (10.0, y + 10.0, 0.0),
+                24.0,
+                Alignment::Left,
+                None,
+                None,
+            );
+            renderer.draw_baked_text(&translucent, (10.0, y + 40.0, 0.0), (0.0, 0.0, 0.0, 0.5));
+        }
+        text.compose_draw_call(&mut renderer);
+        window.present(&mut renderer);
+    }
+    Ok(())
+}
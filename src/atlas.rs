@@ -0,0 +1,207 @@
+//! Texture atlas functionality: drawing named sprites from a single
+//! texture, based on metadata from a texture packer.
+
+use crate::error::FaeError;
+use crate::renderer::{DrawCallHandle, Renderer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A named region of an atlas, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// The left edge of the region.
+    pub x: f32,
+    /// The top edge of the region.
+    pub y: f32,
+    /// The width of the region, as it is stored in the atlas.
+    pub w: f32,
+    /// The height of the region, as it is stored in the atlas.
+    pub h: f32,
+    /// Whether the sprite is stored rotated 90 degrees clockwise.
+    pub rotated: bool,
+}
+
+/// A collection of named sprites in the texture of a draw call.
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    draw_call: DrawCallHandle,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl Atlas {
+    /// Creates an atlas from JSON metadata describing the sprites in
+    /// the texture of `draw_call`. The JSON should be an array of
+    /// objects like `{"name": "player", "x": 0, "y": 0, "w": 16, "h":
+    /// 32, "rotated": false}`, where the rectangle is the region of
+    /// the sprite in the texture, in pixels. `rotated` is optional,
+    /// see [`Renderer::draw_sprite_rotated_atlas`] for what it means.
+    ///
+    /// Results in a [`FaeError::AtlasParse`](../enum.FaeError.html)
+    /// if the JSON can't be parsed.
+    pub fn from_json(json: &str, draw_call: DrawCallHandle) -> Result<Atlas, FaeError> {
+        let json: Value =
+            serde_json::from_str(json).map_err(|err| FaeError::AtlasParse(err.to_string()))?;
+        let entries = json
+            .as_array()
+            .ok_or_else(|| FaeError::AtlasParse("expected an array of sprites".to_owned()))?;
+
+        let mut regions = HashMap::with_capacity(entries.len());
+        for entry in entries {
+            let name = entry["name"]
+                .as_str()
+                .ok_or_else(|| FaeError::AtlasParse("expected a name for a sprite".to_owned()))?;
+            let number = |field: &str| {
+                entry[field].as_f64().map(|n| n as f32).ok_or_else(|| {
+                    FaeError::AtlasParse(format!("expected a number for {} of {}", field, name))
+                })
+            };
+            let region = AtlasRegion {
+                x: number("x")?,
+                y: number("y")?,
+                w: number("w")?,
+                h: number("h")?,
+                rotated: entry["rotated"].as_bool().unwrap_or(false),
+            };
+            regions.insert(name.to_owned(), region);
+        }
+        Ok(Atlas { draw_call, regions })
+    }
+
+    /// Returns the region of the sprite called `name`, if there is
+    /// one.
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).cloned()
+    }
+
+    /// Returns the draw call the atlas draws with.
+    pub fn draw_call(&self) -> DrawCallHandle {
+        self.draw_call
+    }
+
+    /// Draws the sprite called `name`.
+    ///
+    /// - `coords`: The coordinates of the corners of the drawn
+    ///   sprite, in (logical) pixels. Arrangement: (left, top, right,
+    ///   bottom)
+    ///
+    /// Results in a [`FaeError::UnknownSprite`](../enum.FaeError.html)
+    /// if there is no sprite called `name` in the atlas. See
+    /// [`Renderer::draw_quad`] for the rest of the parameters' docs.
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        name: &str,
+        coords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        depth: f32,
+    ) -> Result<(), FaeError> {
+        let region = self
+            .region(name)
+            .ok_or_else(|| FaeError::UnknownSprite(name.to_owned()))?;
+        let src_px = (region.x, region.y, region.x + region.w, region.y + region.h);
+        renderer.draw_sprite_rotated_atlas(
+            coords,
+            src_px,
+            region.rotated,
+            color,
+            depth,
+            &self.draw_call,
+        );
+        Ok(())
+    }
+}
+
+/// How an [`Animation`] proceeds after its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// The animation stops at the last frame.
+    Once,
+    /// The animation starts over from the first frame.
+    Loop,
+    /// The animation plays backwards to the first frame, and then
+    /// forwards again, without repeating the first and last frames.
+    PingPong,
+}
+
+/// A flipbook animation, which steps through a list of sprites in an
+/// [`Atlas`] at a constant rate.
+///
+/// # Example
+/// ```
+/// use fae::atlas::{Animation, PlaybackMode};
+/// use std::time::Duration;
+///
+/// let frames = vec!["walk0".to_owned(), "walk1".to_owned()];
+/// let mut animation = Animation::new(frames, Duration::from_millis(100), PlaybackMode::Loop);
+/// animation.update(Duration::from_millis(150));
+/// assert_eq!(animation.current_frame(), "walk1");
+/// // Then draw with: atlas.draw(&mut renderer, animation.current_frame(), ...)
+/// ```
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<String>,
+    frame_duration: Duration,
+    mode: PlaybackMode,
+    elapsed: Duration,
+}
+
+impl Animation {
+    /// Creates a new animation showing each of the `frames` (names
+    /// of sprites in an atlas) for `frame_duration`.
+    ///
+    /// Panics if `frames` is empty.
+    pub fn new(frames: Vec<String>, frame_duration: Duration, mode: PlaybackMode) -> Animation {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        Animation {
+            frames,
+            frame_duration: frame_duration.max(Duration::from_nanos(1)),
+            mode,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    /// Advances the animation by `delta`, usually the time since the
+    /// last frame.
+    pub fn update(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    /// Rewinds the animation to the first frame.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::from_secs(0);
+    }
+
+    /// Returns the index of the current frame in the frame list.
+    pub fn current_frame_index(&self) -> usize {
+        let count = self.frames.len();
+        let step = (self.elapsed.as_nanos() / self.frame_duration.as_nanos()) as usize;
+        match self.mode {
+            PlaybackMode::Once => step.min(count - 1),
+            PlaybackMode::Loop => step % count,
+            PlaybackMode::PingPong if count > 1 => {
+                let period = count * 2 - 2;
+                let index = step % period;
+                if index < count {
+                    index
+                } else {
+                    period - index
+                }
+            }
+            PlaybackMode::PingPong => 0,
+        }
+    }
+
+    /// Returns the name of the current frame.
+    pub fn current_frame(&self) -> &str {
+        &self.frames[self.current_frame_index()]
+    }
+
+    /// Returns whether a [`PlaybackMode::Once`] animation has shown
+    /// its last frame for its whole duration. Other animations never
+    /// finish.
+    pub fn is_finished(&self) -> bool {
+        self.mode == PlaybackMode::Once
+            && self.elapsed.as_nanos() >= self.frame_duration.as_nanos() * self.frames.len() as u128
+    }
+}
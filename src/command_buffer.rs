@@ -0,0 +1,209 @@
+//! Draw commands recorded as data, for building draw lists without
+//! access to the [`Renderer`](renderer/struct.Renderer.html), eg. on
+//! other threads, or for drawing the same things every frame without
+//! rebuilding them.
+
+use crate::renderer::{DrawCallHandle, QuadDesc};
+#[cfg(feature = "text")]
+use crate::text::Alignment;
+
+/// A draw recorded into a [`CommandBuffer`]. The fields correspond
+/// to the parameters of the `Renderer` (or `TextRenderer`) function
+/// of the same name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    /// A quad, see
+    /// [`Renderer::draw_quad`](renderer/struct.Renderer.html#method.draw_quad).
+    Quad {
+        /// The quad.
+        quad: QuadDesc,
+        /// The draw call to draw the quad in.
+        call: DrawCallHandle,
+    },
+    /// A triangle, see
+    /// [`Renderer::draw_triangle`](renderer/struct.Renderer.html#method.draw_triangle).
+    Triangle {
+        /// The corners of the triangle. Arrangement: [(x, y); 3]
+        vertices: [(f32, f32); 3],
+        /// The texture coordinates of each corner.
+        texcoords: [(f32, f32); 3],
+        /// The color tint of each corner.
+        colors: [(f32, f32, f32, f32); 3],
+        /// The depth of the triangle.
+        depth: f32,
+        /// The draw call to draw the triangle in.
+        call: DrawCallHandle,
+    },
+    /// Pushes a clipping rectangle, see
+    /// [`Renderer::push_clip_rect`](renderer/struct.Renderer.html#method.push_clip_rect).
+    PushClipRect((f32, f32, f32, f32)),
+    /// Pops the latest clipping rectangle, see
+    /// [`Renderer::pop_clip_rect`](renderer/struct.Renderer.html#method.pop_clip_rect).
+    PopClipRect,
+    /// A piece of text, see
+    /// [`TextRenderer::draw_text`](text/struct.TextRenderer.html#method.draw_text).
+    /// Drawn by
+    /// [`TextRenderer::execute`](text/struct.TextRenderer.html#method.execute),
+    /// the `Renderer` skips these.
+    #[cfg(feature = "text")]
+    Text {
+        /// The text.
+        text: String,
+        /// The position of the top-left corner of the text area.
+        /// Arrangement: (x, y, z)
+        position: (f32, f32, f32),
+        /// The size of the font.
+        font_size: f32,
+        /// The alignment of the text.
+        alignment: Alignment,
+        /// The width at which the text wraps.
+        max_row_width: Option<f32>,
+        /// The area outside of which the text is cut off.
+        clip_area: Option<(f32, f32, f32, f32)>,
+    },
+}
+
+/// A list of draws, recorded with the same functions as the ones of
+/// the [`Renderer`](renderer/struct.Renderer.html), and replayed
+/// into a renderer with
+/// [`Renderer::replay`](renderer/struct.Renderer.html#method.replay).
+///
+/// Command buffers can also be kept around and drawn every frame
+/// with
+/// [`Renderer::execute`](renderer/struct.Renderer.html#method.execute),
+/// eg. for UI that doesn't change often. The recorded commands can
+/// be inspected with [`CommandBuffer::commands`], and compared with
+/// `==`, eg. to only redraw when a frame differs from the last one.
+///
+/// The renderer can only be used on the thread its OpenGL context is
+/// current on, but command buffers are `Send`, so they can be built
+/// on other threads, and sent to the rendering thread once done.
+///
+/// # Example
+/// ```no_run
+/// # use fae::{renderer::Renderer, CommandBuffer};
+/// # let mut renderer = Renderer::new(false);
+/// # let call = renderer.create_draw_call(Default::default()).unwrap();
+/// let (sender, receiver) = std::sync::mpsc::channel();
+/// std::thread::spawn(move || {
+///     let mut commands = CommandBuffer::new();
+///     let (coords, texcoords) = ((0.0, 0.0, 16.0, 16.0), (0.0, 0.0, 1.0, 1.0));
+///     let (color, rotation) = ((1.0, 1.0, 1.0, 1.0), (0.0, 0.0, 0.0));
+///     commands.draw_quad(coords, texcoords, color, rotation, 0.0, &call);
+///     sender.send(commands).unwrap();
+/// });
+/// for commands in receiver {
+///     renderer.replay(commands);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandBuffer {
+    commands: Vec<DrawCommand>,
+}
+
+impl CommandBuffer {
+    /// Creates an empty command buffer.
+    pub fn new() -> CommandBuffer {
+        CommandBuffer::default()
+    }
+
+    /// Records a quad, see
+    /// [`Renderer::draw_quad`](renderer/struct.Renderer.html#method.draw_quad).
+    pub fn draw_quad(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        texcoords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        rotation: (f32, f32, f32),
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        self.commands.push(DrawCommand::Quad {
+            quad: QuadDesc {
+                coords,
+                texcoords,
+                color,
+                rotation,
+                depth,
+            },
+            call: *call_handle,
+        });
+    }
+
+    /// Records a triangle, see
+    /// [`Renderer::draw_triangle`](renderer/struct.Renderer.html#method.draw_triangle).
+    pub fn draw_triangle(
+        &mut self,
+        vertices: [(f32, f32); 3],
+        texcoords: [(f32, f32); 3],
+        colors: [(f32, f32, f32, f32); 3],
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        self.commands.push(DrawCommand::Triangle {
+            vertices,
+            texcoords,
+            colors,
+            depth,
+            call: *call_handle,
+        });
+    }
+
+    /// Records pushing a clipping rectangle, see
+    /// [`Renderer::push_clip_rect`](renderer/struct.Renderer.html#method.push_clip_rect).
+    pub fn push_clip_rect(&mut self, rect: (f32, f32, f32, f32)) {
+        self.commands.push(DrawCommand::PushClipRect(rect));
+    }
+
+    /// Records popping the latest clipping rectangle, see
+    /// [`Renderer::pop_clip_rect`](renderer/struct.Renderer.html#method.pop_clip_rect).
+    pub fn pop_clip_rect(&mut self) {
+        self.commands.push(DrawCommand::PopClipRect);
+    }
+
+    /// Records a piece of text, see
+    /// [`TextRenderer::draw_text`](text/struct.TextRenderer.html#method.draw_text).
+    /// As the text isn't laid out until it's executed, the bounding
+    /// rectangle isn't returned.
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        position: (f32, f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        clip_area: Option<(f32, f32, f32, f32)>,
+    ) {
+        self.commands.push(DrawCommand::Text {
+            text: text.to_owned(),
+            position,
+            font_size,
+            alignment,
+            max_row_width,
+            clip_area,
+        });
+    }
+
+    /// Returns the recorded commands, in the order they were
+    /// recorded in.
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /// Returns the amount of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns whether there are no recorded commands.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all the recorded commands, keeping the allocation for
+    /// recording new ones.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+}
@@ -0,0 +1,52 @@
+//! The error type of the crate.
+
+use std::error::Error;
+use std::fmt;
+
+/// Describes what went wrong when something in fae fails. The
+/// variants carry a human-readable description of the underlying
+/// problem.
+#[derive(Debug, Clone)]
+pub enum FaeError {
+    /// The window could not be created.
+    WindowCreation(String),
+    /// The OpenGL context could not be created, or made current.
+    ContextCreation(String),
+    /// A shader failed to compile, or a shader program failed to
+    /// link. Contains the full info log from the driver.
+    ShaderCompile(String),
+    /// An image could not be decoded.
+    ImageDecode(String),
+    /// A font could not be loaded.
+    FontLoad(String),
+    /// The metadata of an atlas could not be parsed.
+    AtlasParse(String),
+    /// There is no sprite with the name in the atlas.
+    UnknownSprite(String),
+    /// An image is larger than the maximum texture size of the
+    /// OpenGL driver.
+    TextureTooLarge(String),
+    /// The requested functionality is not supported by the current
+    /// window backend or OpenGL context.
+    Unsupported(String),
+}
+
+impl fmt::Display for FaeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaeError::WindowCreation(msg) => write!(f, "window creation failed: {}", msg),
+            FaeError::ContextCreation(msg) => write!(f, "OpenGL context creation failed: {}", msg),
+            FaeError::ShaderCompile(msg) => write!(f, "shader compilation failed:\n{}", msg),
+            FaeError::ImageDecode(msg) => write!(f, "image decoding failed: {}", msg),
+            FaeError::FontLoad(msg) => write!(f, "font loading failed: {}", msg),
+            FaeError::AtlasParse(msg) => write!(f, "atlas parsing failed: {}", msg),
+            FaeError::UnknownSprite(name) => {
+                write!(f, "no sprite called \"{}\" in the atlas", name)
+            }
+            FaeError::TextureTooLarge(msg) => write!(f, "texture too large: {}", msg),
+            FaeError::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+        }
+    }
+}
+
+impl Error for FaeError {}
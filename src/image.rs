@@ -1,8 +1,48 @@
-use crate::gl;
-#[cfg(feature = "png")]
-use png;
 #[cfg(feature = "png")]
-use std::error::Error;
+use crate::error::FaeError;
+use crate::gl;
+
+/// The color channels of the data an [`Image`] was created from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorType {
+    /// One channel: brightness.
+    Grayscale,
+    /// Two channels: brightness and alpha.
+    GrayscaleAlpha,
+    /// Three channels: red, green and blue.
+    Rgb,
+    /// Four channels: red, green, blue and alpha.
+    Rgba,
+}
+
+impl ColorType {
+    /// Returns the amount of color channels.
+    pub fn channels(self) -> usize {
+        match self {
+            ColorType::Grayscale => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    /// Returns true if the color type has an alpha channel.
+    pub fn has_alpha(self) -> bool {
+        self == ColorType::GrayscaleAlpha || self == ColorType::Rgba
+    }
+}
+
+/// The filtering used when resizing an [`Image`] with
+/// [`Image::resize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Each pixel is copied from the closest pixel of the original
+    /// image. Keeps pixel art sharp.
+    Nearest,
+    /// Each pixel is interpolated from the four closest pixels of the
+    /// original image.
+    Bilinear,
+}
 
 /// Contains the raw pixel color data of an image (`u8` per color
 /// channel).
@@ -19,6 +59,12 @@ pub struct Image {
     /// GL_RGBA by default, which means that OpenGL will assume
     /// `pixels` is laid out like so: `[r, g, b, a, r, g, ...]`.
     pub format: u32,
+    /// The color type of the data the image was created from. Can be
+    /// used to eg. disable blending for images without an alpha
+    /// channel. Note that PNGs are always expanded to RGBA `pixels`,
+    /// and paletted PNGs are reported as `Rgb`, or `Rgba` if they
+    /// have transparency.
+    pub color_type: ColorType,
 }
 
 impl Image {
@@ -31,16 +77,41 @@ impl Image {
     /// let sprite = Image::from_png(&fs::read("sprite.png").unwrap()).unwrap();
     /// ```
     #[cfg(feature = "png")]
-    pub fn from_png(bytes: &[u8]) -> Result<Image, Box<Error>> {
+    pub fn from_png(bytes: &[u8]) -> Result<Image, FaeError> {
+        let decode_error = |err: png::DecodingError| FaeError::ImageDecode(err.to_string());
         let decoder = png::Decoder::new(bytes);
-        let (info, mut reader) = decoder.read_info()?;
-        let mut pixels = vec![0; info.buffer_size()];
-        reader.next_frame(&mut pixels)?;
+        let (info, mut reader) = decoder.read_info().map_err(decode_error)?;
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).map_err(decode_error)?;
+        // The decoder expands palettes and bit depths under 8, and
+        // strips 16-bit samples, so the data is 8 bits per channel
+        let color_type = match info.color_type {
+            png::ColorType::Grayscale => ColorType::Grayscale,
+            png::ColorType::GrayscaleAlpha => ColorType::GrayscaleAlpha,
+            png::ColorType::RGB | png::ColorType::Indexed => ColorType::Rgb,
+            png::ColorType::RGBA => ColorType::Rgba,
+        };
+        let pixels = if color_type == ColorType::Rgba {
+            data
+        } else {
+            let channels = color_type.channels();
+            let mut pixels = Vec::with_capacity(data.len() / channels * 4);
+            for pixel in data.chunks(channels) {
+                match *pixel {
+                    [gray] => pixels.extend_from_slice(&[gray, gray, gray, 0xFF]),
+                    [gray, alpha] => pixels.extend_from_slice(&[gray, gray, gray, alpha]),
+                    [r, g, b] => pixels.extend_from_slice(&[r, g, b, 0xFF]),
+                    _ => unreachable!(),
+                }
+            }
+            pixels
+        };
         Ok(Image {
             pixels,
             width: info.width as i32,
             height: info.height as i32,
             format: gl::RGBA,
+            color_type,
         })
     }
 
@@ -59,11 +130,18 @@ impl Image {
         for i in 0..pixels.len() {
             pixels[i] = color[i % color.len()];
         }
+        let color_type = match color.len() {
+            1 => ColorType::Grayscale,
+            2 => ColorType::GrayscaleAlpha,
+            3 => ColorType::Rgb,
+            _ => ColorType::Rgba,
+        };
         Image {
             pixels,
             width,
             height,
             format: gl::RGBA,
+            color_type,
         }
     }
 
@@ -80,4 +158,66 @@ impl Image {
         self.format = format;
         self
     }
+
+    /// Creates a resized copy of the image, eg. to downscale large
+    /// textures before uploading them. Works with any amount of
+    /// bytes per pixel.
+    ///
+    /// # Example
+    /// ```
+    /// use fae::{Image, ResizeFilter};
+    /// let image = Image::from_color(128, 128, &[0xB4, 0x6E, 0xC8, 0xFF]);
+    /// let thumbnail = image.resize(16, 16, ResizeFilter::Bilinear);
+    /// assert_eq!(thumbnail.pixels.len(), 16 * 16 * 4);
+    /// ```
+    pub fn resize(&self, new_width: u32, new_height: u32, filter: ResizeFilter) -> Image {
+        let (width, height) = (self.width.max(0) as usize, self.height.max(0) as usize);
+        let (new_width, new_height) = (new_width as usize, new_height as usize);
+        let channels = self.pixels.len().checked_div(width * height).unwrap_or(4);
+        let mut pixels = vec![0; new_width * new_height * channels];
+        if width * height > 0 {
+            // Maps pixel centers of the new image onto the old one
+            let scale_x = width as f32 / new_width as f32;
+            let scale_y = height as f32 / new_height as f32;
+            let pixel = |x: usize, y: usize| {
+                let i = (y * width + x) * channels;
+                &self.pixels[i..i + channels]
+            };
+            for y in 0..new_height {
+                for x in 0..new_width {
+                    let src_x = (x as f32 + 0.5) * scale_x;
+                    let src_y = (y as f32 + 0.5) * scale_y;
+                    let dst = &mut pixels[(y * new_width + x) * channels..][..channels];
+                    match filter {
+                        ResizeFilter::Nearest => {
+                            let src_x = (src_x as usize).min(width - 1);
+                            let src_y = (src_y as usize).min(height - 1);
+                            dst.copy_from_slice(pixel(src_x, src_y));
+                        }
+                        ResizeFilter::Bilinear => {
+                            let src_x = (src_x - 0.5).max(0.0).min((width - 1) as f32);
+                            let src_y = (src_y - 0.5).max(0.0).min((height - 1) as f32);
+                            let (x0, y0) = (src_x as usize, src_y as usize);
+                            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+                            let (tx, ty) = (src_x - x0 as f32, src_y - y0 as f32);
+                            for (c, dst) in dst.iter_mut().enumerate() {
+                                let lerp =
+                                    |a: u8, b: u8, t: f32| a as f32 + (b as f32 - a as f32) * t;
+                                let top = lerp(pixel(x0, y0)[c], pixel(x1, y0)[c], tx);
+                                let bottom = lerp(pixel(x0, y1)[c], pixel(x1, y1)[c], tx);
+                                *dst = (top + (bottom - top) * ty).round() as u8;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Image {
+            pixels,
+            width: new_width as i32,
+            height: new_height as i32,
+            format: self.format,
+            color_type: self.color_type,
+        }
+    }
 }
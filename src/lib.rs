@@ -5,23 +5,36 @@
 //! crate's usage.
 
 #![warn(missing_docs)]
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
 #[cfg(test)]
 extern crate test;
 #[cfg(test)]
 mod tests;
 
-#[allow(missing_docs, unknown_lints, clippy::all)]
+#[allow(missing_docs, bare_trait_objects, unknown_lints, clippy::all)]
 pub mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
 
+mod command_buffer;
+mod error;
 mod image;
 pub mod renderer;
+pub mod software;
+mod timestep;
 
-pub use crate::image::Image;
+pub use crate::command_buffer::{CommandBuffer, DrawCommand};
+pub use crate::error::FaeError;
+pub use crate::image::{ColorType, Image, ResizeFilter};
+pub use crate::timestep::Timestep;
 
 #[cfg(feature = "text")]
 pub mod text;
 
+#[cfg(feature = "atlas")]
+pub mod atlas;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
 pub mod window;
@@ -9,6 +9,20 @@
 //!   and similar functions using the draw call index to use specific
 //!   textures.
 //!
+//! ## Coordinate spaces
+//! - All coordinates and sizes given to the drawing functions are in
+//!   logical pixels, with the origin at the top-left corner of the
+//!   window. The `width` and `height` given to
+//!   [`Renderer::render`](struct.Renderer.html#method.render) should
+//!   be the window's logical size, like `Window::width` and
+//!   `Window::height`.
+//! - The OpenGL viewport is in physical pixels (the size of the
+//!   framebuffer), and the window sets it up when it's created and
+//!   resized. The projection maps the logical size onto the whole
+//!   viewport, so one logical pixel covers `dpi_factor` physical
+//!   pixels in both directions, and rotations, which are done before
+//!   the projection, happen in undistorted logical space.
+//!
 //! ## Optimization tips
 //! - Try to define different Z coordinates for your elements, and
 //!   draw the ones in front first. This way you'll avoid rendering
@@ -21,11 +35,14 @@
 //!   if you're handling OpenGL state properly yourself, set your
 //!   [`Renderer`](struct.Renderer.html)'s `preserve_gl_state` to false.
 
+use crate::command_buffer::{CommandBuffer, DrawCommand};
+use crate::error::FaeError;
 use crate::gl;
 use crate::gl::types::*;
-use crate::image::Image;
+use crate::image::{Image, ResizeFilter};
+use std::marker::PhantomData;
 use std::mem;
-use std::ptr;
+use std::time::Duration;
 
 type TextureHandle = GLuint;
 type VBOHandle = GLuint;
@@ -34,10 +51,35 @@ type VAOHandle = GLuint;
 /// A handle with which you can draw during a specific draw
 /// call. Created during [`Renderer::create_draw_call`], used during
 /// [`Renderer::draw_quad`] and its variations.
-pub struct DrawCallHandle(usize);
+///
+/// Handles stay valid until the draw call is deleted with
+/// [`Renderer::delete_draw_call`]. After that, its slot can be reused
+/// by a new draw call, but the handles of the deleted one cannot be
+/// used to draw into it: using them causes a panic. Using a handle
+/// with a renderer other than the one that created it panics too, if
+/// the index is out of that renderer's range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawCallHandle {
+    index: usize,
+    generation: u32,
+}
 
 /// Represents the shader code for a shader. Used in
 /// [`Renderer::create_draw_call`].
+///
+/// Both versions of the vertex shader get the same per-vertex
+/// attributes: `position` (vec3), `texcoord` (vec2), `color` (vec4)
+/// and `rotation` (vec3: radians, pivot x, pivot y), and the
+/// `projection_matrix` (mat4) uniform.
+///
+/// The texture of the draw call is bound to texture unit 0, sampled
+/// with the `tex` (sampler2D) uniform in the default fragment
+/// shaders. Textures set with
+/// [`Renderer::set_draw_call_extra_texture`] are bound to units 1 to
+/// [`MAX_EXTRA_TEXTURES`], and the `tex1`, `tex2`, etc. sampler
+/// uniforms are set to sample from those units, so eg. a mask
+/// texture in unit 1 can be used by declaring `uniform sampler2D
+/// tex1;` in the fragment shader.
 #[derive(Clone, Copy, Debug)]
 pub struct Shaders {
     /// The GLSL 3.30 version of the vertex shader. Ensure that the
@@ -64,16 +106,11 @@ struct ShaderProgram {
     texcoord_attrib_location: GLuint,
     color_attrib_location: GLuint,
     rotation_attrib_location: GLuint,
-    depth_attrib_location: GLuint,
-    shared_position_attrib_location: GLuint,
-    shared_texcoord_attrib_location: GLuint,
 }
 
 #[derive(Clone, Debug)]
 struct Attributes {
     vbo: VBOHandle,
-    vbo_static: VBOHandle,
-    element_buffer: VBOHandle,
     vao: VAOHandle,
     vbo_data: Vec<f32>,
     allocated_vbo_data_size: isize,
@@ -82,10 +119,54 @@ struct Attributes {
 #[derive(Clone, Debug)]
 struct DrawCall {
     texture: TextureHandle,
+    /// The size of the texture, in pixels. Arrangement: (width,
+    /// height)
+    texture_size: (i32, i32),
+    /// False if the texture was created outside of fae, and
+    /// shouldn't be deleted with the draw call.
+    owns_texture: bool,
     program: ShaderProgram,
     attributes: Attributes,
     blend: bool,
+    blend_mode: BlendMode,
+    blend_equation: BlendEquation,
     lowest_depth: f32,
+    /// Static calls keep their quads between frames.
+    is_static: bool,
+    /// Whether the quads of a static call need to be uploaded again.
+    static_dirty: bool,
+    /// Deleted calls are left in place as tombstones, so that the
+    /// indices of the other calls stay valid. Their slot is reused by
+    /// the next created draw call.
+    deleted: bool,
+    /// The textures bound to texture units 1 and up, 0 if there's no
+    /// texture in the unit. See
+    /// [`Renderer::set_draw_call_extra_texture`].
+    extra_textures: [TextureHandle; MAX_EXTRA_TEXTURES as usize],
+    /// The glPolygonOffset factor and units applied when drawing, see
+    /// [`Renderer::set_polygon_offset`].
+    polygon_offset: Option<(f32, f32)>,
+    /// The minification and magnification filters the texture was
+    /// created with, restored when pixel perfect mode is disabled.
+    filters: (GLint, GLint),
+    /// Incremented every time the call is deleted, to tell handles to
+    /// the deleted call apart from handles to the one reusing its
+    /// slot.
+    generation: u32,
+}
+
+impl DrawCall {
+    fn can_batch_with(&self, other: &DrawCall) -> bool {
+        !self.is_static
+            && !other.is_static
+            && self.texture == other.texture
+            && self.extra_textures == other.extra_textures
+            && self.program.program == other.program.program
+            && self.blend == other.blend
+            && self.blend_mode == other.blend_mode
+            && self.blend_equation == other.blend_equation
+            && self.polygon_offset == other.polygon_offset
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -99,32 +180,195 @@ struct OpenGLState {
     depth_test: bool,
     depth_func: GLint,
     blend: bool,
-    blend_func: (GLint, GLint),
+    blend_func: (GLint, GLint, GLint, GLint),
+    blend_equation: GLint,
     program: GLint,
     vao: GLint,
     texture: GLint,
     vbo: GLint,
-    element_buffer: GLint,
+    framebuffer_srgb: bool,
+    /// Set by [`Renderer::push_gl_state`], when the state should only
+    /// be restored by [`Renderer::pop_gl_state`].
+    held: bool,
 }
 
 /// Contains the data and functionality needed to draw rectangles with
 /// OpenGL. **Requires** a valid OpenGL context.
+///
+/// When dropped, the textures, buffers and shader programs of all the
+/// draw calls are deleted, so the OpenGL context should still be
+/// current at that point: drop the renderer before the window.
+///
+/// OpenGL contexts are bound to the thread they're current on, so
+/// the renderer is not `Send`, and can't be moved to another thread:
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<fae::renderer::Renderer>();
+/// ```
+///
+/// To build draws on other threads, record them into a
+/// [`CommandBuffer`](../struct.CommandBuffer.html), which is `Send`,
+/// and replay it on the rendering thread with
+/// [`Renderer::replay`].
 #[derive(Debug)]
 pub struct Renderer {
     calls: Vec<DrawCall>,
     gl_state: OpenGLState,
     profiler: Profiler,
+    dpi_factor: f32,
+    auto_batch: bool,
+    z_interleaving: bool,
+    wireframe: bool,
+    max_quads_per_batch: Option<usize>,
+    srgb: bool,
+    /// Whether `GL_FRAMEBUFFER_SRGB` can be enabled, checked once in
+    /// [`Renderer::new`].
+    srgb_supported: bool,
+    depth_range: (f32, f32),
+    /// See [`Renderer::white_draw_call`].
+    white_call: DrawCallHandle,
+    debug_callback: Option<Box<DebugCallback>>,
+    stats: RenderStats,
+    projection: [f32; 16],
+    screen_size: (f32, f32),
+    screen_offset: (f32, f32),
+    viewport: Option<(i32, i32, i32, i32)>,
+    dirty_rect: Option<(f32, f32, f32, f32)>,
+    clear_color: (f32, f32, f32, f32),
+    virtual_resolution: Option<(u32, u32)>,
+    clip_stack: Vec<(f32, f32, f32, f32)>,
+    masks: Vec<StencilMask>,
+    mask_state: MaskState,
+    pixel_perfect: bool,
+    /// Keeps the renderer on the thread of its OpenGL context, even if
+    /// the rest of the fields were `Send`.
+    _not_send: PhantomData<*const ()>,
     /// Whether the Renderer should try to preserve the OpenGL
     /// state. If you're not using OpenGL yourself, set this to
     /// `false` for less overhead.
     pub preserve_gl_state: bool,
 }
 
+/// The quads of a stencil mask, per draw call index. See
+/// [`Renderer::begin_mask`].
+#[derive(Debug, Default)]
+struct StencilMask {
+    /// The quads that make up the shape of the mask.
+    shape: Vec<(usize, Vec<f32>)>,
+    /// The quads which are only drawn inside the shape.
+    masked: Vec<(usize, Vec<f32>)>,
+}
+
+/// Where drawn quads are queued, see [`Renderer::begin_mask`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MaskState {
+    Inactive,
+    Shape,
+    Masked,
+}
+
+/// The severity of an OpenGL debug message. See
+/// [`Renderer::set_debug_callback`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugSeverity {
+    /// Informational messages, eg. about buffer allocations.
+    Notification,
+    /// Minor performance warnings, or redundant state changes.
+    Low,
+    /// Significant performance warnings, or use of deprecated
+    /// functionality.
+    Medium,
+    /// Errors, or undefined behavior.
+    High,
+}
+
+/// A closure which receives OpenGL debug messages. See
+/// [`Renderer::set_debug_callback`].
+pub type DebugMessageHandler = Box<dyn FnMut(DebugSeverity, &str)>;
+
+struct DebugCallback {
+    min_severity: DebugSeverity,
+    callback: DebugMessageHandler,
+}
+
+impl std::fmt::Debug for DebugCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("DebugCallback")
+            .field("min_severity", &self.min_severity)
+            .finish()
+    }
+}
+
+/// A marker in the stream of OpenGL commands, created with
+/// [`Renderer::fence`]. Once the GPU has executed every command
+/// issued before the fence, the fence is signaled, which can be
+/// waited for with [`Renderer::wait_fence`].
+///
+/// Dropping the fence deletes the underlying sync object.
+#[derive(Debug)]
+pub struct Fence {
+    /// None on legacy OpenGL, which doesn't have sync objects.
+    sync: Option<GLsync>,
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        if let Some(sync) = self.sync {
+            if gl::DeleteSync::is_loaded() {
+                unsafe {
+                    gl::DeleteSync(sync);
+                }
+            }
+        }
+    }
+}
+
+/// Statistics about the OpenGL work done during the last
+/// [`Renderer::render`]. See [`Renderer::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The amount of draw calls issued (`glDrawArrays` calls). With
+    /// auto-batching, this can be less than the amount of draw calls
+    /// drawn into.
+    pub draw_calls: usize,
+    /// The amount of quads drawn. Triangles and polygons count as
+    /// fractions of quads, as this is just `vertices / 6`.
+    pub quads: usize,
+    /// The amount of vertices drawn.
+    pub vertices: usize,
+    /// The amount of times a texture was bound. Consecutive draw
+    /// calls using the same texture only bind it once.
+    pub texture_binds: usize,
+}
+
+/// Describes a quad for [`Renderer::upload_static`]. The fields
+/// correspond to the parameters of [`Renderer::draw_quad`], see its
+/// docs for details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuadDesc {
+    /// The coordinates of the corners of the quad. Arrangement:
+    /// (left, top, right, bottom)
+    pub coords: (f32, f32, f32, f32),
+    /// The texture coordinates of the quad.
+    pub texcoords: (f32, f32, f32, f32),
+    /// The color tint of the quad.
+    pub color: (f32, f32, f32, f32),
+    /// The rotation and pivot of the quad. Arrangement: (radians,
+    /// x, y)
+    pub rotation: (f32, f32, f32),
+    /// The depth of the quad.
+    pub depth: f32,
+}
+
 /// Options which set capabilities, restrictions and resources for
 /// draw calls. Used in [`Renderer::create_draw_call`].
 #[derive(Debug, Clone)]
 pub struct DrawCallParameters {
-    /// The texture used when drawing with this draw call.
+    /// The texture used when drawing with this draw call. If `None`,
+    /// the texture is a single white pixel, so the quads are drawn
+    /// in their vertex colors, which is useful for draw calls that
+    /// are only used for solid colors.
     pub image: Option<Image>,
     /// The shaders used when drawing with this draw call.
     pub shaders: Option<Shaders>,
@@ -132,6 +376,11 @@ pub struct DrawCallParameters {
     /// over them, or just replace the color. (In OpenGL terms:
     /// whether GL_BLEND is enabled.)
     pub alpha_blending: bool,
+    /// How the colors are blended, if `alpha_blending` is enabled.
+    pub blend_mode: BlendMode,
+    /// How the blended colors are combined, if `alpha_blending` is
+    /// enabled. See [`BlendEquation`].
+    pub blend_equation: BlendEquation,
     /// When drawing quads that are smaller than the texture provided,
     /// use linear (true) or nearest neighbor (false) smoothing when
     /// scaling? (Linear is probably always better.)
@@ -141,13 +390,70 @@ pub struct DrawCallParameters {
     /// scaling? (Tip: for pixel art or other textures that don't
     /// suffer from jaggies, set this to false for the intended look.)
     pub magnification_smoothing: bool,
-    /// Use this to specify the verticies on a triangle, the order
-    /// doesn't matter. If you supply a value to this then be sure
-    /// to set the `triangles` parameter to true.
-    pub verticies:Option<[(f32,f32);3]>,
-    /// Set this to true if you are using `verticies`, ALWAYS set
-    /// this true if you supply `verticies`
-    pub triangle:bool,
+    /// The amount of anisotropic filtering used when sampling the
+    /// texture, which keeps textures sharp when drawn at steep
+    /// angles. 1.0 disables it, and values above the driver's
+    /// maximum are clamped. Ignored if the
+    /// `GL_EXT_texture_filter_anisotropic` extension isn't supported.
+    pub anisotropy: f32,
+    /// If the image is larger than the maximum texture size of the
+    /// OpenGL driver (see [`Renderer::max_texture_size`]), scale it
+    /// down to fit (true), or make [`Renderer::create_draw_call`]
+    /// return [`FaeError::TextureTooLarge`] (false).
+    pub downscale_large_images: bool,
+}
+
+/// Describes how the colors drawn by a draw call are blended with
+/// the previously drawn pixels. See
+/// [`DrawCallParameters::blend_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Regular alpha blending, for colors which are not
+    /// premultiplied. (In OpenGL terms:
+    /// `glBlendFuncSeparate(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA,
+    /// GL_ONE, GL_ONE_MINUS_SRC_ALPHA)`, so that the framebuffer's
+    /// alpha stays correct for transparent windows.)
+    Alpha,
+    /// Blending for colors which have already been multiplied with
+    /// their alpha, like the output of the text shaders. Avoids the
+    /// dark fringes that regular alpha blending causes around
+    /// anti-aliased edges. (In OpenGL terms: `glBlendFunc(GL_ONE,
+    /// GL_ONE_MINUS_SRC_ALPHA)`.)
+    Premultiplied,
+}
+
+/// Describes how the drawn color (weighted by the [`BlendMode`]) is
+/// combined with the previously drawn color. See
+/// [`DrawCallParameters::blend_equation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendEquation {
+    /// The colors are added together, the default. (In OpenGL terms:
+    /// `glBlendEquation(GL_FUNC_ADD)`.)
+    Add,
+    /// The previous color is subtracted from the drawn
+    /// one. (`GL_FUNC_SUBTRACT`)
+    Subtract,
+    /// The drawn color is subtracted from the previous one, eg. for
+    /// darkening with light colors. (`GL_FUNC_REVERSE_SUBTRACT`)
+    ReverseSubtract,
+    /// The smaller of the colors is kept, per channel, for a "darken"
+    /// effect. The blend mode doesn't affect this. (`GL_MIN`)
+    Min,
+    /// The larger of the colors is kept, per channel, for a "lighten"
+    /// effect. The blend mode doesn't affect this. (`GL_MAX`)
+    Max,
+}
+
+impl BlendEquation {
+    fn gl_equation(self) -> GLenum {
+        match self {
+            BlendEquation::Add => gl::FUNC_ADD,
+            BlendEquation::Subtract => gl::FUNC_SUBTRACT,
+            BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+            BlendEquation::Min => gl::MIN,
+            BlendEquation::Max => gl::MAX,
+        }
+    }
 }
 
 impl Default for DrawCallParameters {
@@ -156,10 +462,12 @@ impl Default for DrawCallParameters {
             image: None,
             shaders: None,
             alpha_blending: true,
+            blend_mode: BlendMode::Alpha,
+            blend_equation: BlendEquation::Add,
             minification_smoothing: true,
             magnification_smoothing: false,
-            verticies:None,
-            triangle:false,
+            anisotropy: 1.0,
+            downscale_large_images: false,
         }
     }
 }
@@ -168,12 +476,18 @@ impl Renderer {
     /// Creates a new Renderer.
     ///
     /// `opengl21` disables some post-OpenGL 2.1 functionality, like
-    /// VAOs, instanced rendering, and indexed rendering. Ideally this
+    /// VAOs and GLSL 3.30 shaders. Ideally this
     /// should be `false` in all cases where the OpenGL version is
     /// \>=3.3 (or OpenGL ES >=3) to allow for more optimized
     /// rendering.
+    ///
+    /// The renderer starts out with one draw call, the
+    /// [`Renderer::white_draw_call`], so the OpenGL functions should
+    /// be loaded (eg. by creating the `Window`) before this. If they
+    /// aren't, the white draw call is only a placeholder, which can't
+    /// be rendered.
     pub fn new(opengl21: bool) -> Renderer {
-        Renderer {
+        let mut renderer = Renderer {
             calls: Vec::with_capacity(2),
             gl_state: OpenGLState {
                 legacy: opengl21,
@@ -181,16 +495,415 @@ impl Renderer {
                 depth_test: false,
                 depth_func: 0,
                 blend: false,
-                blend_func: (0, 0),
+                blend_func: (0, 0, 0, 0),
+                blend_equation: gl::FUNC_ADD as GLint,
                 program: 0,
                 vao: 0,
                 texture: 0,
                 vbo: 0,
-                element_buffer: 0,
+                framebuffer_srgb: false,
+                held: false,
             },
             profiler: Profiler::new(),
+            dpi_factor: 1.0,
+            auto_batch: false,
+            z_interleaving: false,
+            wireframe: false,
+            max_quads_per_batch: None,
+            srgb: false,
+            srgb_supported: framebuffer_srgb_supported(opengl21),
+            depth_range: (-1.0, 1.0),
+            white_call: DrawCallHandle {
+                index: 0,
+                generation: 0,
+            },
+            debug_callback: None,
+            stats: RenderStats::default(),
+            projection: projection_matrix(1.0, 1.0, (-1.0, 1.0)),
+            screen_size: (1.0, 1.0),
+            screen_offset: (0.0, 0.0),
+            viewport: None,
+            dirty_rect: None,
+            clear_color: (1.0, 1.0, 1.0, 1.0),
+            virtual_resolution: None,
+            clip_stack: Vec::new(),
+            masks: Vec::new(),
+            mask_state: MaskState::Inactive,
+            pixel_perfect: false,
+            _not_send: PhantomData,
             preserve_gl_state: true,
+        };
+        renderer.white_call = if gl::Viewport::is_loaded() {
+            match renderer.create_draw_call(DrawCallParameters::default()) {
+                Ok(call_handle) => call_handle,
+                Err(err) => {
+                    log::error!("Could not create the white draw call: {}", err);
+                    renderer.create_dummy_draw_call()
+                }
+            }
+        } else {
+            renderer.create_dummy_draw_call()
+        };
+        renderer
+    }
+
+    /// Sets the DPI factor of the window the renderer is drawing
+    /// into. This should be called with the window's `dpi_factor`
+    /// after every refresh, so that the parts of the crate that
+    /// rasterize things themselves (eg. a
+    /// [`TextRenderer`](../text/struct.TextRenderer.html) with
+    /// automatic DPI scaling) can produce sharp results.
+    pub fn set_dpi_factor(&mut self, dpi_factor: f32) {
+        self.dpi_factor = dpi_factor;
+    }
+
+    /// Toggles automatic batching of draw calls. When enabled,
+    /// [`Renderer::render`] merges draw calls that would be rendered
+    /// one after another, and share the same texture, shaders and
+    /// blending setting, into one upload and draw. The draw calls'
+    /// ordering is unaffected, so this works best when the merged
+    /// calls' quads don't overlap, or when depth testing orders them
+    /// correctly anyway.
+    ///
+    /// Disabled by default, as it changes the order in which the
+    /// quads inside the merged calls are drawn relative to each
+    /// other.
+    pub fn set_auto_batch(&mut self, auto_batch: bool) {
+        self.auto_batch = auto_batch;
+    }
+
+    /// Toggles z-interleaving of alpha-blended draw calls. Normally,
+    /// each blended draw call is drawn as a whole, ordered by its
+    /// backmost quad, so the quads of different calls can't be
+    /// interleaved: eg. text drawn with a
+    /// [`TextRenderer`](../text/struct.TextRenderer.html), which uses
+    /// its own draw calls, ends up either in front of or behind all
+    /// the quads of a sprite draw call, regardless of their `z`.
+    ///
+    /// When enabled, [`Renderer::render`] sorts the triangles of all
+    /// the blended calls together, back to front, and draws them in
+    /// runs of consecutive triangles from the same call. Triangles
+    /// with the same `z` are drawn in submission order. Opaque calls
+    /// are unaffected, as depth testing already orders them.
+    ///
+    /// Disabled by default, as interleaved calls cost one draw per
+    /// run instead of one per call.
+    pub fn set_z_interleaving(&mut self, z_interleaving: bool) {
+        self.z_interleaving = z_interleaving;
+    }
+
+    /// Toggles wireframe rendering, for debugging geometry. When
+    /// enabled, [`Renderer::render`] draws only the edges of the
+    /// triangles, with `glPolygonMode(GL_FRONT_AND_BACK, GL_LINE)`,
+    /// and sets the mode back to `GL_FILL` afterwards.
+    ///
+    /// Only supported with modern OpenGL: OpenGL ES 2.0 doesn't have
+    /// glPolygonMode, so this does nothing when running with legacy
+    /// (2.1 or 2.0 ES) OpenGL.
+    pub fn set_wireframe(&mut self, wireframe: bool) {
+        self.wireframe = wireframe;
+    }
+
+    /// Limits the amount of quads drawn with a single glDrawArrays
+    /// call, for drivers which struggle with very large draws. Draw
+    /// calls with more quads queued are drawn with multiple
+    /// glDrawArrays calls over parts of the same vertex buffer, which
+    /// show up in [`RenderStats::draw_calls`]. `None`, the default,
+    /// removes the limit.
+    ///
+    /// The limit applies to the quads queued into draw calls, not the
+    /// ones in stencil masks or z-interleaved runs (see
+    /// [`Renderer::set_z_interleaving`]). A limit of 0 is treated as
+    /// 1.
+    pub fn set_max_quads_per_batch(&mut self, max_quads: Option<usize>) {
+        self.max_quads_per_batch = max_quads;
+    }
+
+    /// Toggles sRGB-correct rendering. When enabled,
+    /// `GL_FRAMEBUFFER_SRGB` is enabled during [`Renderer::render`],
+    /// and the colors passed to [`Renderer::draw_quad`] and its
+    /// variations are converted from sRGB to linear before they're
+    /// queued, so blending and interpolation between colors happen
+    /// in linear space, and the framebuffer converts the results
+    /// back to sRGB. When disabled (the default),
+    /// `GL_FRAMEBUFFER_SRGB` is disabled during rendering, and colors
+    /// are written and blended as-is, ie. in sRGB space.
+    ///
+    /// Either way, the colors given to the drawing functions are
+    /// interpreted as sRGB, so toggling this doesn't change how solid
+    /// colors look, only how they blend. Note that the window has to
+    /// have an sRGB-capable framebuffer for this to have an effect,
+    /// and textures are not converted, so they are treated as linear
+    /// unless the custom shaders convert them.
+    ///
+    /// With OpenGL 2.1, this requires the `ARB_framebuffer_sRGB`
+    /// extension, and with OpenGL ES 2.0, `EXT_sRGB_write_control`.
+    /// Without them, a warning is logged and sRGB-correct rendering
+    /// stays disabled.
+    pub fn set_srgb(&mut self, enabled: bool) {
+        if enabled && !self.srgb_supported {
+            log::warn!(
+                "sRGB-correct rendering requires ARB_framebuffer_sRGB or EXT_sRGB_write_control, which are not supported, so it stays disabled"
+            );
+            return;
+        }
+        self.srgb = enabled;
+    }
+
+    /// Sets the range of the depth values given to
+    /// [`Renderer::draw_quad`] and its variations. Depths from `near`
+    /// to `far` are mapped linearly onto the whole depth buffer, and
+    /// anything outside the range is not drawn. Smaller values are in
+    /// front. The default range is `-1.0 - 1.0`.
+    ///
+    /// Giving the range in the units you use for your layers (eg.
+    /// `0.0 - 1000.0` for a thousand integer layers) avoids having to
+    /// squeeze them into small fractions.
+    pub fn set_depth_range(&mut self, near: f32, far: f32) {
+        self.depth_range = (near, far);
+    }
+
+    /// Toggles pixel perfect rendering, for crisp pixel art. When
+    /// enabled, the `coords` given to [`Renderer::draw_quad`] and its
+    /// variations (including the rotated ones, before rotation) are
+    /// snapped to whole logical pixels, and the textures of the draw
+    /// calls use nearest-neighbor filtering. The position is rounded
+    /// separately from the size, so moving sprites don't change size
+    /// from frame to frame. Disabling it restores the filtering the
+    /// draw calls were created with. Textures created outside of fae
+    /// (see [`Renderer::create_draw_call_from_texture`]) are not
+    /// affected, and neither are triangles and polygons.
+    ///
+    /// Zooming is done by changing the logical size given to
+    /// [`Renderer::render`] (or the DPI factor): with integer zoom
+    /// levels, one logical pixel covers exactly 2x2, 3x3, etc.
+    /// physical pixels, so every texel ends up the same size. With
+    /// fractional zoom levels, the logical pixels don't line up with
+    /// the physical ones, and some texels end up a physical pixel
+    /// wider than others. To avoid that, render the scene at an
+    /// integer scale into a framebuffer with
+    /// [`Renderer::render_into`], and draw that texture scaled to the
+    /// window with smoothing, which trades the uneven texels for a
+    /// slight blur.
+    pub fn set_pixel_perfect(&mut self, pixel_perfect: bool) {
+        if self.pixel_perfect == pixel_perfect {
+            return;
+        }
+        self.pixel_perfect = pixel_perfect;
+        if !gl::TexParameteri::is_loaded() {
+            return;
+        }
+        self.gl_push();
+        for call in self.calls.iter().filter(|c| !c.deleted && c.owns_texture) {
+            let (min_filter, mag_filter) = if pixel_perfect {
+                (gl::NEAREST as GLint, gl::NEAREST as GLint)
+            } else {
+                call.filters
+            };
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, call.texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min_filter);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag_filter);
+            }
+        }
+        self.gl_pop();
+    }
+
+    /// Returns whether pixel perfect rendering is enabled, see
+    /// [`Renderer::set_pixel_perfect`].
+    pub fn pixel_perfect(&self) -> bool {
+        self.pixel_perfect
+    }
+
+    /// Returns the DPI factor set with [`Renderer::set_dpi_factor`].
+    pub fn dpi_factor(&self) -> f32 {
+        self.dpi_factor
+    }
+
+    /// Returns the projection matrix used in the latest
+    /// [`Renderer::render`], in the same row-major layout as given to
+    /// the shaders.
+    pub fn projection_matrix(&self) -> [f32; 16] {
+        self.projection
+    }
+
+    /// Converts a point in screen space (logical pixels from the
+    /// top-left of the window, like `Window::mouse_coords`) into the
+    /// coordinates used when drawing, by inverting the projection
+    /// matrix of the latest [`Renderer::render`].
+    ///
+    /// Unless a virtual resolution is set (see
+    /// [`Renderer::set_virtual_resolution`]), the drawing coordinates
+    /// are also logical pixels, so this is (nearly) an identity
+    /// function, but it's the correct
+    /// way to hit-test mouse coordinates against drawn sprites if the
+    /// projection ever includes a view transformation, like a camera
+    /// with panning and zooming.
+    pub fn screen_to_world(&self, point: (f32, f32)) -> (f32, f32) {
+        let (x, y) = (
+            point.0 - self.screen_offset.0,
+            point.1 - self.screen_offset.1,
+        );
+        screen_to_world(&self.projection, self.screen_size, (x, y))
+    }
+
+    /// Converts a point in drawing coordinates into screen
+    /// space. The inverse of [`Renderer::screen_to_world`].
+    pub fn world_to_screen(&self, point: (f32, f32)) -> (f32, f32) {
+        let (x, y) = world_to_screen(&self.projection, self.screen_size, point);
+        (x + self.screen_offset.0, y + self.screen_offset.1)
+    }
+
+    /// Reads the color of one pixel of the currently bound
+    /// framebuffer, eg. for an eyedropper tool. Call this after
+    /// [`Renderer::render`], but before swapping the buffers, to read
+    /// what was just rendered.
+    ///
+    /// - `point`: The point to read, in screen space (logical pixels
+    ///   from the top-left of the window, like
+    ///   `Window::mouse_coords`). Converted into framebuffer pixels
+    ///   with the DPI factor, relative to the current OpenGL viewport.
+    ///
+    /// Returns the color as (red, green, blue, alpha), or all zeroes
+    /// if the point is outside the viewport. Much cheaper than reading
+    /// back the whole framebuffer, but glReadPixels still waits for
+    /// the GPU to finish all the queued rendering before returning,
+    /// which stalls the pipeline like [`Renderer::synchronize`].
+    pub fn read_pixel(&self, point: (f32, f32)) -> (u8, u8, u8, u8) {
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+        let [viewport_x, viewport_y, viewport_w, viewport_h] = viewport;
+        let x = (point.0 * self.dpi_factor).floor() as i32;
+        let y = (point.1 * self.dpi_factor).floor() as i32;
+        if x < 0 || y < 0 || x >= viewport_w || y >= viewport_h {
+            return (0, 0, 0, 0);
         }
+        // OpenGL's framebuffer y starts from the bottom
+        let (x, y) = (viewport_x + x, viewport_y + viewport_h - 1 - y);
+        let mut pixel = [0u8; 4];
+        unsafe {
+            gl::ReadPixels(
+                x,
+                y,
+                1,
+                1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixel.as_mut_ptr() as *mut _,
+            );
+        }
+        print_gl_errors("after reading a pixel");
+        let [r, g, b, a] = pixel;
+        (r, g, b, a)
+    }
+
+    /// Confines the following [`Renderer::render`]s into a rectangle
+    /// of the window, eg. for split-screen views or a minimap. `None`
+    /// resets rendering to the whole window.
+    ///
+    /// - `viewport`: The rectangle, in physical (framebuffer) pixels,
+    ///   from the top-left corner of the window. Arrangement: (x, y,
+    ///   width, height)
+    ///
+    /// The projection is adjusted so that the logical coordinates
+    /// (0, 0) are at the top-left corner of the viewport, and one
+    /// logical pixel still covers `dpi_factor` physical pixels. Only
+    /// the viewport is cleared at the start of the render, so
+    /// multiple viewports can be rendered into the same frame. Clip
+    /// rects (see [`Renderer::push_clip_rect`]) are in the viewport's
+    /// coordinates as well, and everything outside the viewport is
+    /// clipped by OpenGL anyway.
+    ///
+    /// The window's viewport is restored after the render.
+    pub fn set_viewport(&mut self, viewport: Option<(i32, i32, i32, i32)>) {
+        self.viewport = viewport;
+    }
+
+    /// Returns the viewport set with [`Renderer::set_viewport`].
+    pub fn viewport(&self) -> Option<(i32, i32, i32, i32)> {
+        self.viewport
+    }
+
+    /// Sets the color the screen (or the viewport, see
+    /// [`Renderer::set_viewport`]) is cleared with at the start of
+    /// each [`Renderer::render`]. The default is opaque white.
+    ///
+    /// For transparent windows (see `WindowSettings::transparent`),
+    /// use an alpha below 1. The color should be premultiplied by the
+    /// alpha, eg. `(0.0, 0.0, 0.5, 0.5)` for half-transparent blue,
+    /// since that's what compositors expect, and what the alpha
+    /// blending produces.
+    pub fn set_clear_color(&mut self, color: (f32, f32, f32, f32)) {
+        self.clear_color = color;
+    }
+
+    /// Returns the color set with [`Renderer::set_clear_color`].
+    pub fn clear_color(&self) -> (f32, f32, f32, f32) {
+        self.clear_color
+    }
+
+    /// Limits the following [`Renderer::render`]s to a "dirty"
+    /// rectangle, for applications which only change a small part of
+    /// the screen per frame. `None` resets rendering to the whole
+    /// screen.
+    ///
+    /// - `rect`: The rectangle which has changed since the last
+    ///   frame, in the same coordinates as the drawing functions.
+    ///   Arrangement: (left, top, right, bottom)
+    ///
+    /// Only the dirty rectangle is cleared, all drawing is scissored
+    /// into it, and draw calls whose quads don't overlap it are
+    /// skipped entirely. The quads still need to be drawn every
+    /// frame as usual, so that the ones overlapping the rectangle
+    /// can be redrawn.
+    ///
+    /// **Note:** the rest of the screen is left as it was, so this
+    /// only works if the framebuffer's contents are preserved between
+    /// frames, ie. when rendering into a single-buffered context or
+    /// your own framebuffer (see [`Renderer::render_into`]), or when
+    /// the platform copies the back buffer on swap. With regular
+    /// double-buffering, the back buffer contains an older frame, or
+    /// undefined contents. Render the whole screen at least once
+    /// before using dirty rectangles.
+    pub fn set_dirty_rect(&mut self, rect: Option<(f32, f32, f32, f32)>) {
+        self.dirty_rect = rect;
+    }
+
+    /// Returns the rectangle set with [`Renderer::set_dirty_rect`].
+    pub fn dirty_rect(&self) -> Option<(f32, f32, f32, f32)> {
+        self.dirty_rect
+    }
+
+    /// Makes the following [`Renderer::render`]s draw at a fixed
+    /// virtual resolution, eg. 320x180 for a pixel-art game, instead
+    /// of the window's logical size. `None` resets rendering to the
+    /// window's size.
+    ///
+    /// - `resolution`: The size of the drawing area, in the units
+    ///   given to the drawing functions. Arrangement: (width, height)
+    ///
+    /// The virtual area is scaled up by the largest integer factor
+    /// (in physical pixels) that fits in the window, or the viewport
+    /// if one is set with [`Renderer::set_viewport`], and centered,
+    /// with black bars filling the rest. If the window is smaller
+    /// than the virtual resolution, the area is scaled down to fit
+    /// instead. Combined with [`Renderer::set_pixel_perfect`], every
+    /// texel ends up the same size on the screen.
+    ///
+    /// [`Renderer::screen_to_world`] maps window coordinates (eg.
+    /// `Window::mouse_coords`) into the virtual space, so they can be
+    /// used for hit-testing as usual. Points on the black bars map
+    /// outside of the virtual area.
+    pub fn set_virtual_resolution(&mut self, resolution: Option<(u32, u32)>) {
+        self.virtual_resolution = resolution;
+    }
+
+    /// Returns the resolution set with
+    /// [`Renderer::set_virtual_resolution`].
+    pub fn virtual_resolution(&self) -> Option<(u32, u32)> {
+        self.virtual_resolution
     }
 
     /// Toggles whether profiling is enabled.
@@ -217,55 +930,258 @@ impl Renderer {
     /// with the `shaders` parameter. Use `None` for defaults. Make
     /// sure to study the uniform variables and attributes of the
     /// default shaders before making your own.
-    pub fn create_draw_call(&mut self, params: DrawCallParameters) -> DrawCallHandle {
+    ///
+    /// Results in a [`FaeError::ShaderCompile`](../enum.FaeError.html)
+    /// if the shaders fail to compile or link, and a
+    /// [`FaeError::TextureTooLarge`](../enum.FaeError.html) if the
+    /// image is too large for the driver, unless
+    /// `downscale_large_images` is set. Results in a
+    /// [`FaeError::Unsupported`](../enum.FaeError.html) if the
+    /// `blend_equation` is `Min` or `Max` on OpenGL ES 2.0 without
+    /// the `GL_EXT_blend_minmax` extension. Results in a
+    /// [`FaeError::ContextCreation`](../enum.FaeError.html) if the
+    /// OpenGL functions haven't been loaded yet, eg. because the
+    /// window hasn't been created.
+    pub fn create_draw_call(
+        &mut self,
+        params: DrawCallParameters,
+    ) -> Result<DrawCallHandle, FaeError> {
+        check_gl_loaded()?;
+        let mut image = params
+            .image
+            .unwrap_or_else(|| Image::from_color(1, 1, &[0xFF, 0xFF, 0xFF, 0xFF]));
+        let max_size = self.max_texture_size();
+        if image.width > max_size || image.height > max_size {
+            if !params.downscale_large_images {
+                return Err(FaeError::TextureTooLarge(format!(
+                    "the image is {}x{}, but the maximum texture size is {}x{}",
+                    image.width, image.height, max_size, max_size
+                )));
+            }
+            let scale = max_size as f32 / image.width.max(image.height) as f32;
+            let width = ((image.width as f32 * scale) as u32)
+                .max(1)
+                .min(max_size as u32);
+            let height = ((image.height as f32 * scale) as u32)
+                .max(1)
+                .min(max_size as u32);
+            image = image.resize(width, height, ResizeFilter::Bilinear);
+        }
+        let min_max = matches!(
+            params.blend_equation,
+            BlendEquation::Min | BlendEquation::Max
+        );
+        if min_max && !blend_minmax_supported(self.gl_state.legacy) {
+            return Err(FaeError::Unsupported(format!(
+                "the {:?} blend equation requires GL_EXT_blend_minmax",
+                params.blend_equation
+            )));
+        }
+
         self.gl_push();
 
         let shaders = params.shaders.unwrap_or(DEFAULT_QUAD_SHADERS);
-        let (vert, frag) = if self.gl_state.legacy {
-            (shaders.vertex_shader_110, shaders.fragment_shader_110)
-        } else {
-            (shaders.vertex_shader_330, shaders.fragment_shader_330)
-        };
-        let index = self.calls.len();
-
-        let program = create_program(&vert, &frag, self.gl_state.legacy);
-        let attributes = if !params.triangle {
-            create_attributes(self.gl_state.legacy, program)
-        } else {
-            create_triangle_attributes(self.gl_state.legacy,program,params.verticies.unwrap())
+        let program = match self.create_program(&shaders) {
+            Ok(program) => program,
+            Err(err) => {
+                self.gl_pop();
+                return Err(err);
+            }
         };
+        let attributes = create_attributes(self.gl_state.legacy, program);
         let filter = |smoothed| if smoothed { gl::LINEAR } else { gl::NEAREST } as i32;
-        let texture = create_texture(
+        let filters = (
             filter(params.minification_smoothing),
             filter(params.magnification_smoothing),
         );
-        self.calls.push(DrawCall {
+        let texture = if self.pixel_perfect {
+            create_texture(gl::NEAREST as GLint, gl::NEAREST as GLint)
+        } else {
+            create_texture(filters.0, filters.1)
+        };
+        if params.anisotropy > 1.0 {
+            set_anisotropy(self.gl_state.legacy, params.anisotropy);
+        }
+        let index = self.insert_call(DrawCall {
             texture,
+            texture_size: (image.width, image.height),
+            owns_texture: true,
             program,
             attributes,
             blend: params.alpha_blending,
-            lowest_depth: 1.0,
+            blend_mode: params.blend_mode,
+            blend_equation: params.blend_equation,
+            lowest_depth: f32::INFINITY,
+            is_static: false,
+            static_dirty: false,
+            deleted: false,
+            extra_textures: [0; MAX_EXTRA_TEXTURES as usize],
+            polygon_offset: None,
+            filters,
+            generation: 0,
         });
+        insert_texture(
+            self.calls[index].texture,
+            image.format,
+            image.width,
+            image.height,
+            &image.pixels,
+        );
 
-        if let Some(image) = params.image {
-            insert_texture(
-                self.calls[index].texture,
-                image.format,
-                image.width,
-                image.height,
-                &image.pixels,
-            );
+        self.gl_pop();
+        Ok(self.handle(index))
+    }
+
+    /// Creates a new draw call which draws with a texture created
+    /// outside of fae, eg. video frames uploaded by your own OpenGL
+    /// code, or a texture from another OpenGL library. The draw call
+    /// uses the default shaders and alpha blending.
+    ///
+    /// - `texture`: The OpenGL texture handle, of a `GL_TEXTURE_2D`.
+    ///
+    /// - `width`, `height`: The size of the texture, in pixels.
+    ///
+    /// The renderer does not take ownership of the texture: it's not
+    /// deleted when the draw call is deleted or the renderer is
+    /// dropped, so you're responsible for deleting it. The texture
+    /// must stay alive (and not be deleted by you) as long as the
+    /// draw call is used for drawing. Updating the texture's
+    /// contents in between renders with your own OpenGL calls is
+    /// fine, but the functions that take pixel coordinates (eg.
+    /// [`Renderer::draw_sprite_rotated_atlas`]) will keep using the
+    /// size given here. Note that [`Renderer::replace_draw_call_image`]
+    /// uploads into the given texture.
+    ///
+    /// Results in a [`FaeError::ShaderCompile`](../enum.FaeError.html)
+    /// if the default shaders fail to compile or link, and a
+    /// [`FaeError::ContextCreation`](../enum.FaeError.html) if the
+    /// OpenGL functions haven't been loaded yet.
+    pub fn create_draw_call_from_texture(
+        &mut self,
+        texture: GLuint,
+        width: u32,
+        height: u32,
+    ) -> Result<DrawCallHandle, FaeError> {
+        check_gl_loaded()?;
+        self.gl_push();
+        let program = match self.create_program(&DEFAULT_QUAD_SHADERS) {
+            Ok(program) => program,
+            Err(err) => {
+                self.gl_pop();
+                return Err(err);
+            }
+        };
+        let attributes = create_attributes(self.gl_state.legacy, program);
+        let params = DrawCallParameters::default();
+        let index = self.insert_call(DrawCall {
+            texture,
+            texture_size: (width as i32, height as i32),
+            owns_texture: false,
+            program,
+            attributes,
+            blend: params.alpha_blending,
+            blend_mode: params.blend_mode,
+            blend_equation: params.blend_equation,
+            lowest_depth: f32::INFINITY,
+            is_static: false,
+            static_dirty: false,
+            deleted: false,
+            extra_textures: [0; MAX_EXTRA_TEXTURES as usize],
+            polygon_offset: None,
+            filters: (gl::LINEAR as GLint, gl::NEAREST as GLint),
+            generation: 0,
+        });
+        self.gl_pop();
+        Ok(self.handle(index))
+    }
+
+    /// Creates a new draw call which draws with the texture of
+    /// `texture_call`, but with its own shaders, blending and vertex
+    /// buffer, as set in `params`. Useful for drawing from the same
+    /// texture in several independently ordered batches, eg. the
+    /// text draw calls of
+    /// [`TextRenderer::create_text_draw_call`](../text/struct.TextRenderer.html#method.create_text_draw_call).
+    /// `params.image` and the texture filtering settings are ignored,
+    /// as the texture is shared.
+    ///
+    /// The texture stays owned by `texture_call`, so it's deleted
+    /// along with it, after which the new draw call shouldn't be
+    /// used for drawing anymore. Images replaced with
+    /// [`Renderer::replace_draw_call_image`] on either call show up
+    /// in both.
+    ///
+    /// Results in the same errors as [`Renderer::create_draw_call`],
+    /// except for [`FaeError::TextureTooLarge`](../enum.FaeError.html).
+    pub fn create_draw_call_sharing_texture(
+        &mut self,
+        params: DrawCallParameters,
+        texture_call: &DrawCallHandle,
+    ) -> Result<DrawCallHandle, FaeError> {
+        check_gl_loaded()?;
+        let min_max = matches!(
+            params.blend_equation,
+            BlendEquation::Min | BlendEquation::Max
+        );
+        if min_max && !blend_minmax_supported(self.gl_state.legacy) {
+            return Err(FaeError::Unsupported(format!(
+                "the {:?} blend equation requires GL_EXT_blend_minmax",
+                params.blend_equation
+            )));
         }
+        let (texture, texture_size, filters) = {
+            let source = &self.calls[self.call_index(texture_call)];
+            (source.texture, source.texture_size, source.filters)
+        };
 
+        self.gl_push();
+        let shaders = params.shaders.unwrap_or(DEFAULT_QUAD_SHADERS);
+        let program = match self.create_program(&shaders) {
+            Ok(program) => program,
+            Err(err) => {
+                self.gl_pop();
+                return Err(err);
+            }
+        };
+        let attributes = create_attributes(self.gl_state.legacy, program);
+        let index = self.insert_call(DrawCall {
+            texture,
+            texture_size,
+            owns_texture: false,
+            program,
+            attributes,
+            blend: params.alpha_blending,
+            blend_mode: params.blend_mode,
+            blend_equation: params.blend_equation,
+            lowest_depth: f32::INFINITY,
+            is_static: false,
+            static_dirty: false,
+            deleted: false,
+            extra_textures: [0; MAX_EXTRA_TEXTURES as usize],
+            polygon_offset: None,
+            filters,
+            generation: 0,
+        });
         self.gl_pop();
-        DrawCallHandle(index)
+        Ok(self.handle(index))
     }
 
-    #[allow(dead_code)]
+    /// Compiles and links the shaders for the current OpenGL version.
+    fn create_program(&self, shaders: &Shaders) -> Result<ShaderProgram, FaeError> {
+        let (vert, frag) = if self.gl_state.legacy {
+            (shaders.vertex_shader_110, shaders.fragment_shader_110)
+        } else {
+            (shaders.vertex_shader_330, shaders.fragment_shader_330)
+        };
+        create_program(vert, frag)
+    }
+
+    /// Creates a draw call without any OpenGL objects, which can be
+    /// queued into, but not rendered.
     pub(crate) fn create_dummy_draw_call(&mut self) -> DrawCallHandle {
-        let index = self.calls.len();
-        self.calls.push(DrawCall {
+        let index = self.insert_call(DrawCall {
             texture: 0,
+            texture_size: (1, 1),
+            owns_texture: true,
             program: ShaderProgram {
                 program: 0,
                 vertex_shader: 0,
@@ -275,22 +1191,166 @@ impl Renderer {
                 texcoord_attrib_location: 0,
                 color_attrib_location: 0,
                 rotation_attrib_location: 0,
-                depth_attrib_location: 0,
-                shared_position_attrib_location: 0,
-                shared_texcoord_attrib_location: 0,
             },
             attributes: Attributes {
                 vbo: 0,
-                vbo_static: 0,
-                element_buffer: 0,
                 vao: 0,
                 vbo_data: Vec::new(),
                 allocated_vbo_data_size: 0,
             },
             blend: false,
-            lowest_depth: 1.0,
+            blend_mode: BlendMode::Alpha,
+            blend_equation: BlendEquation::Add,
+            lowest_depth: f32::INFINITY,
+            is_static: false,
+            static_dirty: false,
+            deleted: false,
+            extra_textures: [0; MAX_EXTRA_TEXTURES as usize],
+            polygon_offset: None,
+            filters: (gl::NEAREST as GLint, gl::NEAREST as GLint),
+            generation: 0,
+        });
+        self.handle(index)
+    }
+
+    /// Returns the renderer's built-in draw call with a 1x1 white
+    /// texture and alpha blending, for drawing solid colors (lines,
+    /// rectangles, debug overlays) without creating a draw call for
+    /// them. It's created in [`Renderer::new`], and can't be deleted.
+    /// The primitive drawing functions, like
+    /// [`Renderer::draw_polygon`], draw into it when they're given
+    /// `None` as the draw call.
+    pub fn white_draw_call(&self) -> &DrawCallHandle {
+        &self.white_call
+    }
+
+    /// Routes the debug messages of the OpenGL driver to `callback`,
+    /// if the driver supports debug output (`GL_KHR_debug`, or
+    /// OpenGL 4.3). Messages less severe than `min_severity` are
+    /// ignored. The callback is called synchronously, during the
+    /// OpenGL call that caused the message. `None` disables debug
+    /// output.
+    ///
+    /// Returns an error if debug output isn't supported, in which
+    /// case errors are still checked with `glGetError` after most
+    /// OpenGL calls, like they are by default. Debug output usually
+    /// requires a debug context, depending on the driver.
+    pub fn set_debug_callback(
+        &mut self,
+        callback: Option<DebugMessageHandler>,
+        min_severity: DebugSeverity,
+    ) -> Result<(), FaeError> {
+        if !gl::DebugMessageCallback::is_loaded()
+            || !extension_supported(self.gl_state.legacy, "GL_KHR_debug")
+        {
+            return Err(FaeError::Unsupported(
+                "OpenGL debug output is not supported by the driver".to_owned(),
+            ));
+        }
+        self.debug_callback = callback.map(|callback| {
+            Box::new(DebugCallback {
+                min_severity,
+                callback,
+            })
         });
-        DrawCallHandle(index)
+        unsafe {
+            if let Some(callback) = &mut self.debug_callback {
+                let user_param = &mut **callback as *mut DebugCallback;
+                gl::DebugMessageCallback(debug_message_callback, user_param as *const _);
+                gl::Enable(gl::DEBUG_OUTPUT);
+                gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            } else {
+                gl::DebugMessageCallback(debug_message_callback, std::ptr::null());
+                gl::Disable(gl::DEBUG_OUTPUT);
+            }
+        }
+        print_gl_errors("after setting the debug callback");
+        Ok(())
+    }
+
+    /// Puts the call into the first deleted slot, or the end of the
+    /// list if there are none, and returns its index.
+    fn insert_call(&mut self, mut call: DrawCall) -> usize {
+        if let Some(index) = self.calls.iter().position(|call| call.deleted) {
+            call.generation = self.calls[index].generation;
+            self.calls[index] = call;
+            index
+        } else {
+            self.calls.push(call);
+            self.calls.len() - 1
+        }
+    }
+
+    fn handle(&self, index: usize) -> DrawCallHandle {
+        DrawCallHandle {
+            index,
+            generation: self.calls[index].generation,
+        }
+    }
+
+    /// Returns the index of the draw call, panicking if the handle
+    /// refers to a deleted draw call.
+    fn call_index(&self, call_handle: &DrawCallHandle) -> usize {
+        let call = match self.calls.get(call_handle.index) {
+            Some(call) => call,
+            None => panic!(
+                "the draw call handle {:?} is out of range: this renderer only has draw calls 0..{} (was the handle created by another renderer?)",
+                call_handle,
+                self.calls.len()
+            ),
+        };
+        if call.deleted || call.generation != call_handle.generation {
+            panic!(
+                "the draw call handle {:?} refers to a deleted draw call",
+                call_handle
+            );
+        }
+        call_handle.index
+    }
+
+    /// Deletes the draw call, releasing its texture, vertex buffer
+    /// and shader program. Any quads queued into it are discarded.
+    ///
+    /// The draw call's slot is reused by the next
+    /// [`Renderer::create_draw_call`]. The handles of the other draw
+    /// calls stay valid, but using `call_handle` (or its copies) after
+    /// this causes a panic. Deleting the [`Renderer::white_draw_call`]
+    /// panics as well.
+    pub fn delete_draw_call(&mut self, call_handle: DrawCallHandle) {
+        assert!(
+            call_handle != self.white_call,
+            "the white draw call is built into the renderer, and can't be deleted"
+        );
+        let index = self.call_index(&call_handle);
+        let call = &mut self.calls[index];
+        if gl::Viewport::is_loaded() {
+            delete_gl_resources(call, self.gl_state.legacy);
+        }
+        call.attributes.vbo_data = Vec::new();
+        call.attributes.allocated_vbo_data_size = 0;
+        call.lowest_depth = f32::INFINITY;
+        call.is_static = false;
+        call.static_dirty = false;
+        call.deleted = true;
+        call.generation = call.generation.wrapping_add(1);
+    }
+
+    /// Replaces the texture of the draw call with `image`, keeping
+    /// the rest of the draw call (shaders, blending, queued quads)
+    /// as-is. The new image can be of a different size than the old
+    /// one.
+    pub fn replace_draw_call_image(&mut self, call_handle: &DrawCallHandle, image: &Image) {
+        let index = self.call_index(call_handle);
+        self.gl_push();
+        insert_texture(
+            self.calls[index].texture,
+            image.format,
+            image.width,
+            image.height,
+            &image.pixels,
+        );
+        self.calls[index].texture_size = (image.width, image.height);
+        self.gl_pop();
     }
 
     /// Draws a rectangle with a ninepatch texture on the screen. This
@@ -303,11 +1363,12 @@ impl Renderer {
     /// on both axis.
     ///
     /// - `ninepatch_dimensions`: Contains the widths and the heights
-    /// of the tiles. Arrangement: ((left tile width, middle, right),
-    /// (top tile height, middle, bottom))
+    ///   of the tiles. Arrangement: ((left tile width, middle, right),
+    ///   (top tile height, middle, bottom))
     ///
     /// See [`Renderer::draw_quad`] for the rest of the parameters'
     /// docs.
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_quad_ninepatch(
         &mut self,
         ninepatch_dimensions: ((f32, f32, f32), (f32, f32, f32)),
@@ -358,7 +1419,7 @@ impl Renderer {
     /// inside `clip_area`.
     ///
     /// - `clip_area`: The coordinates of the corners of the clipping
-    /// area, in (logical) pixels. Arrangement: (left, top, right, bottom)
+    ///   area, in (logical) pixels. Arrangement: (left, top, right, bottom)
     ///
     /// See [`Renderer::draw_quad`] for the rest of the parameters'
     /// docs.
@@ -395,6 +1456,10 @@ impl Renderer {
             ty1.min(ty1 + th * (y1 - oy1) / oh),
         );
 
+        // Keep the pivot in the same place relative to the original quad
+        let (rads, pivot_x, pivot_y) = rotation;
+        let rotation = (rads, pivot_x + ox0 - x0, pivot_y + oy0 - y0);
+
         self.draw_quad((x0, y0, x1, y1), texcoords, color, rotation, z, call_handle);
     }
 
@@ -421,81 +1486,281 @@ impl Renderer {
         );
     }
 
-    /// Draws a textured rectangle on the screen.
-    ///
-    /// - `coords`: The coordinates of the corners of the quad, in
-    /// (logical) pixels. Arrangement: (left, top, right, bottom)
-    ///
-    /// - `texcoords`: The texture coordinates (UVs) of the quad, in
-    /// the range `0.0 - 1.0`. The shaders will not use the texture at
-    /// all if the texcoords are all `-1.0`. Same arrangement as
-    /// `coords`.
-    ///
-    /// - `color`: The color tint of the quad, in the range
-    /// `0-255`. Arrangement: (red, green, blue, alpha)
-    ///
-    /// - `rotation`: The rotation of the quad, in radians, and the
-    /// point (relative to `coords` x and y, in logical pixels as well)
-    /// around which the sprite pivots. Arrangement: (radians, x, y)
-    ///
-    /// - `depth`: Used for ordering sprites on screen, in the range
-    /// `-1.0 - 1.0`. Negative values are in front.
-    ///
-    /// - `call_handle`: The index of the draw call to draw the quad
-    /// in. This is the returned value from [`Renderer::create_draw_call`].
-    #[inline]
-    pub fn draw_quad(
+    /// Draws a textured rectangle rotated `radians` around its
+    /// center. Like [`Renderer::draw_quad`], without having to
+    /// calculate the pivot.
+    pub fn draw_rotated_quad(
         &mut self,
         coords: (f32, f32, f32, f32),
         texcoords: (f32, f32, f32, f32),
         color: (f32, f32, f32, f32),
-        rotation: (f32, f32, f32),
-        depth: f32,
+        radians: f32,
+        z: f32,
         call_handle: &DrawCallHandle,
     ) {
-        let (x0, y0, x1, y1) = coords;
-        let (tx0, ty0, tx1, ty1) = texcoords;
-        let (red, green, blue, alpha) = color;
-        let (rads, pivot_x, pivot_y) = rotation;
+        let (sin, cos) = radians.sin_cos();
+        let matrix = [cos, -sin, sin, cos];
+        self.draw_quad_transform(coords, texcoords, color, z, call_handle, matrix);
+    }
 
-        self.calls[call_handle.0].lowest_depth = self.calls[call_handle.0].lowest_depth.min(depth);
-        if self.gl_state.legacy {
-            let (pivot_x, pivot_y) = (pivot_x + x0, pivot_y + y0);
-
-            // 6 vertices, each of which consist of: position (x, y,
-            // z), texcoord (x, y), colors (r, g, b, a), rotation
-            // rads, rotation pivot (x, y)
-            //
-            // I apologize for this mess.
-            let quad = [
-                x0, y0, depth, tx0, ty0, red, green, blue, alpha, rads, pivot_x, pivot_y, x1, y0,
-                depth, tx1, ty0, red, green, blue, alpha, rads, pivot_x, pivot_y, x1, y1, depth,
-                tx1, ty1, red, green, blue, alpha, rads, pivot_x, pivot_y, x0, y0, depth, tx0, ty0,
-                red, green, blue, alpha, rads, pivot_x, pivot_y, x1, y1, depth, tx1, ty1, red,
-                green, blue, alpha, rads, pivot_x, pivot_y, x0, y1, depth, tx0, ty1, red, green,
-                blue, alpha, rads, pivot_x, pivot_y,
-            ];
+    /// Draws a textured rectangle rotated `radians` around `pivot`,
+    /// which is in absolute (logical) pixels, so it can be outside of
+    /// the quad, eg. the center of a clock for its hands.
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rotated_quad_pivot(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        texcoords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        radians: f32,
+        pivot: (f32, f32),
+        z: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let (x0, y0, _, _) = coords;
+        let rotation = (radians, pivot.0 - x0, pivot.1 - y0);
+        self.draw_quad(coords, texcoords, color, rotation, z, call_handle);
+    }
 
-            self.calls[call_handle.0]
-                .attributes
-                .vbo_data
-                .extend_from_slice(&quad);
-        } else {
-            let (width, height, tw, th) = (x1 - x0, y1 - y0, tx1 - tx0, ty1 - ty0);
-            let quad = [
-                x0, y0, width, height, tx0, ty0, tw, th, red, green, blue, alpha, rads, pivot_x,
-                pivot_y, depth,
-            ];
-            self.calls[call_handle.0]
-                .attributes
-                .vbo_data
-                .extend_from_slice(&quad);
+    /// Draws a textured rectangle scaled by `scale` (x and y) and
+    /// then rotated `radians`, both around its center. Useful for
+    /// zooming animations, as `coords` can stay the same.
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_scaled_quad(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        texcoords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        radians: f32,
+        scale: (f32, f32),
+        z: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let (sin, cos) = radians.sin_cos();
+        let (sx, sy) = scale;
+        let matrix = [cos * sx, -sin * sy, sin * sx, cos * sy];
+        self.draw_quad_transform(coords, texcoords, color, z, call_handle, matrix);
+    }
+
+    /// Makes the draw call static, and replaces its quads with
+    /// `quads`. Static draw calls keep their quads between frames,
+    /// and only upload them to the GPU (with `GL_STATIC_DRAW`) when
+    /// they have changed, so they're a good fit for things like UI
+    /// layers that rarely change.
+    ///
+    /// Calling this again replaces the quads, and quads drawn into a
+    /// static draw call with [`Renderer::draw_quad`] and its
+    /// variations are added to the existing ones. Both mark the draw
+    /// call as dirty, causing a re-upload in the next
+    /// [`Renderer::render`]. [`Renderer::flush`] does not clear
+    /// static draw calls, use [`Renderer::clear_static`] for that.
+    pub fn upload_static(&mut self, call_handle: &DrawCallHandle, quads: &[QuadDesc]) {
+        let index = self.call_index(call_handle);
+        self.calls[index].attributes.vbo_data.clear();
+        self.calls[index].lowest_depth = f32::INFINITY;
+        self.draw_quads(quads, call_handle);
+        let call = &mut self.calls[index];
+        call.is_static = true;
+        call.static_dirty = true;
+    }
+
+    /// Clears the quads of a draw call made static with
+    /// [`Renderer::upload_static`], and turns it back into a regular
+    /// draw call, which is cleared every frame.
+    pub fn clear_static(&mut self, call_handle: &DrawCallHandle) {
+        let index = self.call_index(call_handle);
+        let call = &mut self.calls[index];
+        call.attributes.vbo_data.clear();
+        call.lowest_depth = f32::INFINITY;
+        call.is_static = false;
+        call.static_dirty = false;
+    }
+
+    /// Draws the whole texture of the draw call on the screen, with
+    /// an opaque white tint and no rotation. This is a debugging aid
+    /// for inspecting textures which are generated at runtime, like
+    /// the glyph cache of a
+    /// [`TextRenderer`](../text/struct.TextRenderer.html) (see
+    /// `TextRenderer::glyph_cache_draw_call`).
+    ///
+    /// See [`Renderer::draw_quad`] for the parameters' docs.
+    pub fn draw_debug_texture(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        z: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        self.draw_quad(
+            coords,
+            (0.0, 0.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0, 1.0),
+            (0.0, 0.0, 0.0),
+            z,
+            call_handle,
+        );
+    }
+
+    /// Draws a tinted rectangle with a soft shadow behind it.
+    ///
+    /// The shadow is approximated by layering progressively larger,
+    /// more transparent copies of the rectangle behind it, so the
+    /// draw call should have `alpha_blending` enabled. With an
+    /// `offset` of (0, 0), the shadow works as a soft outline.
+    ///
+    /// - `shadow_color`: The color of the shadow where it is most
+    ///   opaque. Same arrangement as `color`.
+    ///
+    /// - `offset`: How far the shadow is moved from the rectangle, in
+    ///   (logical) pixels. Arrangement: (x, y)
+    ///
+    /// - `spread`: How far the shadow fades out past the edges of the
+    ///   (offset) rectangle, in (logical) pixels. With a spread of 0,
+    ///   the shadow has hard edges.
+    ///
+    /// - `call_handle`: The draw call to draw the rectangle and its
+    ///   shadow in, or `None` for the [`Renderer::white_draw_call`].
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_quad_with_shadow(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        shadow_color: (f32, f32, f32, f32),
+        offset: (f32, f32),
+        spread: f32,
+        depth: f32,
+        call_handle: Option<&DrawCallHandle>,
+    ) {
+        let call_handle = &call_handle.copied().unwrap_or(self.white_call);
+        let (x0, y0, x1, y1) = coords;
+        let (x0, y0, x1, y1) = (x0 + offset.0, y0 + offset.1, x1 + offset.0, y1 + offset.1);
+        let layers = if spread > 0.0 { SHADOW_LAYERS } else { 1 };
+        let (red, green, blue, alpha) = shadow_color;
+        let layer_color = (red, green, blue, alpha / layers as f32);
+        // From the outermost layer to the innermost, so the layers
+        // stack up towards the middle
+        for i in 0..layers {
+            let grow = spread * (layers - i) as f32 / layers as f32;
+            self.draw_quad_tinted(
+                (x0 - grow, y0 - grow, x1 + grow, y1 + grow),
+                layer_color,
+                (0.0, 0.0, 0.0),
+                depth,
+                call_handle,
+            );
         }
+        self.draw_quad_tinted(coords, color, (0.0, 0.0, 0.0), depth, call_handle);
     }
 
-    /// Does the same as [`Renderer::draw_quad()`] except draws a triangle.
-    // This documentation is just so RLS doesn't warn me about the lack of docs.
-    pub fn draw_triangle(
+    /// Reserves space for at least `quad_count` more quads in the
+    /// draw call, to avoid reallocating while drawing them. The
+    /// space is never given back: the draw calls are cleared every
+    /// frame without shrinking, so after a busy frame, the following
+    /// frames can draw as many quads without reallocations.
+    pub fn reserve(&mut self, call_handle: &DrawCallHandle, quad_count: usize) {
+        let index = self.call_index(call_handle);
+        self.calls[index]
+            .attributes
+            .vbo_data
+            .reserve(quad_count * FLOATS_PER_QUAD);
+    }
+
+    /// Draws all of the quads, like calling [`Renderer::draw_quad`]
+    /// for each of them, but reserves the space for all of them at
+    /// once.
+    pub fn draw_quads(&mut self, quads: &[QuadDesc], call_handle: &DrawCallHandle) {
+        self.reserve(call_handle, quads.len());
+        for quad in quads {
+            self.draw_quad(
+                quad.coords,
+                quad.texcoords,
+                quad.color,
+                quad.rotation,
+                quad.depth,
+                call_handle,
+            );
+        }
+    }
+
+    /// Draws the commands recorded into the command buffer, in the
+    /// order they were recorded in, like calling the corresponding
+    /// `Renderer` functions directly. The buffer is left as-is, so
+    /// it can be executed again next frame. Text commands are
+    /// skipped, see
+    /// [`TextRenderer::execute`](../text/struct.TextRenderer.html#method.execute).
+    pub fn execute(&mut self, commands: &CommandBuffer) {
+        for command in commands.commands() {
+            match command {
+                DrawCommand::Quad { quad, call } => self.draw_quad(
+                    quad.coords,
+                    quad.texcoords,
+                    quad.color,
+                    quad.rotation,
+                    quad.depth,
+                    call,
+                ),
+                DrawCommand::Triangle {
+                    vertices,
+                    texcoords,
+                    colors,
+                    depth,
+                    call,
+                } => self.draw_triangle(*vertices, *texcoords, *colors, *depth, call),
+                DrawCommand::PushClipRect(rect) => self.push_clip_rect(*rect),
+                DrawCommand::PopClipRect => {
+                    self.pop_clip_rect();
+                }
+                #[cfg(feature = "text")]
+                DrawCommand::Text { .. } => {}
+            }
+        }
+    }
+
+    /// Draws the commands like [`Renderer::execute`], and returns the
+    /// buffer, emptied, so its allocation can be reused for
+    /// recording the next frame, eg. by sending it back to the thread
+    /// that built it.
+    pub fn replay(&mut self, mut commands: CommandBuffer) -> CommandBuffer {
+        self.execute(&commands);
+        commands.clear();
+        commands
+    }
+
+    /// Draws a textured rectangle on the screen.
+    ///
+    /// - `coords`: The coordinates of the corners of the quad, in
+    ///   (logical) pixels. Arrangement: (left, top, right, bottom)
+    ///
+    /// - `texcoords`: The texture coordinates (UVs) of the quad, in
+    ///   the range `0.0 - 1.0`. The shaders will not use the texture at
+    ///   all if the texcoords are all `-1.0`. Same arrangement as
+    ///   `coords`.
+    ///
+    /// - `color`: The color tint of the quad, in the range
+    ///   `0.0 - 1.0`, in the sRGB color space (see
+    ///   [`Renderer::set_srgb`]). Arrangement: (red, green, blue,
+    ///   alpha)
+    ///
+    /// - `rotation`: The rotation of the quad, in radians, and the
+    ///   point (relative to `coords` x and y, in logical pixels as well)
+    ///   around which the sprite pivots. Arrangement: (radians, x, y)
+    ///
+    /// - `depth`: Used for ordering sprites on screen, in the range
+    ///   `-1.0 - 1.0` by default (see
+    ///   [`Renderer::set_depth_range`]). Smaller values are in front.
+    ///
+    /// - `call_handle`: The index of the draw call to draw the quad
+    ///   in. This is the returned value from [`Renderer::create_draw_call`].
+    #[inline]
+    pub fn draw_quad(
         &mut self,
         coords: (f32, f32, f32, f32),
         texcoords: (f32, f32, f32, f32),
@@ -504,30 +1769,526 @@ impl Renderer {
         depth: f32,
         call_handle: &DrawCallHandle,
     ) {
+        let (tx0, ty0, tx1, ty1) = texcoords;
+        self.draw_quad_with_uvs(
+            coords,
+            [(tx0, ty0), (tx1, ty0), (tx1, ty1), (tx0, ty1)],
+            color,
+            rotation,
+            depth,
+            call_handle,
+        );
+    }
+
+    /// Draws a textured rectangle on the screen, with explicit
+    /// texture coordinates for each corner. This allows drawing
+    /// regions of the texture which aren't axis-aligned rectangles,
+    /// like sprites packed into an atlas rotated.
+    ///
+    /// - `uvs`: The texture coordinates of each corner of the quad,
+    ///   in the range `0.0 - 1.0`. Arrangement: [top-left, top-right,
+    ///   bottom-right, bottom-left]
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    #[inline]
+    pub fn draw_quad_with_uvs(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        original_uvs: [(f32, f32); 4],
+        color: (f32, f32, f32, f32),
+        rotation: (f32, f32, f32),
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let coords = if self.pixel_perfect {
+            snap_to_pixels(coords)
+        } else {
+            coords
+        };
+        let (ox0, oy0, ox1, oy1) = coords; // Original coords
+        let (rads, pivot_x, pivot_y) = rotation;
+        let rotation = (rads, pivot_x + ox0, pivot_y + oy0);
+
+        let (x0, y0, x1, y1, uvs);
+        if let Some(&(cx0, cy0, cx1, cy1)) = self.clip_stack.last() {
+            if ox0 > cx1 || ox1 < cx0 || oy0 > cy1 || oy1 < cy0 {
+                return;
+            }
+            x0 = ox0.max(cx0).min(cx1);
+            y0 = oy0.max(cy0).min(cy1);
+            x1 = ox1.max(cx0).min(cx1);
+            y1 = oy1.max(cy0).min(cy1);
+            // Interpolate the UVs of the clipped corners from the
+            // original ones, which works for rotated UVs as well
+            let [tl, tr, br, bl] = original_uvs;
+            let lerp = |a: (f32, f32), b: (f32, f32), t: f32| {
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+            };
+            let uv_at = |x: f32, y: f32| {
+                let (s, t) = ((x - ox0) / (ox1 - ox0), (y - oy0) / (oy1 - oy0));
+                lerp(lerp(tl, tr, s), lerp(bl, br, s), t)
+            };
+            uvs = [uv_at(x0, y0), uv_at(x1, y0), uv_at(x1, y1), uv_at(x0, y1)];
+        } else {
+            x0 = ox0;
+            y0 = oy0;
+            x1 = ox1;
+            y1 = oy1;
+            uvs = original_uvs;
+        }
+
+        self.push_quad(
+            [(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+            uvs,
+            color,
+            rotation,
+            depth,
+            call_handle,
+        );
+    }
+
+    /// Pushes a clipping rectangle onto the clip stack. Until it's
+    /// popped with [`Renderer::pop_clip_rect`], quads are only drawn
+    /// inside the rectangle, intersected with the previously pushed
+    /// ones, so nested areas (eg. scrollable panels inside panels)
+    /// can never draw outside of their parents.
+    ///
+    /// - `rect`: The clipping area, in (logical) pixels. Arrangement:
+    ///   (left, top, right, bottom)
+    ///
+    /// The rectangles work like the `clip_area` of
+    /// [`Renderer::draw_quad_clipped`], so the clipping is done before
+    /// rotation, in the quads' unrotated space. Quads drawn with
+    /// [`Renderer::draw_quad_transform`] are only skipped if they're
+    /// entirely outside the area, and polygons are not clipped.
+    pub fn push_clip_rect(&mut self, rect: (f32, f32, f32, f32)) {
+        let (x0, y0, x1, y1) = rect;
+        let rect = if let Some(&(cx0, cy0, cx1, cy1)) = self.clip_stack.last() {
+            let (x0, y0) = (x0.max(cx0), y0.max(cy0));
+            (x0, y0, x1.min(cx1).max(x0), y1.min(cy1).max(y0))
+        } else {
+            rect
+        };
+        self.clip_stack.push(rect);
+    }
+
+    /// Pops the latest clipping rectangle pushed with
+    /// [`Renderer::push_clip_rect`], returning the intersected area
+    /// that was in use, or `None` if the stack was empty.
+    pub fn pop_clip_rect(&mut self) -> Option<(f32, f32, f32, f32)> {
+        self.clip_stack.pop()
+    }
+
+    /// Returns the current clipping area, the intersection of all the
+    /// rectangles on the clip stack, if there are any.
+    pub fn clip_rect(&self) -> Option<(f32, f32, f32, f32)> {
+        self.clip_stack.last().cloned()
+    }
+
+    /// Draws a textured rectangle transformed by a 2x2 matrix around
+    /// its center, which can rotate, scale, flip and shear (skew) the
+    /// quad.
+    ///
+    /// - `matrix`: The transformation, in row-major order: `[a, b,
+    ///   c, d]` transforms a point `(x, y)` (relative to the center of
+    ///   the quad) into `(a * x + b * y, c * x + d * y)`. For example,
+    ///   `[1.0, 0.0, 0.0, 1.0]` is the identity, `[1.0, -0.3, 0.0,
+    ///   1.0]` leans the quad to the right like italic text, and
+    ///   `[cos, -sin, sin, cos]` rotates it clockwise (on screen, as y
+    ///   points down) like the `rotation` of [`Renderer::draw_quad`].
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    pub fn draw_quad_transform(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        texcoords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        z: f32,
+        call_handle: &DrawCallHandle,
+        matrix: [f32; 4],
+    ) {
+        let coords = if self.pixel_perfect {
+            snap_to_pixels(coords)
+        } else {
+            coords
+        };
         let (x0, y0, x1, y1) = coords;
         let (tx0, ty0, tx1, ty1) = texcoords;
+        let [a, b, c, d] = matrix;
+        let (center_x, center_y) = ((x0 + x1) * 0.5, (y0 + y1) * 0.5);
+        let transform = |x: f32, y: f32| {
+            let (x, y) = (x - center_x, y - center_y);
+            (center_x + a * x + b * y, center_y + c * x + d * y)
+        };
+        let corners = [
+            transform(x0, y0),
+            transform(x1, y0),
+            transform(x1, y1),
+            transform(x0, y1),
+        ];
+        if let Some(&(cx0, cy0, cx1, cy1)) = self.clip_stack.last() {
+            let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+            let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+            for &(x, y) in &corners {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+            if min_x > cx1 || max_x < cx0 || min_y > cy1 || max_y < cy0 {
+                return;
+            }
+        }
+        self.push_quad(
+            corners,
+            [(tx0, ty0), (tx1, ty0), (tx1, ty1), (tx0, ty1)],
+            color,
+            (0.0, 0.0, 0.0),
+            z,
+            call_handle,
+        );
+    }
+
+    /// Queues a quad with the given corners (in the order: top-left,
+    /// top-right, bottom-right, bottom-left) and rotation, where the
+    /// rotation pivot is in absolute coordinates.
+    #[inline]
+    fn push_quad(
+        &mut self,
+        corners: [(f32, f32); 4],
+        uvs: [(f32, f32); 4],
+        color: (f32, f32, f32, f32),
+        rotation: (f32, f32, f32),
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let [(tl_x, tl_y), (tr_x, tr_y), (br_x, br_y), (bl_x, bl_y)] = corners;
+        let [(tl_u, tl_v), (tr_u, tr_v), (br_u, br_v), (bl_u, bl_v)] = uvs;
         let (red, green, blue, alpha) = color;
+        let (red, green, blue) = if self.srgb {
+            (
+                srgb_to_linear(red),
+                srgb_to_linear(green),
+                srgb_to_linear(blue),
+            )
+        } else {
+            (red, green, blue)
+        };
         let (rads, pivot_x, pivot_y) = rotation;
 
-        self.calls[call_handle.0].lowest_depth = self.calls[call_handle.0].lowest_depth.min(depth);
-        if !self.gl_state.legacy {
-            let (width, height, tw, th) = (x1 - x0, y1 - y0, tx1 - tx0, ty1 - ty0);
-            let quad = [
-                x0, y0, width, height, tx0, ty0, tw, th, red, green, blue, alpha, rads, pivot_x,
-                pivot_y, depth,
+        let index = self.call_index(call_handle);
+
+        // 6 vertices, each of which consist of: position (x, y, z),
+        // texcoord (x, y), colors (r, g, b, a), rotation rads,
+        // rotation pivot (x, y)
+        #[rustfmt::skip]
+        let quad = [
+            tl_x, tl_y, depth, tl_u, tl_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+            tr_x, tr_y, depth, tr_u, tr_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+            br_x, br_y, depth, br_u, br_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+            tl_x, tl_y, depth, tl_u, tl_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+            br_x, br_y, depth, br_u, br_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+            bl_x, bl_y, depth, bl_u, bl_v, red, green, blue, alpha, rads, pivot_x, pivot_y,
+        ];
+        self.queue_vertices(index, depth, &quad);
+    }
+
+    /// Queues the vertices into the draw call, or into the current
+    /// stencil mask, if there is one.
+    fn queue_vertices(&mut self, index: usize, depth: f32, vertices: &[f32]) {
+        let mask_queue = match (self.mask_state, self.masks.last_mut()) {
+            (MaskState::Shape, Some(mask)) => Some(&mut mask.shape),
+            (MaskState::Masked, Some(mask)) => Some(&mut mask.masked),
+            _ => None,
+        };
+        if let Some(queue) = mask_queue {
+            match queue.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, queued)) => queued.extend_from_slice(vertices),
+                None => queue.push((index, vertices.to_vec())),
+            }
+        } else {
+            let call = &mut self.calls[index];
+            call.lowest_depth = call.lowest_depth.min(depth);
+            call.static_dirty = call.is_static;
+            call.attributes.vbo_data.extend_from_slice(vertices);
+        }
+    }
+
+    /// Starts drawing a stencil mask, for clipping drawn quads into
+    /// arbitrary shapes. Until [`Renderer::draw_masked`] is called,
+    /// the quads and triangles drawn (with any draw call) make up the
+    /// shape of the mask: they are not visible themselves, but the
+    /// pixels they cover are marked in the stencil buffer. The default
+    /// shaders discard fully transparent pixels, so textured quads
+    /// can be used as arbitrary shapes as well.
+    ///
+    /// After [`Renderer::draw_masked`], the quads drawn until
+    /// [`Renderer::end_mask`] are only visible inside the shape.
+    ///
+    /// ```no_run
+    /// # use fae::renderer::{DrawCallParameters, Renderer};
+    /// # let mut renderer = Renderer::new(false);
+    /// # let circle = renderer.create_draw_call(DrawCallParameters::default()).unwrap();
+    /// # let picture = renderer.create_draw_call(DrawCallParameters::default()).unwrap();
+    /// renderer.begin_mask();
+    /// renderer.draw_quad((0.0, 0.0, 64.0, 64.0), (0.0, 0.0, 1.0, 1.0), (1.0, 1.0, 1.0, 1.0), (0.0, 0.0, 0.0), 0.0, &circle);
+    /// renderer.draw_masked();
+    /// renderer.draw_quad((0.0, 0.0, 128.0, 128.0), (0.0, 0.0, 1.0, 1.0), (1.0, 1.0, 1.0, 1.0), (0.0, 0.0, 0.0), 0.0, &picture);
+    /// renderer.end_mask();
+    /// ```
+    ///
+    /// The masks are rendered in order after the rest of the frame's
+    /// quads, so masked quads are still depth tested against the
+    /// unmasked ones, but blended masked quads always end up on top of
+    /// blended unmasked ones. Masking requires a stencil buffer, see
+    /// `WindowSettings::stencil_bits`: without one, the masked quads
+    /// are drawn everywhere.
+    pub fn begin_mask(&mut self) {
+        self.masks.push(StencilMask::default());
+        self.mask_state = MaskState::Shape;
+    }
+
+    /// Finishes the shape of the current stencil mask, and starts
+    /// drawing the quads that are only visible inside it. See
+    /// [`Renderer::begin_mask`].
+    pub fn draw_masked(&mut self) {
+        if self.mask_state == MaskState::Inactive {
+            log::warn!("Renderer::draw_masked called without Renderer::begin_mask");
+            return;
+        }
+        self.mask_state = MaskState::Masked;
+    }
+
+    /// Ends the current stencil mask: quads drawn after this are
+    /// drawn normally again. The stencil buffer is cleared for each
+    /// mask during rendering, so masks don't affect each other. See
+    /// [`Renderer::begin_mask`].
+    pub fn end_mask(&mut self) {
+        self.mask_state = MaskState::Inactive;
+    }
+
+    /// Draws a sprite from a texture atlas, where the sprite might
+    /// have been rotated to pack it more tightly, like texture
+    /// packers such as TexturePacker do.
+    ///
+    /// - `coords`: The coordinates of the corners of the drawn,
+    ///   upright sprite, in (logical) pixels. Arrangement: (left,
+    ///   top, right, bottom)
+    ///
+    /// - `src_px`: The region of the atlas the sprite is in, in
+    ///   pixels of the draw call's texture, as it is stored in the
+    ///   atlas. Arrangement: (left, top, right, bottom)
+    ///
+    /// - `rotated`: Whether the sprite is stored rotated 90 degrees
+    ///   clockwise in the atlas. If it is, the region is rotated back
+    ///   when drawing, so `src_px` is as wide as the drawn sprite is
+    ///   tall.
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    pub fn draw_sprite_rotated_atlas(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        src_px: (f32, f32, f32, f32),
+        rotated: bool,
+        color: (f32, f32, f32, f32),
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let (width, height) = self.calls[self.call_index(call_handle)].texture_size;
+        let (width, height) = (width as f32, height as f32);
+        let (left, top, right, bottom) = src_px;
+        let (u0, v0, u1, v1) = (left / width, top / height, right / width, bottom / height);
+        let uvs = if rotated {
+            // The top-left corner of the sprite is at the top-right
+            // of the clockwise rotated region, and so on
+            [(u1, v0), (u1, v1), (u0, v1), (u0, v0)]
+        } else {
+            [(u0, v0), (u1, v0), (u1, v1), (u0, v1)]
+        };
+        self.draw_quad_with_uvs(coords, uvs, color, (0.0, 0.0, 0.0), depth, call_handle);
+    }
+
+    /// Draws a textured triangle on the screen.
+    ///
+    /// - `vertices`: The corners of the triangle, in (logical)
+    ///   pixels. Arrangement: [(x, y); 3]
+    ///
+    /// - `texcoords`: The texture coordinates (UVs) of each corner,
+    ///   in the range `0.0 - 1.0`. The shaders will not use the
+    ///   texture at all if the texcoords are all `-1.0`.
+    ///
+    /// - `colors`: The color tint of each corner, interpolated
+    ///   across the triangle. See [`Renderer::draw_quad`] for the
+    ///   color details.
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    pub fn draw_triangle(
+        &mut self,
+        vertices: [(f32, f32); 3],
+        texcoords: [(f32, f32); 3],
+        colors: [(f32, f32, f32, f32); 3],
+        depth: f32,
+        call_handle: &DrawCallHandle,
+    ) {
+        let srgb = self.srgb;
+        let index = self.call_index(call_handle);
+
+        let mut triangle = [0.0; FLOATS_PER_VERTEX * 3];
+        for i in 0..3 {
+            let (x, y) = vertices[i];
+            let (tx, ty) = texcoords[i];
+            let (red, green, blue, alpha) = colors[i];
+            let (red, green, blue) = if srgb {
+                (
+                    srgb_to_linear(red),
+                    srgb_to_linear(green),
+                    srgb_to_linear(blue),
+                )
+            } else {
+                (red, green, blue)
+            };
+            #[rustfmt::skip]
+            let vertex = [
+                x, y, depth, tx, ty, red, green, blue, alpha, 0.0, 0.0, 0.0,
             ];
-            self.calls[call_handle.0]
-                .attributes
-                .vbo_data
-                .extend_from_slice(&quad);
+            triangle[i * FLOATS_PER_VERTEX..(i + 1) * FLOATS_PER_VERTEX].copy_from_slice(&vertex);
+        }
+        self.queue_vertices(index, depth, &triangle);
+    }
+
+    /// Draws a filled, untextured convex polygon on the screen.
+    ///
+    /// The polygon is triangulated as a fan from the first point, so
+    /// only convex polygons are drawn correctly. Concave ones will
+    /// have triangles covering areas outside of the polygon. The
+    /// points can be in clockwise or counter-clockwise order. With
+    /// less than three points, nothing is drawn. As the polygon is
+    /// untextured, it can be drawn with any draw call. `None` draws
+    /// it with the [`Renderer::white_draw_call`].
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    pub fn draw_polygon(
+        &mut self,
+        points: &[(f32, f32)],
+        color: (f32, f32, f32, f32),
+        depth: f32,
+        call_handle: Option<&DrawCallHandle>,
+    ) {
+        let call_handle = &call_handle.copied().unwrap_or(self.white_call);
+        if points.len() < 3 {
+            return;
+        }
+        let first = points[0];
+        for edge in points[1..].windows(2) {
+            self.draw_triangle(
+                [first, edge[0], edge[1]],
+                [(-1.0, -1.0); 3],
+                [color; 3],
+                depth,
+                call_handle,
+            );
+        }
+    }
+
+    /// Draws a filled, untextured rectangle with rounded corners. Like
+    /// [`Renderer::draw_polygon`], it can be drawn with any draw call,
+    /// and `None` draws it with the [`Renderer::white_draw_call`].
+    ///
+    /// - `radius`: The radius of the corners, in (logical)
+    ///   pixels. Clamped to half of the rectangle's smaller side.
+    ///
+    /// - `segments`: How many triangles each corner is made of. More
+    ///   segments result in smoother corners. At least 1.
+    ///
+    /// See [`Renderer::draw_quad`] for the rest of the parameters'
+    /// docs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rounded_rect(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        radius: f32,
+        segments: u32,
+        color: (f32, f32, f32, f32),
+        depth: f32,
+        call_handle: Option<&DrawCallHandle>,
+    ) {
+        let call_handle = &call_handle.copied().unwrap_or(self.white_call);
+        let (x0, y0, x1, y1) = coords;
+        let r = radius.min((x1 - x0).min(y1 - y0) / 2.0).max(0.0);
+        let no_rotation = (0.0, 0.0, 0.0);
+
+        // The center column, and the left and right sides
+        self.draw_quad_tinted(
+            (x0 + r, y0, x1 - r, y1),
+            color,
+            no_rotation,
+            depth,
+            call_handle,
+        );
+        if r <= 0.0 {
+            return;
+        }
+        self.draw_quad_tinted(
+            (x0, y0 + r, x0 + r, y1 - r),
+            color,
+            no_rotation,
+            depth,
+            call_handle,
+        );
+        self.draw_quad_tinted(
+            (x1 - r, y0 + r, x1, y1 - r),
+            color,
+            no_rotation,
+            depth,
+            call_handle,
+        );
+
+        // The corners, as fans around the centers of the corner circles
+        let segments = segments.max(1);
+        let quarter = std::f32::consts::PI / 2.0;
+        let corners = [
+            ((x1 - r, y1 - r), 0.0),
+            ((x0 + r, y1 - r), quarter),
+            ((x0 + r, y0 + r), quarter * 2.0),
+            ((x1 - r, y0 + r), quarter * 3.0),
+        ];
+        for &((cx, cy), start_angle) in &corners {
+            let point = |i: u32| {
+                let angle = start_angle + quarter * i as f32 / segments as f32;
+                (cx + angle.cos() * r, cy + angle.sin() * r)
+            };
+            for i in 0..segments {
+                self.draw_triangle(
+                    [(cx, cy), point(i), point(i + 1)],
+                    [(-1.0, -1.0); 3],
+                    [color; 3],
+                    depth,
+                    call_handle,
+                );
+            }
         }
     }
 
-    /// Clears all queued draws. Like a dummy-version of [`Renderer::render`].
+    /// Clears all queued draws, except the quads of static draw
+    /// calls. Like a dummy-version of [`Renderer::render`]: no
+    /// OpenGL calls are made, so this can be used to skip a frame
+    /// without the quads drawn so far leaking into the next one. Text
+    /// that hasn't been composed into the draw calls yet can be
+    /// discarded with
+    /// [`TextRenderer::flush`](../text/struct.TextRenderer.html#method.flush).
     pub fn flush(&mut self) {
-        for call in self.calls.iter_mut() {
+        self.stats = RenderStats::default();
+        for call in self.calls.iter_mut().filter(|call| !call.is_static) {
             call.attributes.vbo_data.clear();
+            call.lowest_depth = f32::INFINITY;
         }
+        self.masks.clear();
+        self.mask_state = MaskState::Inactive;
     }
 
     /// Renders all currently queued draws.
@@ -536,42 +2297,142 @@ impl Renderer {
     /// alpha-blended ones, back to front. Drawing from front to back
     /// is more efficient, as there is less overdraw because of depth
     /// testing, but proper blending requires back to front ordering.
+    ///
+    /// Panics if the OpenGL functions haven't been loaded yet.
     pub fn render(&mut self, width: f32, height: f32) {
+        if let Err(err) = check_gl_loaded() {
+            panic!("cannot render: {}", err);
+        }
         self.profiler.start("render");
-        let m00 = 2.0 / width;
-        let m11 = -2.0 / height;
-        let matrix = [
-            m00, 0.0, 0.0, -1.0, 0.0, m11, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-        ];
-
-        self.profiler.start("clear");
-        unsafe {
-            gl::ClearColor(1.0, 1.0, 1.0, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        let mut window_viewport = [0; 4];
+        let custom_viewport = self.viewport.is_some() || self.virtual_resolution.is_some();
+        let dirty_rect = self.dirty_rect;
+        if custom_viewport || dirty_rect.is_some() {
+            unsafe {
+                gl::GetIntegerv(gl::VIEWPORT, window_viewport.as_mut_ptr());
+            }
         }
-        self.profiler.end("clear");
+        let matrix = if custom_viewport {
+            // OpenGL's viewport y starts from the bottom
+            let [window_x, window_y, window_w, window_h] = window_viewport;
+            let to_gl = |(x, y, w, h): (i32, i32, i32, i32)| {
+                (window_x + x, window_y + window_h - y - h, w, h)
+            };
+            let area = self.viewport.unwrap_or((0, 0, window_w, window_h));
+            let region = match self.virtual_resolution {
+                Some(resolution) => letterbox(area, resolution),
+                None => area,
+            };
+
+            if dirty_rect.is_none() {
+                self.profiler.start("clear");
+                if self.virtual_resolution.is_some() {
+                    clear_rect(to_gl(area), (0.0, 0.0, 0.0, 1.0));
+                }
+                clear_rect(to_gl(region), self.clear_color);
+                self.profiler.end("clear");
+            }
+
+            let (x, y, w, h) = to_gl(region);
+            unsafe {
+                gl::Viewport(x, y, w, h);
+            }
+            let dpi_factor = self.dpi_factor;
+            let (x, y, w, h) = region;
+            self.screen_offset = (x as f32 / dpi_factor, y as f32 / dpi_factor);
+            self.screen_size = (w as f32 / dpi_factor, h as f32 / dpi_factor);
+            let (width, height) = match self.virtual_resolution {
+                Some((width, height)) => (width as f32, height as f32),
+                None => self.screen_size,
+            };
+            projection_matrix(width, height, self.depth_range)
+        } else {
+            if dirty_rect.is_none() {
+                self.profiler.start("clear");
+                let (r, g, b, a) = self.clear_color;
+                unsafe {
+                    gl::ClearColor(r, g, b, a);
+                    gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                }
+                self.profiler.end("clear");
+            }
+
+            self.screen_offset = (0.0, 0.0);
+            self.screen_size = (width, height);
+            projection_matrix(width, height, self.depth_range)
+        };
+        self.projection = matrix;
+
+        let scissor_test = dirty_rect.map(|(left, top, right, bottom)| {
+            let dpi_factor = self.dpi_factor;
+            let (x0, y0) = self.world_to_screen((left, top));
+            let (x1, y1) = self.world_to_screen((right, bottom));
+            let (x0, x1) = (x0.min(x1) * dpi_factor, x0.max(x1) * dpi_factor);
+            let (y0, y1) = (y0.min(y1) * dpi_factor, y0.max(y1) * dpi_factor);
+            let [window_x, window_y, _, window_h] = window_viewport;
+            let scissor = (
+                window_x + x0.floor() as i32,
+                window_y + window_h - y1.ceil() as i32,
+                (x1.ceil() - x0.floor()) as i32,
+                (y1.ceil() - y0.floor()) as i32,
+            );
+            self.profiler.start("clear");
+            clear_rect(scissor, self.clear_color);
+            self.profiler.end("clear");
+            unsafe {
+                let enabled = gl::IsEnabled(gl::SCISSOR_TEST) != 0;
+                let (x, y, w, h) = scissor;
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(x, y, w, h);
+                enabled
+            }
+        });
 
         self.gl_push();
         let legacy = self.gl_state.legacy;
 
+        let wireframe = self.wireframe && !legacy;
+        let max_quads = self.max_quads_per_batch;
+
         unsafe {
             gl::Enable(gl::DEPTH_TEST);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            if wireframe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            }
+            if self.srgb {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            } else if !legacy {
+                gl::Disable(gl::FRAMEBUFFER_SRGB);
+            }
         }
 
-        let profiler = &self.profiler;
-
         let mut call_indices: Vec<usize> = (0..self.calls.len()).collect();
         call_indices.sort_unstable_by(|a, b| {
             let call_a = &self.calls[*a];
             let call_b = &self.calls[*b];
-            let a = call_a.lowest_depth;
-            let a = if call_a.blend { a } else { 2.0 - a };
-            let b = call_b.lowest_depth;
-            let b = if call_b.blend { b } else { 2.0 - b };
-            b.partial_cmp(&a).unwrap()
+            let (a, b) = (call_a.lowest_depth, call_b.lowest_depth);
+            // Opaque calls first, front to back, then blended calls,
+            // back to front
+            call_a.blend.cmp(&call_b.blend).then_with(|| {
+                if call_a.blend {
+                    b.total_cmp(&a)
+                } else {
+                    a.total_cmp(&b)
+                }
+            })
         });
 
+        if self.auto_batch {
+            self.profiler.start("batching");
+            self.batch_calls(&call_indices);
+            self.profiler.end("batching");
+        }
+        let profiler = &self.profiler;
+        let mut stats = RenderStats::default();
+        let mut bound_texture = None;
+        let z_interleaving = self.z_interleaving;
+        let mut interleaved_calls = Vec::new();
+
         for i in call_indices {
             let call = &mut self.calls[i];
 
@@ -580,16 +2441,25 @@ impl Renderer {
                 profiler.end(format!("call {}", i));
                 continue;
             }
+            if let Some(dirty_rect) = dirty_rect {
+                if !vertices_overlap_rect(&call.attributes.vbo_data, dirty_rect) {
+                    if !call.is_static {
+                        call.attributes.vbo_data.clear();
+                        call.lowest_depth = f32::INFINITY;
+                    }
+                    profiler.end(format!("call {}", i));
+                    continue;
+                }
+            }
+            if z_interleaving && call.blend {
+                interleaved_calls.push(i);
+                profiler.end(format!("call {}", i));
+                continue;
+            }
 
             profiler.start("setting state");
             unsafe {
-                if call.blend {
-                    gl::Enable(gl::BLEND);
-                    gl::DepthFunc(gl::LEQUAL);
-                } else {
-                    gl::Disable(gl::BLEND);
-                    gl::DepthFunc(gl::LESS);
-                }
+                set_blending(call);
                 gl::UseProgram(call.program.program);
                 gl::UniformMatrix4fv(
                     call.program.projection_matrix_location,
@@ -599,9 +2469,13 @@ impl Renderer {
                 );
                 if !legacy {
                     gl::BindVertexArray(call.attributes.vao);
-                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, call.attributes.element_buffer);
                 }
-                gl::BindTexture(gl::TEXTURE_2D, call.texture);
+                if bound_texture != Some(call.texture) {
+                    gl::BindTexture(gl::TEXTURE_2D, call.texture);
+                    bound_texture = Some(call.texture);
+                    stats.texture_binds += 1;
+                }
+                bind_extra_textures(&call.extra_textures, false);
                 gl::BindBuffer(gl::ARRAY_BUFFER, call.attributes.vbo);
             }
             profiler.end("setting state");
@@ -609,7 +2483,17 @@ impl Renderer {
 
             let len = (mem::size_of::<f32>() * call.attributes.vbo_data.len()) as isize;
             let ptr = call.attributes.vbo_data.as_ptr() as *const _;
-            if len <= call.attributes.allocated_vbo_data_size {
+            if call.is_static {
+                if call.static_dirty {
+                    call.attributes.allocated_vbo_data_size = len;
+                    call.static_dirty = false;
+                    unsafe {
+                        profiler.start("bufferData (static)");
+                        gl::BufferData(gl::ARRAY_BUFFER, len, ptr, gl::STATIC_DRAW);
+                        profiler.end("bufferData (static)");
+                    }
+                }
+            } else if len <= call.attributes.allocated_vbo_data_size {
                 unsafe {
                     profiler.start("bufferSubData");
                     gl::BufferSubData(gl::ARRAY_BUFFER, 0, len, ptr);
@@ -625,9 +2509,12 @@ impl Renderer {
             }
             print_gl_errors(&format!("after pushing vertex buffer #{}", i));
 
+            let vertex_count = call.attributes.vbo_data.len() / FLOATS_PER_VERTEX;
+            stats.vertices += vertex_count;
+            unsafe {
+                enable_polygon_offset(call);
+            }
             if legacy {
-                // 12 floats (3 for pos + 2 tex + 4 col + 3 rot) per vertex
-                let vertex_count = call.attributes.vbo_data.len() as i32 / 12;
                 unsafe {
                     profiler.start("enable vertex attribs");
                     enable_vertex_attribs(&[
@@ -638,7 +2525,10 @@ impl Renderer {
                     ]);
                     profiler.end("enable vertex attribs");
                     profiler.start("drawArrays");
-                    gl::DrawArrays(gl::TRIANGLES, 0, vertex_count);
+                    for (first, count) in draw_ranges(vertex_count, max_quads) {
+                        gl::DrawArrays(gl::TRIANGLES, first, count);
+                        stats.draw_calls += 1;
+                    }
                     profiler.end("drawArrays");
                     profiler.start("disable vertex attribs");
                     disable_vertex_attribs(&[
@@ -651,47 +2541,224 @@ impl Renderer {
                 }
                 print_gl_errors(&format!("[legacy] after drawing buffer #{}", i));
             } else {
-                // 16 floats (4 for x,y,w,h + 4 tex xywh + 4 col + 3 rot + 1 z) per vertex
-                let instance_count = call.attributes.vbo_data.len() as i32 / 16;
-                profiler.start("drawElementsInstanced");
+                profiler.start("drawArrays");
+                for (first, count) in draw_ranges(vertex_count, max_quads) {
+                    unsafe {
+                        gl::DrawArrays(gl::TRIANGLES, first, count);
+                    }
+                    stats.draw_calls += 1;
+                }
+                profiler.end("drawArrays");
+                print_gl_errors(&format!("after drawing buffer #{}", i));
+            }
+
+            unsafe {
+                disable_polygon_offset(call);
+                bind_extra_textures(&call.extra_textures, true);
+            }
+            if !call.is_static {
+                call.attributes.vbo_data.clear();
+                call.lowest_depth = f32::INFINITY;
+            }
+
+            print_gl_errors(&format!("after render #{}", i));
+            profiler.end(format!("call {}", i));
+        }
+
+        if !interleaved_calls.is_empty() {
+            profiler.start("z-interleaved calls");
+            let queues = interleaved_calls
+                .iter()
+                .map(|&i| (i, &self.calls[i].attributes.vbo_data[..]))
+                .collect::<Vec<_>>();
+            let runs = interleave_by_depth(&queues);
+            for (i, vertices) in &runs {
                 unsafe {
-                    gl::DrawElementsInstanced(
-                        gl::TRIANGLES,
-                        6,
-                        gl::UNSIGNED_BYTE,
-                        ptr::null(),
-                        instance_count,
-                    );
+                    stats.vertices += draw_vertices(&mut self.calls[*i], vertices, legacy, &matrix);
+                }
+                stats.draw_calls += 1;
+            }
+            for i in interleaved_calls {
+                let call = &mut self.calls[i];
+                if !call.is_static {
+                    call.attributes.vbo_data.clear();
+                    call.lowest_depth = f32::INFINITY;
                 }
-                profiler.end("drawElementsInstanced");
-                print_gl_errors(&format!("after drawing buffer #{}", i));
             }
+            print_gl_errors("after rendering z-interleaved calls");
+            profiler.end("z-interleaved calls");
+        }
 
-            call.attributes.vbo_data.clear();
-            call.lowest_depth = 1.0;
+        if !self.masks.is_empty() {
+            profiler.start("stencil masks");
+            let masks = mem::take(&mut self.masks);
+            unsafe {
+                let stencil_test = gl::IsEnabled(gl::STENCIL_TEST) != 0;
+                gl::Enable(gl::STENCIL_TEST);
+                for mask in &masks {
+                    gl::ClearStencil(0);
+                    gl::Clear(gl::STENCIL_BUFFER_BIT);
+
+                    // Mark the shape in the stencil buffer, without
+                    // touching the color or depth buffers
+                    gl::StencilFunc(gl::ALWAYS, 1, 0xFF);
+                    gl::StencilOp(gl::KEEP, gl::KEEP, gl::REPLACE);
+                    gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+                    gl::DepthMask(gl::FALSE);
+                    gl::Disable(gl::DEPTH_TEST);
+                    for (i, vertices) in &mask.shape {
+                        stats.vertices +=
+                            draw_vertices(&mut self.calls[*i], vertices, legacy, &matrix);
+                        stats.draw_calls += 1;
+                    }
 
-            print_gl_errors(&*format!("after render #{}", i));
-            profiler.end(format!("call {}", i));
+                    gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                    gl::DepthMask(gl::TRUE);
+                    gl::Enable(gl::DEPTH_TEST);
+                    gl::StencilFunc(gl::EQUAL, 1, 0xFF);
+                    gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+                    for (i, vertices) in &mask.masked {
+                        stats.vertices +=
+                            draw_vertices(&mut self.calls[*i], vertices, legacy, &matrix);
+                        stats.draw_calls += 1;
+                    }
+                }
+                if !stencil_test {
+                    gl::Disable(gl::STENCIL_TEST);
+                }
+            }
+            print_gl_errors("after rendering stencil masks");
+            profiler.end("stencil masks");
         }
+        self.mask_state = MaskState::Inactive;
 
+        if wireframe {
+            unsafe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            }
+        }
         self.gl_pop();
+        if scissor_test == Some(false) {
+            unsafe {
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+        }
+        if custom_viewport {
+            let [x, y, w, h] = window_viewport;
+            unsafe {
+                gl::Viewport(x, y, w, h);
+            }
+        }
 
+        stats.quads = stats.vertices / 6;
+        self.stats = stats;
         self.profiler.end("render");
     }
 
-    /// Synchronizes the GPU and CPU state, ensuring that all OpenGL
-    /// calls made so far have been executed. One use case would be
-    /// after swapping buffers, to sleep until the buffers really have
-    /// been swapped.
+    /// Renders all currently queued draws into the framebuffer
+    /// object `fbo`, instead of the currently bound framebuffer (the
+    /// window, by default). Useful for using fae as a 2D layer in an
+    /// engine which manages its own framebuffers.
+    ///
+    /// - `width`, `height`: The size of the framebuffer, in physical
+    ///   pixels. The viewport is set to cover all of it, and the
+    ///   logical size of the render is the size divided by the DPI
+    ///   factor (see [`Renderer::set_dpi_factor`]).
+    ///
+    /// The previously bound framebuffer and the viewport are
+    /// restored afterwards. Otherwise works like
+    /// [`Renderer::render`].
+    pub fn render_into(&mut self, fbo: GLuint, width: u32, height: u32) {
+        let mut previous_fbo = 0;
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+        print_gl_errors("after binding the framebuffer to render into");
+        let dpi_factor = self.dpi_factor;
+        self.render(width as f32 / dpi_factor, height as f32 / dpi_factor);
+        let [x, y, w, h] = previous_viewport;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::Viewport(x, y, w, h);
+        }
+    }
+
+    /// Returns statistics about the last [`Renderer::render`], for
+    /// profiling. Reset by every render, and by
+    /// [`Renderer::flush`].
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Moves the quads of consecutive (in `call_indices` order) draw
+    /// calls with identical state into the first call of each run.
+    pub(crate) fn batch_calls(&mut self, call_indices: &[usize]) {
+        let mut leader: Option<usize> = None;
+        for &i in call_indices {
+            if self.calls[i].attributes.vbo_data.is_empty() {
+                continue;
+            }
+            match leader {
+                Some(l) if self.calls[l].can_batch_with(&self.calls[i]) => {
+                    let mut data = mem::take(&mut self.calls[i].attributes.vbo_data);
+                    self.calls[l].attributes.vbo_data.extend_from_slice(&data);
+                    let depth = self.calls[i].lowest_depth;
+                    self.calls[l].lowest_depth = self.calls[l].lowest_depth.min(depth);
+                    // Give the allocation back to avoid reallocating next frame
+                    data.clear();
+                    self.calls[i].attributes.vbo_data = data;
+                    self.calls[i].lowest_depth = f32::INFINITY;
+                }
+                _ => leader = Some(i),
+            }
+        }
+    }
+
+    /// Returns the amount of floats queued in the draw call's vertex
+    /// buffer.
+    #[cfg(test)]
+    pub(crate) fn queued_floats(&self, call_handle: &DrawCallHandle) -> usize {
+        self.calls[self.call_index(call_handle)]
+            .attributes
+            .vbo_data
+            .len()
+    }
+
+    /// Returns the OpenGL objects of the draw call. Arrangement:
+    /// (texture, vertex buffer, shader program)
+    #[cfg(test)]
+    #[allow(dead_code)]
+    pub(crate) fn gl_objects(&self, call_handle: &DrawCallHandle) -> (GLuint, GLuint, GLuint) {
+        let call = &self.calls[self.call_index(call_handle)];
+        (call.texture, call.attributes.vbo, call.program.program)
+    }
+
+    /// Returns the floats queued in the draw call's vertex buffer.
+    #[cfg(test)]
+    pub(crate) fn queued_vertices(&self, call_handle: &DrawCallHandle) -> &[f32] {
+        &self.calls[self.call_index(call_handle)].attributes.vbo_data
+    }
+
+    /// Synchronizes the GPU and CPU state, blocking until all OpenGL
+    /// calls made so far have been executed by the GPU.
+    /// `Window::swap_buffers` calls this right after swapping, so
+    /// with vsync enabled, the CPU waits until the frame has actually
+    /// been presented, instead of queueing up frames ahead of the
+    /// GPU, which keeps input latency down at the cost of some
+    /// parallelism. For finer control, see [`Renderer::fence`].
     ///
     /// If running with modern OpenGL, this is implemented with
-    /// glClientWaitSync calls, 2ms thread::sleeps in between.
+    /// glClientWaitSync calls, 2ms thread::sleeps in between, so the
+    /// CPU isn't spinning while waiting.
     ///
     /// If running with legacy (2.1 or 2.0 ES) OpenGL, this is
     /// equivalent to glFinish.
     pub fn synchronize(&self) {
         use std::thread::sleep;
-        use std::time::Duration;
 
         let mut synchronized = false;
 
@@ -726,11 +2793,135 @@ impl Renderer {
         }
     }
 
+    /// Inserts a fence after the OpenGL commands issued so far. The
+    /// fence can be waited for later with [`Renderer::wait_fence`],
+    /// eg. to measure how far behind the CPU the GPU is, or to bound
+    /// the amount of frames queued up for the GPU, without blocking
+    /// at every frame like [`Renderer::synchronize`].
+    ///
+    /// Legacy (2.1) OpenGL doesn't have fences, so there the fence is
+    /// a placeholder, and waiting for it calls glFinish.
+    pub fn fence(&self) -> Fence {
+        if self.gl_state.legacy {
+            Fence { sync: None }
+        } else {
+            let sync = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+            Fence { sync: Some(sync) }
+        }
+    }
+
+    /// Waits until the GPU has executed the commands issued before
+    /// `fence`, or until `timeout` has passed. Returns true if the
+    /// fence was signaled. `None` waits indefinitely, and a timeout
+    /// of zero only checks if the fence has been signaled, without
+    /// blocking.
+    ///
+    /// On legacy (2.1) OpenGL, this is equivalent to glFinish
+    /// regardless of the timeout, and always returns true.
+    pub fn wait_fence(&self, fence: &Fence, timeout: Option<Duration>) -> bool {
+        let sync = match fence.sync {
+            Some(sync) => sync,
+            None => {
+                unsafe {
+                    gl::Finish();
+                }
+                return true;
+            }
+        };
+        let timeout = timeout.map_or(u64::MAX, |timeout| {
+            timeout.as_nanos().min(u64::MAX as u128) as u64
+        });
+        let status = unsafe { gl::ClientWaitSync(sync, gl::SYNC_FLUSH_COMMANDS_BIT, timeout) };
+        match status {
+            gl::ALREADY_SIGNALED | gl::CONDITION_SATISFIED => true,
+            gl::WAIT_FAILED => {
+                print_gl_errors("glClientWaitSync");
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the maximum width and height of textures supported by
+    /// the OpenGL driver (`GL_MAX_TEXTURE_SIZE`), or `i32::MAX` if
+    /// OpenGL hasn't been loaded.
+    pub fn max_texture_size(&self) -> i32 {
+        if !gl::GetIntegerv::is_loaded() {
+            return i32::MAX;
+        }
+        let mut size = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut size);
+        }
+        size
+    }
+
     /// Returns the OpenGL texture handle for the texture used by the
     /// draw call.
-    #[cfg(feature = "text")]
-    pub(crate) fn get_texture(&self, call_handle: &DrawCallHandle) -> GLuint {
-        self.calls[call_handle.0].texture
+    pub fn get_texture(&self, call_handle: &DrawCallHandle) -> GLuint {
+        self.calls[self.call_index(call_handle)].texture
+    }
+
+    /// Binds `texture` to the texture unit `unit` (1 to
+    /// [`MAX_EXTRA_TEXTURES`]) when rendering the draw call, in
+    /// addition to the draw call's own texture in unit 0, so that
+    /// custom shaders can sample multiple textures, eg. a color
+    /// texture and a mask for dissolve effects. The texture is
+    /// sampled with the `tex1`, `tex2`, etc. uniforms, see
+    /// [`Shaders`]. A `texture` of 0 removes the texture from the
+    /// unit.
+    ///
+    /// The texture can be from another draw call (see
+    /// [`Renderer::get_texture`]) or created outside of fae. Either
+    /// way, the draw call doesn't own it, so it must stay alive as
+    /// long as the draw call is used. Draw calls are only batched
+    /// together if their extra textures match.
+    ///
+    /// Note that OpenGL 2.1 drivers are only guaranteed to support 2
+    /// texture units in the fragment shader, and 3.3 drivers 16.
+    ///
+    /// Panics if `unit` is 0 or larger than `MAX_EXTRA_TEXTURES`.
+    pub fn set_draw_call_extra_texture(
+        &mut self,
+        call_handle: &DrawCallHandle,
+        unit: u32,
+        texture: GLuint,
+    ) {
+        assert!(
+            (1..=MAX_EXTRA_TEXTURES).contains(&unit),
+            "extra textures go in units 1 to {}, not {}",
+            MAX_EXTRA_TEXTURES,
+            unit
+        );
+        let index = self.call_index(call_handle);
+        self.calls[index].extra_textures[unit as usize - 1] = texture;
+    }
+
+    /// Offsets the depth of the draw call's quads with
+    /// glPolygonOffset when rendering, to keep them reliably in front
+    /// of (or behind) other layers drawn at the same `z`, which
+    /// would otherwise flicker because of z-fighting. See
+    /// [`Renderer::clear_polygon_offset`] for removing the offset.
+    ///
+    /// - `factor`: Scales the quads' depth slope, which is 0 for flat
+    ///   quads.
+    /// - `units`: Multiples of the smallest resolvable depth
+    ///   difference.
+    ///
+    /// Negative values move the quads towards the front.
+    /// `GL_POLYGON_OFFSET_FILL` is disabled again after the draw
+    /// call is drawn. Draw calls are only batched together if their
+    /// offsets match.
+    pub fn set_polygon_offset(&mut self, call_handle: &DrawCallHandle, factor: f32, units: f32) {
+        let index = self.call_index(call_handle);
+        self.calls[index].polygon_offset = Some((factor, units));
+    }
+
+    /// Removes the offset set with [`Renderer::set_polygon_offset`],
+    /// so the draw call's quads are drawn at their own depth again.
+    pub fn clear_polygon_offset(&mut self, call_handle: &DrawCallHandle) {
+        let index = self.call_index(call_handle);
+        self.calls[index].polygon_offset = None;
     }
 
     /// Saves the current OpenGL state for [`Renderer::gl_pop`] and
@@ -741,22 +2932,24 @@ impl Renderer {
             unsafe {
                 self.gl_state.depth_test = gl::IsEnabled(gl::DEPTH_TEST) != 0;
                 self.gl_state.blend = gl::IsEnabled(gl::BLEND) != 0;
+                // Without ARB_framebuffer_sRGB, GL 2.1 would error on this
+                if !self.gl_state.legacy || self.srgb {
+                    self.gl_state.framebuffer_srgb = gl::IsEnabled(gl::FRAMEBUFFER_SRGB) != 0;
+                }
                 gl::GetIntegerv(gl::DEPTH_FUNC, &mut self.gl_state.depth_func);
-                let mut src = 0;
-                let mut dst = 0;
-                gl::GetIntegerv(gl::BLEND_SRC, &mut src);
-                gl::GetIntegerv(gl::BLEND_DST, &mut dst);
-                self.gl_state.blend_func = (src, dst);
+                let (mut src_rgb, mut dst_rgb, mut src_alpha, mut dst_alpha) = (0, 0, 0, 0);
+                gl::GetIntegerv(gl::BLEND_SRC_RGB, &mut src_rgb);
+                gl::GetIntegerv(gl::BLEND_DST_RGB, &mut dst_rgb);
+                gl::GetIntegerv(gl::BLEND_SRC_ALPHA, &mut src_alpha);
+                gl::GetIntegerv(gl::BLEND_DST_ALPHA, &mut dst_alpha);
+                self.gl_state.blend_func = (src_rgb, dst_rgb, src_alpha, dst_alpha);
+                gl::GetIntegerv(gl::BLEND_EQUATION_RGB, &mut self.gl_state.blend_equation);
                 gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut self.gl_state.program);
                 if !self.gl_state.legacy {
                     gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut self.gl_state.vao);
                 }
                 gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut self.gl_state.texture);
                 gl::GetIntegerv(gl::ARRAY_BUFFER_BINDING, &mut self.gl_state.vbo);
-                gl::GetIntegerv(
-                    gl::ELEMENT_ARRAY_BUFFER_BINDING,
-                    &mut self.gl_state.element_buffer,
-                );
             }
 
             self.gl_state.pushed = true;
@@ -767,10 +2960,47 @@ impl Renderer {
 
     /// Restores the OpenGL state saved in [`Renderer::gl_push`].
     fn gl_pop(&mut self) {
-        if !self.preserve_gl_state {
+        if !self.preserve_gl_state || self.gl_state.held {
+            return;
+        }
+        self.restore_gl_state();
+    }
+
+    /// Saves the OpenGL state fae modifies (blending, depth testing,
+    /// sRGB framebuffer, and the bound program, VAO, texture and
+    /// vertex buffer), to be restored with
+    /// [`Renderer::pop_gl_state`]. The fae calls in between don't
+    /// save or restore the state themselves, so wrapping a whole
+    /// frame's worth of fae calls in these is cheaper than
+    /// `preserve_gl_state`, which saves and restores the state
+    /// around every call that touches OpenGL. Works regardless of
+    /// `preserve_gl_state`.
+    ///
+    /// Useful when embedding fae in an application which does its
+    /// own OpenGL rendering. Calling this again before popping does
+    /// nothing.
+    pub fn push_gl_state(&mut self) {
+        if self.gl_state.held {
+            return;
+        }
+        // Query the state now, even if an earlier push was left
+        // unrestored because of `preserve_gl_state`
+        self.gl_state.pushed = false;
+        self.gl_push();
+        self.gl_state.held = true;
+    }
+
+    /// Restores the OpenGL state saved in
+    /// [`Renderer::push_gl_state`].
+    pub fn pop_gl_state(&mut self) {
+        if !self.gl_state.held {
             return;
         }
+        self.gl_state.held = false;
+        self.restore_gl_state();
+    }
 
+    fn restore_gl_state(&mut self) {
         self.profiler.start("gl state pop");
         if self.gl_state.pushed {
             unsafe {
@@ -780,21 +3010,26 @@ impl Renderer {
                 if !self.gl_state.blend {
                     gl::Disable(gl::BLEND);
                 }
+                if self.gl_state.framebuffer_srgb {
+                    gl::Enable(gl::FRAMEBUFFER_SRGB);
+                } else if !self.gl_state.legacy || self.srgb {
+                    gl::Disable(gl::FRAMEBUFFER_SRGB);
+                }
                 gl::DepthFunc(self.gl_state.depth_func as GLuint);
-                gl::BlendFunc(
-                    self.gl_state.blend_func.0 as GLuint,
-                    self.gl_state.blend_func.1 as GLuint,
+                let (src_rgb, dst_rgb, src_alpha, dst_alpha) = self.gl_state.blend_func;
+                gl::BlendFuncSeparate(
+                    src_rgb as GLuint,
+                    dst_rgb as GLuint,
+                    src_alpha as GLuint,
+                    dst_alpha as GLuint,
                 );
+                gl::BlendEquation(self.gl_state.blend_equation as GLuint);
                 gl::UseProgram(self.gl_state.program as GLuint);
                 if !self.gl_state.legacy {
                     gl::BindVertexArray(self.gl_state.vao as GLuint);
                 }
                 gl::BindTexture(gl::TEXTURE_2D, self.gl_state.texture as GLuint);
                 gl::BindBuffer(gl::ARRAY_BUFFER, self.gl_state.vbo as GLuint);
-                gl::BindBuffer(
-                    gl::ELEMENT_ARRAY_BUFFER,
-                    self.gl_state.element_buffer as GLuint,
-                );
             }
             self.gl_state.pushed = false;
             print_gl_errors("after restoring OpenGL state");
@@ -811,35 +3046,82 @@ impl Drop for Renderer {
             return;
         }
         let legacy = self.gl_state.legacy;
-        for call in self.calls.iter() {
-            let ShaderProgram {
-                program,
-                vertex_shader,
-                fragment_shader,
-                ..
-            } = call.program;
-            let Attributes {
-                vbo,
-                vbo_static,
-                element_buffer,
-                vao,
-                ..
-            } = call.attributes;
+        for call in self.calls.iter().filter(|call| !call.deleted) {
+            delete_gl_resources(call, legacy);
+        }
+        if self.debug_callback.is_some() {
+            // The callback is about to be freed
             unsafe {
-                gl::DeleteShader(vertex_shader);
-                gl::DeleteShader(fragment_shader);
-                gl::DeleteProgram(program);
-                gl::DeleteTextures(1, [call.texture].as_ptr());
-                gl::DeleteBuffers(1, [vbo].as_ptr());
-                if !legacy {
-                    gl::DeleteBuffers(2, [vbo_static, element_buffer].as_ptr());
-                    gl::DeleteVertexArrays(1, [vao].as_ptr());
-                }
+                gl::DebugMessageCallback(debug_message_callback, std::ptr::null());
+                gl::Disable(gl::DEBUG_OUTPUT);
             }
         }
     }
 }
 
+extern "system" fn debug_message_callback(
+    _source: GLenum,
+    _gltype: GLenum,
+    _id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    user_param: *mut std::os::raw::c_void,
+) {
+    if user_param.is_null() || message.is_null() {
+        return;
+    }
+    let severity = match severity {
+        gl::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+        gl::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+        gl::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+        _ => DebugSeverity::Notification,
+    };
+    // Safety: user_param is the boxed DebugCallback owned by the
+    // Renderer, which resets the callback before freeing it.
+    let debug_callback = unsafe { &mut *(user_param as *mut DebugCallback) };
+    if severity < debug_callback.min_severity {
+        return;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(message as *const u8, length.max(0) as usize) };
+    (debug_callback.callback)(severity, &String::from_utf8_lossy(bytes));
+}
+
+fn delete_gl_resources(call: &DrawCall, legacy: bool) {
+    let ShaderProgram {
+        program,
+        vertex_shader,
+        fragment_shader,
+        ..
+    } = call.program;
+    let Attributes { vbo, vao, .. } = call.attributes;
+    unsafe {
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(fragment_shader);
+        gl::DeleteProgram(program);
+        if call.owns_texture {
+            gl::DeleteTextures(1, [call.texture].as_ptr());
+        }
+        gl::DeleteBuffers(1, [vbo].as_ptr());
+        if !legacy {
+            gl::DeleteVertexArrays(1, [vao].as_ptr());
+        }
+    }
+}
+
+/// The amount of textures that can be bound to a draw call in
+/// addition to its own, see [`Renderer::set_draw_call_extra_texture`].
+pub const MAX_EXTRA_TEXTURES: u32 = 7;
+
+/// 12 floats (3 for pos + 2 tex + 4 col + 3 rot) per vertex.
+const FLOATS_PER_VERTEX: usize = 12;
+/// Quads are made of two triangles.
+const FLOATS_PER_QUAD: usize = FLOATS_PER_VERTEX * 6;
+
+/// How many quads make up a soft shadow in
+/// [`Renderer::draw_quad_with_shadow`].
+const SHADOW_LAYERS: u32 = 4;
+
 const DEFAULT_QUAD_SHADERS: Shaders = Shaders {
     vertex_shader_110: include_str!("shaders/legacy/texquad.vert"),
     fragment_shader_110: include_str!("shaders/legacy/texquad.frag"),
@@ -848,23 +3130,18 @@ const DEFAULT_QUAD_SHADERS: Shaders = Shaders {
 };
 
 #[inline]
-fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderProgram {
-    let print_shader_error = |shader, shader_type| unsafe {
+fn create_program(vert_source: &str, frag_source: &str) -> Result<ShaderProgram, FaeError> {
+    let get_shader_error = |shader, shader_type| unsafe {
         let mut compilation_status = 0;
         gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut compilation_status);
         if compilation_status as u8 != gl::TRUE {
-            let mut info = [0; 1024];
-            gl::GetShaderInfoLog(shader, 1024, ptr::null_mut(), info.as_mut_ptr());
-
-            let error_msg = format!(
-                "Shader ({}) compilation failed:\n{}",
+            Some(FaeError::ShaderCompile(format!(
+                "{} shader:\n{}",
                 shader_type,
-                String::from_utf8_lossy(&mem::transmute::<[i8; 1024], [u8; 1024]>(info)[..])
-            );
-            if cfg!(debug_assertions) {
-                panic!("{}", error_msg);
-            }
-            eprintln!("{}", error_msg);
+                get_shader_info_log(shader)
+            )))
+        } else {
+            None
         }
     };
 
@@ -883,7 +3160,6 @@ fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderP
             [vert_source.len() as GLint].as_ptr(),
         );
         gl::CompileShader(vertex_shader);
-        print_shader_error(vertex_shader, "vertex");
 
         fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
         gl::ShaderSource(
@@ -893,25 +3169,28 @@ fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderP
             [frag_source.len() as GLint].as_ptr(),
         );
         gl::CompileShader(fragment_shader);
-        print_shader_error(fragment_shader, "fragment");
-
-        gl::AttachShader(program, vertex_shader);
-        gl::AttachShader(program, fragment_shader);
-        gl::LinkProgram(program);
-        let mut link_status = 0;
-        gl::GetProgramiv(program, gl::LINK_STATUS, &mut link_status);
-        if link_status as u8 != gl::TRUE {
-            let mut info = [0; 1024];
-            gl::GetProgramInfoLog(program, 1024, ptr::null_mut(), info.as_mut_ptr());
-
-            let error_msg = format!(
-                "Program linking failed:\n{}",
-                String::from_utf8_lossy(&mem::transmute::<[i8; 1024], [u8; 1024]>(info)[..])
-            );
-            if cfg!(debug_assertions) {
-                panic!("{}", error_msg);
+
+        let mut error = get_shader_error(vertex_shader, "vertex")
+            .or_else(|| get_shader_error(fragment_shader, "fragment"));
+        if error.is_none() {
+            gl::AttachShader(program, vertex_shader);
+            gl::AttachShader(program, fragment_shader);
+            gl::LinkProgram(program);
+            let mut link_status = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut link_status);
+            if link_status as u8 != gl::TRUE {
+                error = Some(FaeError::ShaderCompile(format!(
+                    "linking:\n{}",
+                    get_program_info_log(program)
+                )));
             }
-            eprintln!("{}", error_msg);
+        }
+        if let Some(error) = error {
+            log::error!("{}", error);
+            gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteProgram(program);
+            return Err(error);
         }
         print_gl_errors("after shader program creation");
     }
@@ -921,9 +3200,6 @@ fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderP
     let texcoord_attrib_location;
     let color_attrib_location;
     let rotation_attrib_location;
-    let mut depth_attrib_location = 0;
-    let mut shared_position_attrib_location = 0;
-    let mut shared_texcoord_attrib_location = 0;
     unsafe {
         gl::UseProgram(program);
         projection_matrix_location =
@@ -937,19 +3213,19 @@ fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderP
         rotation_attrib_location =
             gl::GetAttribLocation(program, "rotation\0".as_ptr() as *const _) as GLuint;
 
-        if !legacy {
-            depth_attrib_location =
-                gl::GetAttribLocation(program, "depth\0".as_ptr() as *const _) as GLuint;
-            shared_position_attrib_location =
-                gl::GetAttribLocation(program, "shared_position\0".as_ptr() as *const _) as GLuint;
-            shared_texcoord_attrib_location =
-                gl::GetAttribLocation(program, "shared_texcoord\0".as_ptr() as *const _) as GLuint;
+        // Point the samplers of the extra textures to their units
+        for unit in 1..=MAX_EXTRA_TEXTURES {
+            let name = format!("tex{}\0", unit);
+            let location = gl::GetUniformLocation(program, name.as_ptr() as *const _);
+            if location != -1 {
+                gl::Uniform1i(location, unit as GLint);
+            }
         }
 
         print_gl_errors("after searching for attribute locations");
     }
 
-    ShaderProgram {
+    Ok(ShaderProgram {
         program,
         vertex_shader,
         fragment_shader,
@@ -958,86 +3234,43 @@ fn create_program(vert_source: &str, frag_source: &str, legacy: bool) -> ShaderP
         texcoord_attrib_location,
         color_attrib_location,
         rotation_attrib_location,
-        depth_attrib_location,
-        shared_position_attrib_location,
-        shared_texcoord_attrib_location,
-    }
+    })
 }
 
 #[inline]
-fn create_attributes(opengl21: bool, program: ShaderProgram) -> Attributes {
-    let mut vao = 0;
-    if !opengl21 {
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::BindVertexArray(vao);
-        }
-    }
-
-    let mut vbo_static = 0;
-    let mut element_buffer = 0;
-    if !opengl21 {
-        unsafe {
-            gl::GenBuffers(1, &mut vbo_static);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo_static);
-            enable_vertex_attribs(&[
-                (program.shared_position_attrib_location, 2),
-                (program.shared_texcoord_attrib_location, 2),
-            ]);
-            // This is the vertices of two triangles that form a quad,
-            // interleaved in a (pos x, pos y, tex x, tex y)
-            // arrangement.
-            let static_quad_vertices: [f32; 16] = [
-                0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0,
-            ];
-            let len = (mem::size_of::<f32>() * static_quad_vertices.len()) as isize;
-            let ptr = static_quad_vertices.as_ptr() as *const _;
-            gl::BufferData(gl::ARRAY_BUFFER, len, ptr, gl::STATIC_DRAW);
-
-            gl::GenBuffers(1, &mut element_buffer);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, element_buffer);
-            let elements: [u8; 6] = [0, 1, 2, 0, 2, 3];
-            let len = (mem::size_of::<f32>() * elements.len()) as isize;
-            let ptr = elements.as_ptr() as *const _;
-            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, len, ptr, gl::STATIC_DRAW);
-        }
-    }
-
-    let mut vbo = 0;
-    unsafe {
-        gl::GenBuffers(1, &mut vbo);
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-    }
-
-    if !opengl21 {
-        unsafe {
-            enable_vertex_attribs(&[
-                (program.position_attrib_location, 4),
-                (program.texcoord_attrib_location, 4),
-                (program.color_attrib_location, 4),
-                (program.rotation_attrib_location, 3),
-                (program.depth_attrib_location, 1),
-            ]);
-            gl::VertexAttribDivisor(program.position_attrib_location, 1);
-            gl::VertexAttribDivisor(program.texcoord_attrib_location, 1);
-            gl::VertexAttribDivisor(program.color_attrib_location, 1);
-            gl::VertexAttribDivisor(program.rotation_attrib_location, 1);
-            gl::VertexAttribDivisor(program.depth_attrib_location, 1);
-        }
-    }
-    print_gl_errors("after attribute creation");
+/// Returns the whole info log of the shader.
+unsafe fn get_shader_info_log(shader: GLuint) -> String {
+    let mut length = 0;
+    gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+    let mut info = vec![0u8; length.max(1) as usize];
+    let mut written = 0;
+    gl::GetShaderInfoLog(
+        shader,
+        info.len() as GLsizei,
+        &mut written,
+        info.as_mut_ptr() as *mut GLchar,
+    );
+    info.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&info).into_owned()
+}
 
-    Attributes {
-        vao,
-        vbo,
-        vbo_static,
-        element_buffer,
-        vbo_data: Vec::new(),
-        allocated_vbo_data_size: 0,
-    }
+/// Returns the whole info log of the program.
+unsafe fn get_program_info_log(program: GLuint) -> String {
+    let mut length = 0;
+    gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut length);
+    let mut info = vec![0u8; length.max(1) as usize];
+    let mut written = 0;
+    gl::GetProgramInfoLog(
+        program,
+        info.len() as GLsizei,
+        &mut written,
+        info.as_mut_ptr() as *mut GLchar,
+    );
+    info.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&info).into_owned()
 }
-#[inline]
-fn create_triangle_attributes(opengl21: bool, program: ShaderProgram,verticies:[(f32,f32);3]) -> Attributes {
+
+fn create_attributes(opengl21: bool, program: ShaderProgram) -> Attributes {
     let mut vao = 0;
     if !opengl21 {
         unsafe {
@@ -1046,40 +3279,6 @@ fn create_triangle_attributes(opengl21: bool, program: ShaderProgram,verticies:[
         }
     }
 
-    let mut vbo_static = 0;
-    let mut element_buffer = 0;
-    if !opengl21 {
-        unsafe {
-            gl::GenBuffers(1, &mut vbo_static);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo_static);
-            enable_vertex_attribs(&[
-                (program.shared_position_attrib_location, 2),
-                (program.shared_texcoord_attrib_location, 2),
-            ]);
-            // This is the vertices of two triangles that form a quad,
-            // interleaved in a (pos x, pos y, tex x, tex y)
-            // arrangement.
-            let static_tri_verticies:[f32;12] = [
-                // Pos 1x      Pos y1         Tex x1         Tex y1
-                verticies[0].0,verticies[0].1,verticies[0].0,verticies[0].1,
-                // Pos x2      Pos y2         Tex x2         Tex y2
-                verticies[1].0,verticies[1].1,verticies[1].0,verticies[1].1,
-                // Pos x3      Pos y3         Tex x3         Tex y3
-                verticies[2].0,verticies[2].1,verticies[2].0,verticies[2].1,
-            ];
-            let len = (mem::size_of::<f32>() * static_tri_verticies.len()) as isize;
-            let ptr = static_tri_verticies.as_ptr() as *const _;
-            gl::BufferData(gl::ARRAY_BUFFER, len, ptr, gl::STATIC_DRAW);
-
-            gl::GenBuffers(1, &mut element_buffer);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, element_buffer);
-            let elements: [u8; 6] = [0, 1, 2, 0, 2, 3];
-            let len = (mem::size_of::<f32>() * elements.len()) as isize;
-            let ptr = elements.as_ptr() as *const _;
-            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, len, ptr, gl::STATIC_DRAW);
-        }
-    }
-
     let mut vbo = 0;
     unsafe {
         gl::GenBuffers(1, &mut vbo);
@@ -1089,17 +3288,11 @@ fn create_triangle_attributes(opengl21: bool, program: ShaderProgram,verticies:[
     if !opengl21 {
         unsafe {
             enable_vertex_attribs(&[
-                (program.position_attrib_location, 4),
-                (program.texcoord_attrib_location, 4),
+                (program.position_attrib_location, 3),
+                (program.texcoord_attrib_location, 2),
                 (program.color_attrib_location, 4),
                 (program.rotation_attrib_location, 3),
-                (program.depth_attrib_location, 1),
             ]);
-            gl::VertexAttribDivisor(program.position_attrib_location, 1);
-            gl::VertexAttribDivisor(program.texcoord_attrib_location, 1);
-            gl::VertexAttribDivisor(program.color_attrib_location, 1);
-            gl::VertexAttribDivisor(program.rotation_attrib_location, 1);
-            gl::VertexAttribDivisor(program.depth_attrib_location, 1);
         }
     }
     print_gl_errors("after attribute creation");
@@ -1107,8 +3300,6 @@ fn create_triangle_attributes(opengl21: bool, program: ShaderProgram,verticies:[
     Attributes {
         vao,
         vbo,
-        vbo_static,
-        element_buffer,
         vbo_data: Vec::new(),
         allocated_vbo_data_size: 0,
     }
@@ -1159,6 +3350,207 @@ fn create_texture(min_filter: GLint, mag_filter: GLint) -> GLuint {
     tex
 }
 
+/// Sets the blending and depth function state for drawing the call.
+unsafe fn set_blending(call: &DrawCall) {
+    if call.blend {
+        gl::Enable(gl::BLEND);
+        match call.blend_mode {
+            // The alpha channel is blended like premultiplied alpha, so
+            // that transparent framebuffers end up with correct alpha
+            BlendMode::Alpha => gl::BlendFuncSeparate(
+                gl::SRC_ALPHA,
+                gl::ONE_MINUS_SRC_ALPHA,
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Premultiplied => gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
+        }
+        gl::BlendEquation(call.blend_equation.gl_equation());
+        gl::DepthFunc(gl::LEQUAL);
+    } else {
+        gl::Disable(gl::BLEND);
+        gl::DepthFunc(gl::LESS);
+    }
+}
+
+/// Returns an error explaining what to do if the OpenGL functions
+/// haven't been loaded yet, as calling them would panic with a
+/// generic message.
+fn check_gl_loaded() -> Result<(), FaeError> {
+    if gl::Viewport::is_loaded() {
+        Ok(())
+    } else {
+        Err(FaeError::ContextCreation(
+            "the OpenGL functions haven't been loaded: create the Window first, or load them yourself with fae::gl::load_with".to_owned(),
+        ))
+    }
+}
+
+/// Enables the polygon offset of the call, if it has one. Should be
+/// followed by [`disable_polygon_offset`] after drawing.
+unsafe fn enable_polygon_offset(call: &DrawCall) {
+    if let Some((factor, units)) = call.polygon_offset {
+        gl::Enable(gl::POLYGON_OFFSET_FILL);
+        gl::PolygonOffset(factor, units);
+    }
+}
+
+/// Disables the polygon offset enabled by [`enable_polygon_offset`].
+unsafe fn disable_polygon_offset(call: &DrawCall) {
+    if call.polygon_offset.is_some() {
+        gl::Disable(gl::POLYGON_OFFSET_FILL);
+    }
+}
+
+/// Draws the vertices with the draw call's textures, shaders and
+/// blending, by streaming them into the call's vertex buffer. Used
+/// for the quads of stencil masks, which aren't in the call's own
+/// queue. Returns the amount of vertices drawn.
+unsafe fn draw_vertices(
+    call: &mut DrawCall,
+    vertices: &[f32],
+    legacy: bool,
+    matrix: &[f32; 16],
+) -> usize {
+    set_blending(call);
+    gl::UseProgram(call.program.program);
+    gl::UniformMatrix4fv(
+        call.program.projection_matrix_location,
+        1,
+        gl::FALSE,
+        matrix.as_ptr(),
+    );
+    if !legacy {
+        gl::BindVertexArray(call.attributes.vao);
+    }
+    gl::BindTexture(gl::TEXTURE_2D, call.texture);
+    bind_extra_textures(&call.extra_textures, false);
+    gl::BindBuffer(gl::ARRAY_BUFFER, call.attributes.vbo);
+
+    let len = mem::size_of_val(vertices) as isize;
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        len,
+        vertices.as_ptr() as *const _,
+        gl::STREAM_DRAW,
+    );
+    call.attributes.allocated_vbo_data_size = len;
+    // The buffer of a static call was overwritten, so its quads need
+    // to be uploaded again
+    call.static_dirty |= call.is_static;
+
+    let vertex_count = vertices.len() / FLOATS_PER_VERTEX;
+    enable_polygon_offset(call);
+    if legacy {
+        enable_vertex_attribs(&[
+            (call.program.position_attrib_location, 3),
+            (call.program.texcoord_attrib_location, 2),
+            (call.program.color_attrib_location, 4),
+            (call.program.rotation_attrib_location, 3),
+        ]);
+        gl::DrawArrays(gl::TRIANGLES, 0, vertex_count as i32);
+        disable_vertex_attribs(&[
+            call.program.position_attrib_location,
+            call.program.texcoord_attrib_location,
+            call.program.color_attrib_location,
+            call.program.rotation_attrib_location,
+        ]);
+    } else {
+        gl::DrawArrays(gl::TRIANGLES, 0, vertex_count as i32);
+    }
+    disable_polygon_offset(call);
+    bind_extra_textures(&call.extra_textures, true);
+    vertex_count
+}
+
+/// Binds the extra textures to their texture units, or if `unbind`
+/// is true, binds 0 to the units that have extra textures. Leaves
+/// unit 0 active either way.
+unsafe fn bind_extra_textures(extra_textures: &[TextureHandle], unbind: bool) {
+    let mut bound_any = false;
+    for (i, &texture) in extra_textures.iter().enumerate() {
+        if texture != 0 {
+            gl::ActiveTexture(gl::TEXTURE1 + i as GLenum);
+            gl::BindTexture(gl::TEXTURE_2D, if unbind { 0 } else { texture });
+            bound_any = true;
+        }
+    }
+    if bound_any {
+        gl::ActiveTexture(gl::TEXTURE0);
+    }
+}
+
+/// Returns true if the `GL_MIN` and `GL_MAX` blend equations are
+/// available. They're core in desktop OpenGL since 1.4, but OpenGL ES
+/// 2.0 requires an extension.
+fn blend_minmax_supported(legacy: bool) -> bool {
+    if !legacy {
+        return true;
+    }
+    let version = unsafe { gl::GetString(gl::VERSION) };
+    let is_es = !version.is_null()
+        && unsafe { std::ffi::CStr::from_ptr(version as *const _) }
+            .to_bytes()
+            .starts_with(b"OpenGL ES");
+    !is_es || extension_supported(legacy, "GL_EXT_blend_minmax")
+}
+
+/// Returns whether `GL_FRAMEBUFFER_SRGB` can be toggled: always with
+/// modern OpenGL, and with the sRGB framebuffer extensions with
+/// legacy OpenGL. Without loaded OpenGL functions, only modern
+/// OpenGL is assumed to support it.
+fn framebuffer_srgb_supported(legacy: bool) -> bool {
+    if !legacy {
+        return true;
+    }
+    gl::GetString::is_loaded()
+        && [
+            "GL_ARB_framebuffer_sRGB",
+            "GL_EXT_framebuffer_sRGB",
+            "GL_EXT_sRGB_write_control",
+        ]
+        .iter()
+        .any(|name| extension_supported(legacy, name))
+}
+
+/// Sets the anisotropy of the currently bound texture, if the
+/// extension for it is supported.
+fn set_anisotropy(legacy: bool, anisotropy: f32) {
+    if !extension_supported(legacy, "GL_EXT_texture_filter_anisotropic") {
+        return;
+    }
+    let mut max_anisotropy = 1.0;
+    unsafe {
+        gl::GetFloatv(gl::MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_anisotropy);
+        gl::TexParameterf(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MAX_ANISOTROPY_EXT,
+            anisotropy.min(max_anisotropy),
+        );
+    }
+    print_gl_errors("after setting anisotropy");
+}
+
+fn extension_supported(legacy: bool, name: &str) -> bool {
+    use std::ffi::CStr;
+    let to_str = |ptr: *const GLubyte| unsafe { CStr::from_ptr(ptr as *const _) }.to_bytes();
+    if legacy {
+        // The extension string is a space-separated list
+        let ptr = unsafe { gl::GetString(gl::EXTENSIONS) };
+        !ptr.is_null()
+            && to_str(ptr)
+                .split(|&c| c == b' ')
+                .any(|ext| ext == name.as_bytes())
+    } else {
+        let mut count = 0;
+        unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count) };
+        (0..count as GLuint).any(|i| {
+            let ptr = unsafe { gl::GetStringi(gl::EXTENSIONS, i) };
+            !ptr.is_null() && to_str(ptr) == name.as_bytes()
+        })
+    }
+}
+
 #[inline]
 fn insert_texture(tex: GLuint, format: GLuint, w: GLint, h: GLint, pixels: &[u8]) {
     unsafe {
@@ -1178,7 +3570,179 @@ fn insert_texture(tex: GLuint, format: GLuint, w: GLint, h: GLint, pixels: &[u8]
     print_gl_errors("after inserting a texture");
 }
 
-// TODO: Change this to print out to env_logger or such, not stderr
+/// Creates the projection matrix which maps logical pixel
+/// coordinates (origin at the top-left, y pointing down) into
+/// normalized device coordinates. The shaders multiply vertices with
+/// this matrix from the left, so it's row-major from their point of
+/// view.
+pub(crate) fn projection_matrix(width: f32, height: f32, depth_range: (f32, f32)) -> [f32; 16] {
+    let (near, far) = depth_range;
+    let m00 = 2.0 / width;
+    let m11 = -2.0 / height;
+    let m22 = 2.0 / (far - near);
+    let m23 = -(far + near) / (far - near);
+    [
+        m00, 0.0, 0.0, -1.0, 0.0, m11, 0.0, 1.0, 0.0, 0.0, m22, m23, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/// Transforms `point` with the x and y rows of the (orthographic)
+/// `matrix`, and maps the resulting normalized device coordinates
+/// onto a screen of `size`, with y pointing down.
+pub(crate) fn world_to_screen(
+    matrix: &[f32; 16],
+    size: (f32, f32),
+    point: (f32, f32),
+) -> (f32, f32) {
+    let (x, y) = point;
+    let ndc_x = matrix[0] * x + matrix[1] * y + matrix[3];
+    let ndc_y = matrix[4] * x + matrix[5] * y + matrix[7];
+    ((ndc_x + 1.0) / 2.0 * size.0, (1.0 - ndc_y) / 2.0 * size.1)
+}
+
+/// The inverse of [`world_to_screen`].
+pub(crate) fn screen_to_world(
+    matrix: &[f32; 16],
+    size: (f32, f32),
+    point: (f32, f32),
+) -> (f32, f32) {
+    let (x, y) = point;
+    let dx = x / size.0 * 2.0 - 1.0 - matrix[3];
+    let dy = 1.0 - y / size.1 * 2.0 - matrix[7];
+    let (a, b, c, d) = (matrix[0], matrix[1], matrix[4], matrix[5]);
+    let det = a * d - b * c;
+    ((d * dx - b * dy) / det, (a * dy - c * dx) / det)
+}
+
+/// Splits `vertex_count` vertices into the (first, count) ranges of
+/// the glDrawArrays calls that draw them, with at most `max_quads`
+/// quads' worth of vertices each, or all in one range if there's no
+/// limit. The ranges never split triangles.
+pub(crate) fn draw_ranges(
+    vertex_count: usize,
+    max_quads: Option<usize>,
+) -> impl Iterator<Item = (i32, i32)> {
+    let max_vertices = max_quads.map_or(vertex_count, |quads| quads.max(1) * 6);
+    (0..vertex_count)
+        .step_by(max_vertices.max(1))
+        .map(move |first| (first as i32, max_vertices.min(vertex_count - first) as i32))
+}
+
+/// Merges the triangles queued in the draw calls (given as call
+/// index and vertex data pairs) into runs of consecutive triangles
+/// from the same call, ordered back to front, so that drawing the
+/// runs in order blends them correctly. Equally deep triangles keep
+/// the order they're given in.
+pub(crate) fn interleave_by_depth(queues: &[(usize, &[f32])]) -> Vec<(usize, Vec<f32>)> {
+    const FLOATS_PER_TRIANGLE: usize = FLOATS_PER_VERTEX * 3;
+    let mut triangles = queues
+        .iter()
+        .flat_map(|&(i, vertices)| {
+            vertices
+                .chunks_exact(FLOATS_PER_TRIANGLE)
+                .map(move |triangle| (i, triangle))
+        })
+        .collect::<Vec<_>>();
+    // Stable, so submission order is kept for equal depths. NaN
+    // depths are sorted to the back instead of panicking.
+    triangles.sort_by(|(_, a), (_, b)| b[2].total_cmp(&a[2]));
+
+    let mut runs: Vec<(usize, Vec<f32>)> = Vec::new();
+    for (i, triangle) in triangles {
+        match runs.last_mut() {
+            Some((run_call, vertices)) if *run_call == i => vertices.extend_from_slice(triangle),
+            _ => runs.push((i, triangle.to_vec())),
+        }
+    }
+    runs
+}
+
+/// Returns whether any of the vertices' triangles could overlap the
+/// rectangle. Rotated vertices are treated as covering the whole
+/// circle around their pivot. Rectangle arrangement: (left, top,
+/// right, bottom)
+pub(crate) fn vertices_overlap_rect(
+    vertices: &[f32],
+    (left, top, right, bottom): (f32, f32, f32, f32),
+) -> bool {
+    let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+    let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for vertex in vertices.chunks_exact(FLOATS_PER_VERTEX) {
+        let (vx, vy, rads, pivot_x, pivot_y) =
+            (vertex[0], vertex[1], vertex[9], vertex[10], vertex[11]);
+        let (x0, y0, x1, y1) = if rads == 0.0 {
+            (vx, vy, vx, vy)
+        } else {
+            let radius = ((vx - pivot_x).powi(2) + (vy - pivot_y).powi(2)).sqrt();
+            (
+                pivot_x - radius,
+                pivot_y - radius,
+                pivot_x + radius,
+                pivot_y + radius,
+            )
+        };
+        min_x = min_x.min(x0);
+        min_y = min_y.min(y0);
+        max_x = max_x.max(x1);
+        max_y = max_y.max(y1);
+    }
+    min_x < right && max_x > left && min_y < bottom && max_y > top
+}
+
+/// Returns the largest rectangle sized an integer multiple of
+/// `resolution` that fits in `area`, centered in it. If the
+/// resolution doesn't fit even once, it's scaled down to fit.
+pub(crate) fn letterbox(
+    (x, y, w, h): (i32, i32, i32, i32),
+    resolution: (u32, u32),
+) -> (i32, i32, i32, i32) {
+    let (res_w, res_h) = (resolution.0.max(1) as f32, resolution.1.max(1) as f32);
+    let fit = (w as f32 / res_w).min(h as f32 / res_h);
+    let scale = if fit >= 1.0 { fit.floor() } else { fit };
+    let (scaled_w, scaled_h) = (
+        (res_w * scale).round() as i32,
+        (res_h * scale).round() as i32,
+    );
+    (
+        x + (w - scaled_w) / 2,
+        y + (h - scaled_h) / 2,
+        scaled_w,
+        scaled_h,
+    )
+}
+
+/// Clears the rectangle (in OpenGL's window coordinates) of the
+/// framebuffer with the color, by scissoring the clear, which is
+/// what glClear doesn't do by itself.
+fn clear_rect((x, y, w, h): (i32, i32, i32, i32), (r, g, b, a): (f32, f32, f32, f32)) {
+    unsafe {
+        let scissor_test = gl::IsEnabled(gl::SCISSOR_TEST) != 0;
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(x, y, w, h);
+        gl::ClearColor(r, g, b, a);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        if !scissor_test {
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+    }
+}
+
+/// Rounds the position and the size of the rectangle to whole pixels.
+fn snap_to_pixels((x0, y0, x1, y1): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+    let (x, y) = (x0.round(), y0.round());
+    (x, y, x + (x1 - x0).round(), y + (y1 - y0).round())
+}
+
+/// Converts an sRGB color component into linear space.
+#[inline]
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
 fn print_gl_errors(context: &str) {
     let mut error = unsafe { gl::GetError() };
     while error != gl::NO_ERROR {
@@ -1186,7 +3750,7 @@ fn print_gl_errors(context: &str) {
         if cfg!(debug_assertions) {
             panic!("{}", error_msg);
         }
-        eprintln!("{}", error_msg);
+        log::error!("{}", error_msg);
         error = unsafe { gl::GetError() };
     }
 }
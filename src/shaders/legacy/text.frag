@@ -5,8 +5,12 @@ varying vec4 frag_color;
 uniform sampler2D tex;
 
 void main(void) {
-  vec4 out_color = frag_color;
-  out_color.a = texture2D(tex, frag_texcoord).r;
+  // Premultiplied alpha, drawn with premultiplied alpha blending, so
+  // that anti-aliased edges don't darken. The alpha of the color
+  // still applies, for translucent text.
+  float coverage = texture2D(tex, frag_texcoord).r;
+  float alpha = frag_color.a * coverage;
+  vec4 out_color = vec4(frag_color.rgb * alpha, alpha);
   if (out_color.a < 0.01) {
     discard;
   }
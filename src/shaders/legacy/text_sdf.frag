@@ -0,0 +1,20 @@
+#version 110
+
+varying vec2 frag_texcoord;
+varying vec4 frag_color;
+uniform sampler2D tex;
+
+void main(void) {
+  // The glyph cache contains signed distance fields, where 0.5 is
+  // the edge of the glyph. Smoothing over the distance covered by
+  // one pixel keeps the edges crisp, but anti-aliased, at any scale.
+  float distance = texture2D(tex, frag_texcoord).r;
+  float smoothing = max(fwidth(distance) * 0.5, 0.001);
+  float coverage = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+  float alpha = frag_color.a * coverage;
+  vec4 out_color = vec4(frag_color.rgb * alpha, alpha);
+  if (out_color.a < 0.01) {
+    discard;
+  }
+  gl_FragColor = out_color;
+}
@@ -6,9 +6,12 @@ in vec4 frag_color;
 uniform sampler2D tex;
 
 void main(void) {
-  vec4 color = frag_color;
-  color.a = texture(tex, frag_texcoord).r;
-  out_color = color;
+  // Premultiplied alpha, drawn with premultiplied alpha blending, so
+  // that anti-aliased edges don't darken. The alpha of the color
+  // still applies, for translucent text.
+  float coverage = texture(tex, frag_texcoord).r;
+  float alpha = frag_color.a * coverage;
+  out_color = vec4(frag_color.rgb * alpha, alpha);
   if (out_color.a < 0.01) {
     discard;
   }
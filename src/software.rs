@@ -0,0 +1,353 @@
+//! Software rendering: drawing quads and text into an [`Image`] on
+//! the CPU, without a window or an OpenGL context.
+//!
+//! The [`SoftwareRenderer`] mirrors the drawing functions of the
+//! [`Renderer`](../renderer/struct.Renderer.html), but instead of
+//! sending the quads to the GPU, [`SoftwareRenderer::render`]
+//! rasterizes them one by one, from the furthest to the closest
+//! (painter's algorithm), and blends them into an RGBA image. It's a
+//! lot slower, but the results don't depend on the GPU or its
+//! drivers, so it's useful for batch jobs like generating thumbnails
+//! on a server, and for golden-image tests.
+
+use crate::error::FaeError;
+use crate::gl;
+use crate::image::Image;
+#[cfg(feature = "text")]
+use crate::text::{Alignment, TextRenderer};
+use std::mem;
+
+/// A texture of a [`SoftwareRenderer`], the software counterpart of
+/// a draw call. Created with [`SoftwareRenderer::create_texture`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftwareTexture {
+    index: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TextureRef {
+    Texture(usize),
+    /// A texture rasterized from text during this frame.
+    #[cfg_attr(not(feature = "text"), allow(dead_code))]
+    Text(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SoftwareQuad {
+    coords: (f32, f32, f32, f32),
+    uvs: [(f32, f32); 4],
+    color: (f32, f32, f32, f32),
+    /// The rotation of the quad: (radians, pivot x, pivot y), where
+    /// the pivot is in absolute pixels.
+    rotation: (f32, f32, f32),
+    clip_area: Option<(f32, f32, f32, f32)>,
+    depth: f32,
+    texture: TextureRef,
+}
+
+/// Draws quads and text into an RGBA [`Image`] on the CPU. See the
+/// [module-level documentation](index.html).
+///
+/// The coordinates are in pixels of the resulting image, with the
+/// origin at the top-left corner, so they correspond to the logical
+/// pixels of a [`Renderer`](../renderer/struct.Renderer.html) with a
+/// DPI factor of 1.
+pub struct SoftwareRenderer {
+    width: i32,
+    height: i32,
+    clear_color: (f32, f32, f32, f32),
+    /// The textures, converted to RGBA.
+    textures: Vec<Image>,
+    text_textures: Vec<Image>,
+    quads: Vec<SoftwareQuad>,
+    #[cfg(feature = "text")]
+    text_renderer: Option<TextRenderer>,
+}
+
+impl SoftwareRenderer {
+    /// Creates a new software renderer, which renders images of the
+    /// given size.
+    pub fn new(width: i32, height: i32) -> SoftwareRenderer {
+        SoftwareRenderer {
+            width: width.max(0),
+            height: height.max(0),
+            clear_color: (0.0, 0.0, 0.0, 0.0),
+            textures: Vec::new(),
+            text_textures: Vec::new(),
+            quads: Vec::new(),
+            #[cfg(feature = "text")]
+            text_renderer: None,
+        }
+    }
+
+    /// Sets the color the image is filled with before drawing. The
+    /// default is transparent black.
+    pub fn set_clear_color(&mut self, color: (f32, f32, f32, f32)) {
+        self.clear_color = color;
+    }
+
+    /// Creates a texture to draw quads with, like
+    /// [`Renderer::create_draw_call`](../renderer/struct.Renderer.html#method.create_draw_call).
+    /// The image can be `GL_RGBA` or `GL_RGB`, or `GL_RED`, in which
+    /// case the channel is treated as the coverage of a white image,
+    /// like the glyph cache of the text renderer.
+    ///
+    /// Results in a [`FaeError::Unsupported`](../enum.FaeError.html)
+    /// if the image has some other format.
+    pub fn create_texture(&mut self, image: &Image) -> Result<SoftwareTexture, FaeError> {
+        let rgba = to_rgba(image).ok_or_else(|| {
+            FaeError::Unsupported(format!(
+                "the software renderer can't draw images with the format 0x{:X}",
+                image.format
+            ))
+        })?;
+        self.textures.push(rgba);
+        Ok(SoftwareTexture {
+            index: self.textures.len() - 1,
+        })
+    }
+
+    /// Draws a textured rectangle. Works exactly like
+    /// [`Renderer::draw_quad`](../renderer/struct.Renderer.html#method.draw_quad),
+    /// including the depth: quads with smaller depths end up in front.
+    #[inline]
+    pub fn draw_quad(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        texcoords: (f32, f32, f32, f32),
+        color: (f32, f32, f32, f32),
+        rotation: (f32, f32, f32),
+        depth: f32,
+        texture: &SoftwareTexture,
+    ) {
+        let (tx0, ty0, tx1, ty1) = texcoords;
+        self.draw_quad_with_uvs(
+            coords,
+            [(tx0, ty0), (tx1, ty0), (tx1, ty1), (tx0, ty1)],
+            color,
+            rotation,
+            depth,
+            texture,
+        );
+    }
+
+    /// Draws a textured rectangle with explicit texture coordinates
+    /// for each corner, like
+    /// [`Renderer::draw_quad_with_uvs`](../renderer/struct.Renderer.html#method.draw_quad_with_uvs).
+    #[inline]
+    pub fn draw_quad_with_uvs(
+        &mut self,
+        coords: (f32, f32, f32, f32),
+        uvs: [(f32, f32); 4],
+        color: (f32, f32, f32, f32),
+        rotation: (f32, f32, f32),
+        depth: f32,
+        texture: &SoftwareTexture,
+    ) {
+        let (rads, pivot_x, pivot_y) = rotation;
+        self.quads.push(SoftwareQuad {
+            coords,
+            uvs,
+            color,
+            rotation: (rads, pivot_x + coords.0, pivot_y + coords.1),
+            clip_area: None,
+            depth,
+            texture: TextureRef::Texture(texture.index),
+        });
+    }
+
+    /// Sets the font used by [`SoftwareRenderer::draw_text`].
+    ///
+    /// - `font_data`: The bytes that consist a .ttf file, like in
+    ///   [`TextRenderer::create`](../text/struct.TextRenderer.html#method.create).
+    ///
+    /// Results in a [`FaeError::FontLoad`](../enum.FaeError.html) if
+    /// the font can't be loaded.
+    #[cfg(feature = "text")]
+    pub fn set_font(&mut self, font_data: Vec<u8>) -> Result<(), FaeError> {
+        self.text_renderer = Some(TextRenderer::create_headless(font_data)?);
+        Ok(())
+    }
+
+    /// Draws black text, laid out exactly like
+    /// [`TextRenderer::draw_text`](../text/struct.TextRenderer.html#method.draw_text),
+    /// with the font set with [`SoftwareRenderer::set_font`]. Returns
+    /// the bounding rectangle of the text.
+    ///
+    /// If no font has been set, nothing is drawn, a warning is
+    /// logged, and the returned rectangle is empty.
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        (x, y, z): (f32, f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        clip_area: Option<(f32, f32, f32, f32)>,
+    ) -> (f32, f32, f32, f32) {
+        let text_renderer = match &self.text_renderer {
+            Some(text_renderer) => text_renderer,
+            None => {
+                log::warn!("the software renderer has no font (see SoftwareRenderer::set_font)");
+                return (x, y, x, y);
+            }
+        };
+        let bounds = text_renderer.measure(text, (x, y), font_size, alignment, max_row_width);
+        let (image, (width, height)) =
+            text_renderer.rasterize(text, font_size, alignment, max_row_width);
+        if let Some(rgba) = to_rgba(&image) {
+            self.text_textures.push(rgba);
+            self.quads.push(SoftwareQuad {
+                coords: (x, y, x + width, y + height),
+                uvs: [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+                color: (0.0, 0.0, 0.0, 1.0),
+                rotation: (0.0, x, y),
+                clip_area,
+                depth: z,
+                texture: TextureRef::Text(self.text_textures.len() - 1),
+            });
+        }
+        bounds
+    }
+
+    /// Rasterizes the quads and text drawn since the last render into
+    /// a new RGBA image, and clears the queue. The quads are drawn
+    /// from the largest depth to the smallest, and quads with equal
+    /// depths in the order they were drawn in. Blending works like
+    /// the `Renderer`'s default alpha blending.
+    pub fn render(&mut self) -> Image {
+        let (r, g, b, a) = self.clear_color;
+        let clear_color = [to_byte(r), to_byte(g), to_byte(b), to_byte(a)];
+        let mut image = Image::from_color(self.width, self.height, &clear_color);
+
+        // The sort is stable, so draw order is kept for equal depths.
+        // NaN depths are sorted to the back instead of panicking.
+        let mut quads = mem::take(&mut self.quads);
+        quads.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        for quad in &quads {
+            let texture = match quad.texture {
+                TextureRef::Texture(index) => &self.textures[index],
+                TextureRef::Text(index) => &self.text_textures[index],
+            };
+            rasterize_quad(&mut image, quad, texture);
+        }
+        self.text_textures.clear();
+        image
+    }
+}
+
+fn rasterize_quad(target: &mut Image, quad: &SoftwareQuad, texture: &Image) {
+    let (x0, y0, x1, y1) = quad.coords;
+    if x1 <= x0 || y1 <= y0 || texture.width <= 0 || texture.height <= 0 {
+        return;
+    }
+    let (rads, pivot_x, pivot_y) = quad.rotation;
+    let (sin, cos) = rads.sin_cos();
+    let rotate = |x: f32, y: f32, sin: f32| {
+        let (x, y) = (x - pivot_x, y - pivot_y);
+        (pivot_x + cos * x - sin * y, pivot_y + sin * x + cos * y)
+    };
+
+    let corners = [
+        rotate(x0, y0, sin),
+        rotate(x1, y0, sin),
+        rotate(x1, y1, sin),
+        rotate(x0, y1, sin),
+    ];
+    let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+    let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for &(x, y) in &corners {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    let start_x = (min_x.floor() as i32).max(0);
+    let start_y = (min_y.floor() as i32).max(0);
+    let end_x = (max_x.ceil() as i32).min(target.width);
+    let end_y = (max_y.ceil() as i32).min(target.height);
+
+    let [tl, tr, br, bl] = quad.uvs;
+    let lerp =
+        |a: (f32, f32), b: (f32, f32), t: f32| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+    let (red, green, blue, alpha) = quad.color;
+    for py in start_y..end_y {
+        for px in start_x..end_x {
+            // Rotate the pixel's center back into the unrotated quad
+            let (x, y) = rotate(px as f32 + 0.5, py as f32 + 0.5, -sin);
+            if x < x0 || x >= x1 || y < y0 || y >= y1 {
+                continue;
+            }
+            if let Some((cx0, cy0, cx1, cy1)) = quad.clip_area {
+                if x < cx0 || x >= cx1 || y < cy0 || y >= cy1 {
+                    continue;
+                }
+            }
+
+            let (s, t) = ((x - x0) / (x1 - x0), (y - y0) / (y1 - y0));
+            let (u, v) = lerp(lerp(tl, tr, s), lerp(bl, br, s), t);
+            let texel = sample_nearest(texture, u, v);
+            let source = [
+                texel[0] as f32 / 255.0 * red,
+                texel[1] as f32 / 255.0 * green,
+                texel[2] as f32 / 255.0 * blue,
+                texel[3] as f32 / 255.0 * alpha,
+            ];
+
+            let i = (py * target.width + px) as usize * 4;
+            let destination = &mut target.pixels[i..i + 4];
+            let source_alpha = source[3];
+            for (dst, src) in destination.iter_mut().zip(&source) {
+                let dst_value = *dst as f32 / 255.0;
+                *dst = to_byte(src * source_alpha + dst_value * (1.0 - source_alpha));
+            }
+        }
+    }
+}
+
+/// Returns the texel at the UV coordinates, clamped to the edges of
+/// the texture, like `GL_NEAREST` and `GL_CLAMP_TO_EDGE`.
+fn sample_nearest(texture: &Image, u: f32, v: f32) -> [u8; 4] {
+    let x = ((u * texture.width as f32).floor() as i32).clamp(0, texture.width - 1);
+    let y = ((v * texture.height as f32).floor() as i32).clamp(0, texture.height - 1);
+    let i = (y * texture.width + x) as usize * 4;
+    match texture.pixels.get(i..i + 4) {
+        Some(texel) => [texel[0], texel[1], texel[2], texel[3]],
+        None => [0; 4],
+    }
+}
+
+/// Converts the image to RGBA, if it's in a format the software
+/// renderer can draw.
+fn to_rgba(image: &Image) -> Option<Image> {
+    let pixels = match image.format {
+        gl::RGBA => image.pixels.clone(),
+        gl::RGB => {
+            let mut pixels = Vec::with_capacity(image.pixels.len() / 3 * 4);
+            for rgb in image.pixels.chunks_exact(3) {
+                pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 0xFF]);
+            }
+            pixels
+        }
+        gl::RED => {
+            let mut pixels = Vec::with_capacity(image.pixels.len() * 4);
+            for &coverage in &image.pixels {
+                pixels.extend_from_slice(&[0xFF, 0xFF, 0xFF, coverage]);
+            }
+            pixels
+        }
+        _ => return None,
+    };
+    Some(Image {
+        pixels,
+        width: image.width,
+        height: image.height,
+        format: gl::RGBA,
+        color_type: image.color_type,
+    })
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
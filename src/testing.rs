@@ -0,0 +1,124 @@
+//! Golden-image testing utilities: comparing rendered images against
+//! stored reference PNGs, to catch rendering regressions.
+//!
+//! Render the scene with the
+//! [`SoftwareRenderer`](../software/struct.SoftwareRenderer.html),
+//! which doesn't depend on the GPU, and check it with
+//! [`assert_matches_reference`]. If the reference image doesn't exist
+//! yet, or the `FAE_UPDATE_REFERENCES` environment variable is set,
+//! the rendered image is written as the new reference instead.
+
+use crate::image::Image;
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Compares two images, pixel by pixel. Pixels where any channel
+/// differs by more than `tolerance` count as different.
+///
+/// Returns `None` if there are no differing pixels, otherwise an
+/// image highlighting the differing pixels in red, on top of a dimmed
+/// grayscale version of `reference`. Images of different sizes are
+/// always different, and the diff image is the size of `reference`.
+///
+/// Both images should be RGBA, like the ones rendered by the
+/// `SoftwareRenderer` or loaded with
+/// [`Image::from_png`](../struct.Image.html#method.from_png).
+pub fn diff_images(image: &Image, reference: &Image, tolerance: u8) -> Option<Image> {
+    let same_size = image.width == reference.width && image.height == reference.height;
+    let mut diff = reference.clone();
+    let mut differences = 0;
+    for (i, reference_pixel) in reference.pixels.chunks_exact(4).enumerate() {
+        let pixel = image.pixels.get(i * 4..i * 4 + 4).filter(|_| same_size);
+        let differs = match pixel {
+            Some(pixel) => pixel
+                .iter()
+                .zip(reference_pixel)
+                .any(|(&a, &b)| (a as i32 - b as i32).abs() > tolerance as i32),
+            None => true,
+        };
+        let diff_pixel = &mut diff.pixels[i * 4..i * 4 + 4];
+        if differs {
+            differences += 1;
+            diff_pixel.copy_from_slice(&[0xFF, 0x00, 0x00, 0xFF]);
+        } else {
+            let brightness = reference_pixel[..3].iter().map(|&c| c as u32).sum::<u32>() / 3;
+            let dimmed = (brightness / 4 + 0x40) as u8;
+            diff_pixel.copy_from_slice(&[dimmed, dimmed, dimmed, 0xFF]);
+        }
+    }
+    if differences == 0 && same_size {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+/// Asserts that `image` matches the reference PNG at
+/// `reference_path`, with each channel allowed to differ by
+/// `tolerance`. See [`diff_images`].
+///
+/// On a mismatch, the diff image is written next to the reference,
+/// with the extension `.diff.png`, along with the rendered image
+/// (`.actual.png`), and the function panics.
+///
+/// If the reference doesn't exist, or the `FAE_UPDATE_REFERENCES`
+/// environment variable is set, `image` is written as the reference
+/// and the assertion passes.
+pub fn assert_matches_reference<P: AsRef<Path>>(image: &Image, reference_path: P, tolerance: u8) {
+    let reference_path = reference_path.as_ref();
+    if env::var_os("FAE_UPDATE_REFERENCES").is_some() || !reference_path.exists() {
+        write_png(image, reference_path);
+        return;
+    }
+
+    let bytes = fs::read(reference_path).unwrap_or_else(|err| {
+        panic!("could not read {}: {}", reference_path.display(), err);
+    });
+    let reference = Image::from_png(&bytes).unwrap_or_else(|err| {
+        panic!("could not decode {}: {}", reference_path.display(), err);
+    });
+    if let Some(diff) = diff_images(image, &reference, tolerance) {
+        let diff_path = sibling_path(reference_path, "diff");
+        let actual_path = sibling_path(reference_path, "actual");
+        write_png(&diff, &diff_path);
+        write_png(image, &actual_path);
+        panic!(
+            "the rendered {}x{} image doesn't match the {}x{} reference {} (diff: {}, rendered image: {})",
+            image.width,
+            image.height,
+            reference.width,
+            reference.height,
+            reference_path.display(),
+            diff_path.display(),
+            actual_path.display(),
+        );
+    }
+}
+
+/// Returns eg. `foo.diff.png` for `foo.png` and `"diff"`.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map_or_else(Default::default, |stem| stem.to_string_lossy());
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+/// Writes the RGBA image as a PNG, panicking on failure.
+fn write_png(image: &Image, path: &Path) {
+    use png::HasParameters;
+    let write = || -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, image.width as u32, image.height as u32);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&image.pixels)?;
+        Ok(())
+    };
+    if let Err(err) = write() {
+        panic!("could not write {}: {}", path.display(), err);
+    }
+}
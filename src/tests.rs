@@ -1,6 +1,1034 @@
-use crate::renderer::Renderer;
+use crate::renderer::{self, DrawCallHandle, QuadDesc, Renderer};
+use crate::window::{DoubleClickTracker, FrameLimiter, KeyRepeatTimer, Mouse, ScrollDelta};
+use crate::Timestep;
+use std::time::{Duration, Instant};
 use test::Bencher;
 
+#[test]
+fn double_click_detection() {
+    let mut tracker = DoubleClickTracker::new();
+    let start = Instant::now();
+    let later = |millis| start + Duration::from_millis(millis);
+
+    assert!(!tracker.press(Mouse::Left, (10.0, 10.0), start));
+    assert!(tracker.press(Mouse::Left, (11.0, 10.0), later(200)));
+    // The third press of a triple click starts over
+    assert!(!tracker.press(Mouse::Left, (11.0, 10.0), later(300)));
+
+    // Too slow, too far, and a different button
+    assert!(!tracker.press(Mouse::Left, (11.0, 10.0), later(1000)));
+    assert!(!tracker.press(Mouse::Left, (50.0, 10.0), later(1100)));
+    assert!(!tracker.press(Mouse::Right, (50.0, 10.0), later(1200)));
+}
+
+#[test]
+fn key_repeat_timing() {
+    let mut timer = KeyRepeatTimer::new();
+    timer.delay = Duration::from_millis(500);
+    timer.interval = Duration::from_millis(100);
+    let start = Instant::now();
+    let later = |millis| start + Duration::from_millis(millis);
+    let mut repeats = Vec::new();
+
+    timer.press('a', start);
+    timer.update(later(400), &mut repeats);
+    assert!(repeats.is_empty());
+    timer.update(later(500), &mut repeats);
+    assert_eq!(repeats, vec!['a']);
+    // A long frame repeats the key several times
+    timer.update(later(800), &mut repeats);
+    assert_eq!(repeats, vec!['a'; 4]);
+
+    repeats.clear();
+    timer.release('a');
+    timer.update(later(2000), &mut repeats);
+    assert!(repeats.is_empty());
+}
+
+#[test]
+fn scroll_deltas_accumulate_on_both_axes() {
+    let (mut pixels, mut notches) = ((0.0, 0.0), (0.0, 0.0));
+    // A tilt wheel step to the right, and a wheel notch down
+    let step = ScrollDelta::Notches(1.0, -1.0).accumulate(&mut pixels, &mut notches, 36.0);
+    assert_eq!(step, (36.0, -36.0));
+    // A trackpad swipe, which is already in pixels
+    let swipe = ScrollDelta::Pixels(-9.0, 18.0).accumulate(&mut pixels, &mut notches, 36.0);
+    assert_eq!(swipe, (-9.0, 18.0));
+    assert_eq!(pixels, (27.0, -18.0));
+    assert_eq!(notches, (0.75, -0.5));
+}
+
+#[test]
+fn frame_limiter_paces_frames() {
+    let mut limiter = FrameLimiter::new();
+    let start = Instant::now();
+    let later = |millis| start + Duration::from_millis(millis);
+    assert_eq!(limiter.next_frame(start), None);
+
+    limiter.set_frame_rate(Some(100.0));
+    assert_eq!(limiter.next_frame(start), None);
+    let deadline = limiter.next_frame(later(4)).unwrap();
+    assert!(deadline >= later(9) && deadline <= later(11));
+    // A late frame starts right away, and the next one is a full
+    // interval after it
+    assert_eq!(limiter.next_frame(later(50)), None);
+    let deadline = limiter.next_frame(later(51)).unwrap();
+    assert!(deadline >= later(59) && deadline <= later(61));
+
+    limiter.set_frame_rate(None);
+    assert_eq!(limiter.next_frame(later(52)), None);
+}
+
+#[test]
+fn timestep_accumulates_and_caps_steps() {
+    let mut timestep = Timestep::new(Duration::from_millis(10));
+    assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+    assert_eq!(timestep.advance(Duration::from_millis(20)), 2);
+    assert!((timestep.alpha() - 0.5).abs() < 0.001);
+
+    // Falling far behind drops the extra time
+    timestep.set_max_steps(3);
+    assert_eq!(timestep.advance(Duration::from_secs(1)), 3);
+    assert_eq!(timestep.alpha(), 0.0);
+}
+
+/// Draws a white 10x10 quad at the origin.
+fn draw_test_quad(renderer: &mut Renderer, call: &DrawCallHandle) {
+    renderer.draw_quad(
+        (0.0, 0.0, 10.0, 10.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.0, 0.0, 0.0),
+        0.0,
+        call,
+    );
+}
+
+/// Draws a test quad into two draw calls, the second one set up with
+/// `setup`, and batches them. Returns the amount of quads left in
+/// each call.
+fn quads_after_batching(setup: impl FnOnce(&mut Renderer, &DrawCallHandle)) -> (usize, usize) {
+    let mut renderer = Renderer::new(false);
+    let first = renderer.create_dummy_draw_call();
+    let second = renderer.create_dummy_draw_call();
+    setup(&mut renderer, &second);
+    draw_test_quad(&mut renderer, &first);
+    draw_test_quad(&mut renderer, &second);
+
+    // The white draw call is at index 0
+    renderer.batch_calls(&[1, 2]);
+    let floats_per_quad = 12 * 6;
+    (
+        renderer.queued_floats(&first) / floats_per_quad,
+        renderer.queued_floats(&second) / floats_per_quad,
+    )
+}
+
+#[test]
+fn auto_batching_merges_identical_calls() {
+    assert_eq!(quads_after_batching(|_, _| {}), (2, 0));
+}
+
+#[test]
+fn calls_with_different_extra_textures_are_not_batched() {
+    let setup = |renderer: &mut Renderer, call: &DrawCallHandle| {
+        renderer.set_draw_call_extra_texture(call, 1, 42);
+    };
+    assert_eq!(quads_after_batching(setup), (1, 1));
+}
+
+#[test]
+fn calls_with_different_polygon_offsets_are_not_batched() {
+    let setup = |renderer: &mut Renderer, call: &DrawCallHandle| {
+        renderer.set_polygon_offset(call, 0.0, -1.0);
+    };
+    assert_eq!(quads_after_batching(setup), (1, 1));
+}
+
+#[test]
+fn cleared_polygon_offsets_are_batched_again() {
+    let setup = |renderer: &mut Renderer, call: &DrawCallHandle| {
+        renderer.set_polygon_offset(call, 0.0, -1.0);
+        renderer.clear_polygon_offset(call);
+    };
+    assert_eq!(quads_after_batching(setup), (2, 0));
+}
+
+#[test]
+fn draw_ranges_split_at_quad_boundaries() {
+    let ranges =
+        |vertices, max_quads| renderer::draw_ranges(vertices, max_quads).collect::<Vec<_>>();
+    assert_eq!(ranges(60, None), vec![(0, 60)]);
+    assert_eq!(ranges(60, Some(4)), vec![(0, 24), (24, 24), (48, 12)]);
+    // Triangles are half quads, and stay whole
+    assert_eq!(ranges(15, Some(1)), vec![(0, 6), (6, 6), (12, 3)]);
+    assert_eq!(ranges(0, Some(4)), vec![]);
+}
+
+#[test]
+fn masked_quads_are_not_queued_into_draw_calls() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    renderer.begin_mask();
+    draw_test_quad(&mut renderer, &call);
+    renderer.draw_masked();
+    draw_test_quad(&mut renderer, &call);
+    renderer.end_mask();
+    assert_eq!(renderer.queued_floats(&call), 0);
+
+    draw_test_quad(&mut renderer, &call);
+    assert_eq!(renderer.queued_floats(&call), 12 * 6);
+}
+
+#[test]
+fn deleted_draw_call_slots_are_reused() {
+    let mut renderer = Renderer::new(false);
+    let first = renderer.create_dummy_draw_call();
+    let second = renderer.create_dummy_draw_call();
+    draw_test_quad(&mut renderer, &first);
+    renderer.delete_draw_call(first);
+    let third = renderer.create_dummy_draw_call();
+    // The new call takes the deleted slot, without the old quads
+    assert_eq!(renderer.queued_floats(&third), 0);
+    assert_eq!(renderer.queued_floats(&second), 0);
+}
+
+#[test]
+#[should_panic(expected = "deleted draw call")]
+fn stale_draw_call_handles_panic() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let stale = call;
+    renderer.delete_draw_call(call);
+    let _reused_slot = renderer.create_dummy_draw_call();
+    renderer.reserve(&stale, 1);
+}
+
+#[test]
+#[should_panic(expected = "out of range: this renderer only has draw calls 0..2")]
+fn out_of_range_draw_call_handles_panic() {
+    let mut other_renderer = Renderer::new(false);
+    other_renderer.create_dummy_draw_call();
+    let foreign = other_renderer.create_dummy_draw_call();
+    let mut renderer = Renderer::new(false);
+    renderer.create_dummy_draw_call();
+    renderer.reserve(&foreign, 1);
+}
+
+#[test]
+fn command_buffers_replay_like_direct_draws() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let draw = |renderer: &mut Renderer| {
+        renderer.draw_quad(
+            (0.0, 0.0, 10.0, 10.0),
+            (0.0, 0.0, 1.0, 1.0),
+            (1.0, 0.5, 0.5, 1.0),
+            (0.5, 5.0, 5.0),
+            0.25,
+            &call,
+        );
+        renderer.draw_triangle(
+            [(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)],
+            [(-1.0, -1.0); 3],
+            [(0.0, 0.0, 0.0, 1.0); 3],
+            0.5,
+            &call,
+        );
+    };
+    draw(&mut renderer);
+    let direct = renderer.queued_vertices(&call).to_vec();
+    renderer.flush();
+
+    let mut commands = crate::CommandBuffer::new();
+    commands.draw_quad(
+        (0.0, 0.0, 10.0, 10.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 0.5, 0.5, 1.0),
+        (0.5, 5.0, 5.0),
+        0.25,
+        &call,
+    );
+    commands.draw_triangle(
+        [(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)],
+        [(-1.0, -1.0); 3],
+        [(0.0, 0.0, 0.0, 1.0); 3],
+        0.5,
+        &call,
+    );
+    assert_send(&commands);
+    assert_eq!(commands.len(), 2);
+    let commands = renderer.replay(commands);
+    assert!(commands.is_empty());
+    assert_eq!(renderer.queued_vertices(&call), &direct[..]);
+}
+
+#[test]
+fn command_buffers_can_be_executed_repeatedly() {
+    use crate::{CommandBuffer, DrawCommand};
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let mut commands = CommandBuffer::new();
+    commands.push_clip_rect((0.0, 0.0, 5.0, 5.0));
+    commands.draw_quad(
+        (0.0, 0.0, 10.0, 10.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.0, 0.0, 0.0),
+        0.0,
+        &call,
+    );
+    commands.pop_clip_rect();
+    assert_eq!(
+        commands.commands()[0],
+        DrawCommand::PushClipRect((0.0, 0.0, 5.0, 5.0))
+    );
+    assert_eq!(commands.clone(), commands);
+
+    for _ in 0..2 {
+        renderer.execute(&commands);
+        assert_eq!(renderer.clip_rect(), None);
+        let vertices = renderer.queued_vertices(&call);
+        assert_eq!(vertices.len(), 72);
+        // The quad was clipped to the pushed rectangle
+        assert_eq!(vertices[12 * 2], 5.0);
+        renderer.flush();
+    }
+    assert_eq!(commands.len(), 3);
+}
+
+#[test]
+fn creating_draw_calls_without_a_context_fails_clearly() {
+    let mut renderer = Renderer::new(false);
+    match renderer.create_draw_call(Default::default()) {
+        Err(crate::FaeError::ContextCreation(msg)) => assert!(msg.contains("load_with")),
+        result => panic!("expected a ContextCreation error, got {:?}", result),
+    }
+}
+
+#[test]
+fn srgb_stays_disabled_without_the_extension() {
+    let red_after_srgb = |legacy| {
+        let mut renderer = Renderer::new(legacy);
+        let call = renderer.create_dummy_draw_call();
+        renderer.set_srgb(true);
+        renderer.draw_quad(
+            (0.0, 0.0, 10.0, 10.0),
+            (0.0, 0.0, 1.0, 1.0),
+            (0.5, 0.5, 0.5, 1.0),
+            (0.0, 0.0, 0.0),
+            0.0,
+            &call,
+        );
+        renderer.queued_vertices(&call)[5]
+    };
+    assert!(red_after_srgb(false) < 0.5);
+    // Legacy OpenGL without a context can't have ARB_framebuffer_sRGB
+    assert_eq!(red_after_srgb(true), 0.5);
+}
+
+#[test]
+fn dropping_renderers_without_a_context_doesnt_call_opengl() {
+    // Without a loaded OpenGL context, dropping should skip the GL
+    // cleanup instead of calling unloaded functions, which would
+    // panic.
+    for _ in 0..100 {
+        let mut renderer = Renderer::new(false);
+        let call = renderer.create_dummy_draw_call();
+        renderer.create_dummy_draw_call();
+        renderer.delete_draw_call(call);
+    }
+}
+
+#[cfg(feature = "glutin")]
+#[test]
+#[ignore] // Needs a display to create the OpenGL context on
+fn dropping_renderers_deletes_opengl_objects() {
+    use crate::gl;
+    use crate::renderer::DrawCallParameters;
+    use crate::window::{Window, WindowSettings};
+    let window = Window::create(&WindowSettings::default()).unwrap();
+    let mut objects = Vec::new();
+    for _ in 0..10 {
+        let mut renderer = Renderer::new(window.opengl21);
+        for _ in 0..3 {
+            let call = renderer
+                .create_draw_call(DrawCallParameters::default())
+                .unwrap();
+            objects.push(renderer.gl_objects(&call));
+        }
+    }
+    for (texture, vbo, program) in objects {
+        unsafe {
+            assert_eq!(gl::IsTexture(texture), gl::FALSE);
+            assert_eq!(gl::IsBuffer(vbo), gl::FALSE);
+            assert_eq!(gl::IsProgram(program), gl::FALSE);
+        }
+    }
+}
+
+#[test]
+fn image_resizing() {
+    use crate::{Image, ResizeFilter};
+    let mut image = Image::from_color(2, 2, &[0, 0, 0, 0]);
+    image.pixels = vec![
+        0, 0, 0, 255, 100, 0, 0, 255, //
+        0, 200, 0, 255, 0, 0, 40, 255,
+    ];
+    let average = image.resize(1, 1, ResizeFilter::Bilinear);
+    assert_eq!(average.pixels, vec![25, 50, 10, 255]);
+
+    let upscaled = image.resize(4, 4, ResizeFilter::Nearest);
+    assert_eq!((upscaled.width, upscaled.height), (4, 4));
+    assert_eq!(&upscaled.pixels[4..8], &[0, 0, 0, 255]);
+    assert_eq!(&upscaled.pixels[8..12], &[100, 0, 0, 255]);
+    assert_eq!(&upscaled.pixels[60..64], &[0, 0, 40, 255]);
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn png_color_types_are_recorded_and_expanded() {
+    use crate::{ColorType, Image};
+    use png::HasParameters;
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder
+            .set(png::ColorType::Grayscale)
+            .set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0x10, 0x80]).unwrap();
+    }
+    let image = Image::from_png(&bytes).unwrap();
+    assert_eq!(image.color_type, ColorType::Grayscale);
+    assert!(!image.color_type.has_alpha());
+    assert_eq!(
+        image.pixels,
+        vec![0x10, 0x10, 0x10, 0xFF, 0x80, 0x80, 0x80, 0xFF]
+    );
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn text_input_editing() {
+    use crate::text::{TextEdit, TextInput};
+    let mut input = TextInput::new();
+    for c in "hëllo\u{8}".chars() {
+        input.insert(c);
+    }
+    assert_eq!((input.text(), input.caret()), ("hëllo", 5));
+
+    input.edit(TextEdit::Left);
+    input.edit(TextEdit::Left);
+    input.edit(TextEdit::Left);
+    input.edit(TextEdit::Backspace);
+    assert_eq!((input.text(), input.caret()), ("hllo", 1));
+    input.edit(TextEdit::Delete);
+    input.insert('a');
+    assert_eq!((input.text(), input.caret()), ("halo", 2));
+
+    input.edit(TextEdit::Home);
+    input.edit(TextEdit::Backspace);
+    assert_eq!((input.text(), input.caret()), ("halo", 0));
+    input.edit(TextEdit::End);
+    input.edit(TextEdit::Right);
+    input.edit(TextEdit::Delete);
+    assert_eq!((input.text(), input.caret()), ("halo", 4));
+}
+
+#[cfg(feature = "raw-window-handle")]
+#[test]
+fn raw_window_handle_conversion() {
+    use crate::window::RawWindowHandle;
+    let display = 0x1234 as *mut std::os::raw::c_void;
+    let handles = RawWindowHandle::Xlib {
+        window: 42,
+        display,
+    }
+    .into_raw_handles();
+    match handles {
+        (
+            raw_window_handle::RawWindowHandle::Xlib(window),
+            raw_window_handle::RawDisplayHandle::Xlib(display_handle),
+        ) => {
+            assert_eq!(window.window, 42);
+            assert_eq!(display_handle.display, display);
+        }
+        _ => panic!("expected Xlib handles, got {:?}", handles),
+    }
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn text_input_composes_combining_characters() {
+    use crate::text::{Alignment, TextInput, TextRenderer};
+    let mut input = TextInput::new();
+    input.insert('e');
+    input.insert('\u{301}');
+    input.insert('!');
+    assert_eq!((input.text(), input.caret()), ("\u{e9}!", 2));
+
+    input.set_text("cafe\u{301} au lait");
+    assert_eq!(input.text(), "caf\u{e9} au lait");
+    assert_eq!(input.caret(), 12);
+
+    // The caret index matches the renderer's index space, which
+    // counts chars after normalization, even for decomposed text
+    let font = include_bytes!("../examples/res/FiraSans.ttf").to_vec();
+    let text_renderer = TextRenderer::create_headless(font).unwrap();
+    let caret = |text, index| {
+        text_renderer.caret_position(text, (0.0, 0.0), 16.0, Alignment::Left, None, index)
+    };
+    let decomposed = caret("cafe\u{301} au lait", input.caret());
+    assert_eq!(caret(input.text(), input.caret()), decomposed);
+}
+
+#[cfg(all(feature = "text", feature = "glutin"))]
+#[test]
+fn text_input_applies_events_in_order() {
+    use crate::text::TextInput;
+    use crate::window::{Event, Key};
+    let mut input = TextInput::new();
+    let events = [
+        Event::CharTyped('a'),
+        Event::CharTyped('b'),
+        Event::KeyPressed(Key::Back),
+        Event::CharTyped('c'),
+        Event::KeyRepeated(Key::Left),
+        Event::KeyReleased(Key::Left),
+        Event::CharTyped('d'),
+    ];
+    assert!(input.apply_events(&events));
+    assert_eq!((input.text(), input.caret()), ("adc", 2));
+    assert!(!input.apply_events(&[Event::KeyPressed(Key::Home)]));
+    assert_eq!(input.caret(), 0);
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn selection_rects_cover_wrapped_rows() {
+    use crate::text::{Alignment, TextRenderer};
+    let font = include_bytes!("../examples/res/FiraSans.ttf").to_vec();
+    let text_renderer = TextRenderer::create_headless(font).unwrap();
+    let text = "first row\nsecond row\nthird row";
+    let rects = |range| {
+        text_renderer.selection_rects(text, (10.0, 20.0), 16.0, Alignment::Left, None, range)
+    };
+    let caret = |index| {
+        text_renderer.caret_position(text, (10.0, 20.0), 16.0, Alignment::Left, None, index)
+    };
+
+    assert!(rects(3..3).is_empty());
+    let single = rects(0..5);
+    assert_eq!(single.len(), 1);
+    assert_eq!((single[0].0, single[0].1), caret(0));
+    assert!((single[0].0 + single[0].2 - caret(5).0).abs() < 1e-3);
+
+    // From the middle of the first row to the middle of the third
+    let multiple = rects(6..25);
+    assert_eq!(multiple.len(), 3);
+    assert_eq!((multiple[0].0, multiple[0].1), caret(6));
+    assert_eq!((multiple[1].0, multiple[1].1), caret(10));
+    assert!(multiple[1].2 > multiple[0].2);
+    assert_eq!((multiple[2].0, multiple[2].1), caret(21));
+    assert!((multiple[2].0 + multiple[2].2 - caret(25).0).abs() < 1e-3);
+    assert!((multiple[1].1 - multiple[0].1 - multiple[0].3).abs() < 1e-3);
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn text_wraps_at_the_same_width_at_any_dpi() {
+    use crate::text::{Alignment, TextRenderer};
+    let font = include_bytes!("../examples/res/FiraSans.ttf").to_vec();
+    let mut text_renderer = TextRenderer::create_headless(font).unwrap();
+    let text = "wrap this";
+    for &dpi_factor in &[1.0, 2.0] {
+        text_renderer.update_dpi_factor(dpi_factor);
+        let caret = |max_width, index| {
+            text_renderer.caret_position(
+                text,
+                (100.0, 0.0),
+                16.0,
+                Alignment::Left,
+                max_width,
+                index,
+            )
+        };
+        let width = caret(None, 9).0 - 100.0;
+        // The text starts at x = 100.0, which shouldn't count against
+        // the row width, but shouldn't be scaled by the DPI either
+        assert_eq!(caret(Some(width + 1.0), 9).1, caret(None, 0).1);
+        assert!(caret(Some(width * 0.75), 9).1 > caret(None, 0).1);
+    }
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn raster_options_snap_glyphs_and_adjust_coverage() {
+    use crate::text::{self, Alignment, Hinting, RasterOptions, TextRenderer};
+    let font = include_bytes!("../examples/res/FiraSans.ttf").to_vec();
+    let mut text_renderer = TextRenderer::create_headless(font).unwrap();
+    text_renderer.set_rasterization_options(RasterOptions {
+        hinting: Hinting::PixelGrid,
+        gamma: 1.0,
+    });
+    for index in 0..4 {
+        let (x, _) =
+            text_renderer.caret_position("fae!", (0.3, 0.0), 11.0, Alignment::Left, None, index);
+        assert_eq!(x, x.round());
+    }
+
+    assert_eq!(text::apply_gamma(0.25, 1.0), 0.25);
+    assert!((text::apply_gamma(0.25, 2.0) - 0.5).abs() < 1e-6);
+    assert_eq!(text::apply_gamma(1.0, 2.2), 1.0);
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn coverage_to_signed_distance_field() {
+    use crate::text::coverage_to_sdf;
+    // A 9x9 bitmap with a filled 5x5 square in the middle
+    let mut coverage = vec![0u8; 81];
+    for y in 2..7 {
+        for x in 2..7 {
+            coverage[y * 9 + x] = 255;
+        }
+    }
+    let sdf = coverage_to_sdf(&coverage, 9, 9);
+    let at = |x: usize, y: usize| sdf[y * 9 + x];
+    // Inside is above the edge value, outside below it
+    assert!(at(2, 2) > 128 && at(1, 1) < 128);
+    // The distances grow further from the edge
+    assert!(at(4, 4) > at(3, 4) && at(3, 4) > at(2, 4));
+    assert!(at(0, 4) < at(1, 4));
+}
+
+#[cfg(feature = "atlas")]
+#[test]
+fn atlas_parses_named_regions() {
+    use crate::atlas::Atlas;
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let json = r#"[
+        {"name": "player", "x": 0, "y": 0, "w": 16, "h": 32},
+        {"name": "tree", "x": 16, "y": 0, "w": 32, "h": 48, "rotated": true}
+    ]"#;
+    let atlas = Atlas::from_json(json, call).unwrap();
+    let tree = atlas.region("tree").unwrap();
+    assert_eq!((tree.x, tree.w, tree.rotated), (16.0, 32.0, true));
+    assert!(!atlas.region("player").unwrap().rotated);
+
+    let color = (1.0, 1.0, 1.0, 1.0);
+    assert!(atlas
+        .draw(&mut renderer, "player", (0.0, 0.0, 16.0, 32.0), color, 0.0)
+        .is_ok());
+    assert!(atlas
+        .draw(&mut renderer, "rock", (0.0, 0.0, 16.0, 32.0), color, 0.0)
+        .is_err());
+    assert!(Atlas::from_json("{", call).is_err());
+}
+
+#[cfg(feature = "atlas")]
+#[test]
+fn animation_playback_modes() {
+    use crate::atlas::{Animation, PlaybackMode};
+    let frames: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+    let frame = Duration::from_millis(100);
+    let indices = |mode| {
+        let mut animation = Animation::new(frames.clone(), frame, mode);
+        let mut indices = Vec::new();
+        for _ in 0..6 {
+            indices.push(animation.current_frame_index());
+            animation.update(frame);
+        }
+        (indices, animation.is_finished())
+    };
+    assert_eq!(indices(PlaybackMode::Once), (vec![0, 1, 2, 2, 2, 2], true));
+    assert_eq!(indices(PlaybackMode::Loop), (vec![0, 1, 2, 0, 1, 2], false));
+    assert_eq!(
+        indices(PlaybackMode::PingPong),
+        (vec![0, 1, 2, 1, 0, 1], false)
+    );
+}
+
+#[test]
+fn polygon_is_a_triangle_fan() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let hexagon: Vec<(f32, f32)> = (0..6)
+        .map(|i| {
+            let angle = i as f32 / 6.0 * std::f32::consts::PI * 2.0;
+            (angle.cos() * 10.0, angle.sin() * 10.0)
+        })
+        .collect();
+    renderer.draw_polygon(&hexagon, (1.0, 1.0, 1.0, 1.0), 0.0, Some(&call));
+    // 12 floats per vertex, 3 vertices per triangle, N - 2 triangles
+    assert_eq!(renderer.queued_floats(&call), 12 * 3 * (6 - 2));
+}
+
+#[test]
+fn primitives_default_to_the_white_draw_call() {
+    let mut renderer = Renderer::new(false);
+    let white = *renderer.white_draw_call();
+    let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    renderer.draw_polygon(&square, (1.0, 0.0, 0.0, 1.0), 0.0, None);
+    assert_eq!(renderer.queued_floats(&white), 12 * 3 * 2);
+    renderer.draw_rounded_rect(
+        (0.0, 0.0, 10.0, 10.0),
+        0.0,
+        1,
+        (1.0, 0.0, 0.0, 1.0),
+        0.0,
+        None,
+    );
+    assert_eq!(renderer.queued_floats(&white), 12 * 3 * 2 + 12 * 6);
+}
+
+#[test]
+#[should_panic(expected = "the white draw call is built into the renderer")]
+fn deleting_the_white_draw_call_panics() {
+    let mut renderer = Renderer::new(false);
+    let white = *renderer.white_draw_call();
+    renderer.delete_draw_call(white);
+}
+
+#[test]
+fn logical_pixels_scale_with_dpi() {
+    // A 1 logical pixel wide line on a 2x DPI surface should cover 2
+    // physical pixels, when the viewport is the physical size
+    let (width, height, dpi_factor) = (640.0, 480.0, 2.0);
+    let matrix = renderer::projection_matrix(width, height, (-1.0, 1.0));
+    let to_physical_x = |x: f32| {
+        let ndc_x = x * matrix[0] + matrix[3];
+        (ndc_x + 1.0) / 2.0 * width * dpi_factor
+    };
+    let to_physical_y = |y: f32| {
+        let ndc_y = y * matrix[5] + matrix[7];
+        (1.0 - ndc_y) / 2.0 * height * dpi_factor
+    };
+    assert!((to_physical_x(11.0) - to_physical_x(10.0) - 2.0).abs() < 1e-3);
+    assert!((to_physical_y(11.0) - to_physical_y(10.0) - 2.0).abs() < 1e-3);
+    assert!(to_physical_x(0.0).abs() < 1e-3);
+    assert!(to_physical_y(0.0).abs() < 1e-3);
+}
+
+#[test]
+fn depth_range_maps_to_clip_space() {
+    let matrix = renderer::projection_matrix(640.0, 480.0, (0.0, 1000.0));
+    let clip_z = |z: f32| z * matrix[10] + matrix[11];
+    assert!((clip_z(0.0) + 1.0).abs() < 1e-6);
+    assert!((clip_z(500.0)).abs() < 1e-6);
+    assert!((clip_z(1000.0) - 1.0).abs() < 1e-6);
+
+    // The default range leaves the depth as is
+    let matrix = renderer::projection_matrix(640.0, 480.0, (-1.0, 1.0));
+    assert_eq!((matrix[10], matrix[11]), (1.0, 0.0));
+}
+
+#[test]
+fn screen_and_world_coordinates_round_trip() {
+    let size = (640.0, 480.0);
+    let matrix = renderer::projection_matrix(size.0, size.1, (-1.0, 1.0));
+    let screen = renderer::world_to_screen(&matrix, size, (120.0, 45.0));
+    assert!((screen.0 - 120.0).abs() < 1e-3 && (screen.1 - 45.0).abs() < 1e-3);
+    let world = renderer::screen_to_world(&matrix, size, (600.0, 10.0));
+    assert!((world.0 - 600.0).abs() < 1e-3 && (world.1 - 10.0).abs() < 1e-3);
+
+    // Before the first render, the conversions are identities
+    let renderer = Renderer::new(false);
+    assert_eq!(renderer.screen_to_world((3.0, 4.0)), (3.0, 4.0));
+}
+
+#[test]
+fn z_interleaving_splits_calls_by_depth() {
+    let mut renderer = Renderer::new(false);
+    let sprites = renderer.create_dummy_draw_call();
+    let text = renderer.create_dummy_draw_call();
+    let draw = |renderer: &mut Renderer, z, call| {
+        renderer.draw_quad(
+            (0.0, 0.0, 10.0, 10.0),
+            (0.0, 0.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0, 1.0),
+            (0.0, 0.0, 0.0),
+            z,
+            call,
+        );
+    };
+    draw(&mut renderer, -0.5, &sprites);
+    draw(&mut renderer, 0.5, &sprites);
+    draw(&mut renderer, 0.0, &text);
+    draw(&mut renderer, 0.0, &text);
+
+    let queues = [
+        (0, renderer.queued_vertices(&sprites)),
+        (1, renderer.queued_vertices(&text)),
+    ];
+    let runs = renderer::interleave_by_depth(&queues);
+    let calls = runs.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    assert_eq!(calls, vec![0, 1, 0]);
+    let floats = runs.iter().map(|(_, v)| v.len()).collect::<Vec<_>>();
+    assert_eq!(floats, vec![72, 144, 72]);
+}
+
+#[test]
+fn interleaving_nan_depths_doesnt_panic() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    for &z in &[0.0, f32::NAN, 1.0] {
+        renderer.draw_quad(
+            (0.0, 0.0, 10.0, 10.0),
+            (0.0, 0.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0, 1.0),
+            (0.0, 0.0, 0.0),
+            z,
+            &call,
+        );
+    }
+    let runs = renderer::interleave_by_depth(&[(0, renderer.queued_vertices(&call))]);
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].1.len(), 216);
+}
+
+#[test]
+fn dirty_rect_overlap_accounts_for_rotation() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    let draw = |renderer: &mut Renderer, rotation| {
+        renderer.draw_quad(
+            (0.0, 0.0, 10.0, 40.0),
+            (0.0, 0.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0, 1.0),
+            (rotation, 5.0, 20.0),
+            0.0,
+            &call,
+        );
+    };
+    draw(&mut renderer, 0.0);
+    let vertices = renderer.queued_vertices(&call);
+    assert!(renderer::vertices_overlap_rect(
+        vertices,
+        (5.0, 5.0, 15.0, 15.0)
+    ));
+    assert!(!renderer::vertices_overlap_rect(
+        vertices,
+        (15.0, 15.0, 25.0, 25.0)
+    ));
+
+    // Turned sideways, the quad reaches the rect
+    renderer.flush();
+    draw(&mut renderer, std::f32::consts::FRAC_PI_2);
+    let vertices = renderer.queued_vertices(&call);
+    assert!(renderer::vertices_overlap_rect(
+        vertices,
+        (15.0, 15.0, 25.0, 25.0)
+    ));
+}
+
+#[test]
+fn clip_rects_intersect_and_clip_quads() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    renderer.push_clip_rect((0.0, 0.0, 100.0, 100.0));
+    renderer.push_clip_rect((50.0, -50.0, 150.0, 80.0));
+    assert_eq!(renderer.clip_rect(), Some((50.0, 0.0, 100.0, 80.0)));
+
+    // Entirely outside the intersection: nothing is queued
+    renderer.draw_quad(
+        (110.0, 0.0, 120.0, 10.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.0, 0.0, 0.0),
+        0.0,
+        &call,
+    );
+    assert_eq!(renderer.queued_floats(&call), 0);
+
+    // Partially inside: the left half is cut off, along with its UVs
+    renderer.draw_quad(
+        (0.0, 0.0, 100.0, 10.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.0, 0.0, 0.0),
+        0.0,
+        &call,
+    );
+    assert_eq!(renderer.queued_floats(&call), 12 * 6);
+    let top_left = &renderer.queued_vertices(&call)[0..5];
+    assert_eq!(top_left, &[50.0, 0.0, 0.0, 0.5, 0.0]);
+
+    assert_eq!(renderer.pop_clip_rect(), Some((50.0, 0.0, 100.0, 80.0)));
+    assert_eq!(renderer.pop_clip_rect(), Some((0.0, 0.0, 100.0, 100.0)));
+    assert_eq!(renderer.pop_clip_rect(), None);
+}
+
+#[test]
+fn software_renderer_sorts_by_depth_and_blends() {
+    use crate::software::SoftwareRenderer;
+    use crate::Image;
+    let mut renderer = SoftwareRenderer::new(4, 4);
+    renderer.set_clear_color((1.0, 1.0, 1.0, 1.0));
+    let white = renderer
+        .create_texture(&Image::from_color(1, 1, &[0xFF, 0xFF, 0xFF, 0xFF]))
+        .unwrap();
+    let no_rotation = (0.0, 0.0, 0.0);
+    // Drawn first, but in front of the red quad
+    let blue = (0.0, 0.0, 1.0, 1.0);
+    renderer.draw_quad(
+        (0.0, 0.0, 2.0, 2.0),
+        (0.0, 0.0, 1.0, 1.0),
+        blue,
+        no_rotation,
+        -0.5,
+        &white,
+    );
+    let red = (1.0, 0.0, 0.0, 1.0);
+    renderer.draw_quad(
+        (0.0, 0.0, 4.0, 2.0),
+        (0.0, 0.0, 1.0, 1.0),
+        red,
+        no_rotation,
+        0.5,
+        &white,
+    );
+    let translucent_black = (0.0, 0.0, 0.0, 0.5);
+    let quad = (0.0, 2.0, 4.0, 4.0);
+    renderer.draw_quad(
+        quad,
+        (0.0, 0.0, 1.0, 1.0),
+        translucent_black,
+        no_rotation,
+        0.0,
+        &white,
+    );
+
+    let image = renderer.render();
+    let pixel = |x: usize, y: usize| &image.pixels[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+    assert_eq!(pixel(1, 1), &[0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(pixel(3, 1), &[0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(pixel(0, 3), &[0x80, 0x80, 0x80, 0xBF]);
+    // The queue is cleared after rendering
+    assert_eq!(renderer.render().pixels, vec![0xFF; 4 * 4 * 4]);
+}
+
+#[test]
+fn software_renderer_draws_nan_depths_at_the_back() {
+    use crate::software::SoftwareRenderer;
+    use crate::Image;
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    let white = renderer
+        .create_texture(&Image::from_color(1, 1, &[0xFF, 0xFF, 0xFF, 0xFF]))
+        .unwrap();
+    let blue = (0.0, 0.0, 1.0, 1.0);
+    let red = (1.0, 0.0, 0.0, 1.0);
+    for &(color, depth) in &[(blue, 0.5), (red, f32::NAN), (blue, -0.5), (red, f32::NAN)] {
+        renderer.draw_quad(
+            (0.0, 0.0, 2.0, 2.0),
+            (0.0, 0.0, 1.0, 1.0),
+            color,
+            (0.0, 0.0, 0.0),
+            depth,
+            &white,
+        );
+    }
+    let image = renderer.render();
+    assert_eq!(&image.pixels[0..4], &[0x00, 0x00, 0xFF, 0xFF]);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn reference_rotated_quad() {
+    use crate::software::SoftwareRenderer;
+    use crate::testing::assert_matches_reference;
+    use crate::{gl, ColorType, Image};
+    let mut renderer = SoftwareRenderer::new(64, 64);
+    renderer.set_clear_color((1.0, 1.0, 1.0, 1.0));
+    #[rustfmt::skip]
+    let checkers = Image {
+        pixels: vec![
+            0xFF, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF,
+            0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x80,
+        ],
+        width: 2,
+        height: 2,
+        format: gl::RGBA,
+        color_type: ColorType::Rgba,
+    };
+    let texture = renderer.create_texture(&checkers).unwrap();
+    renderer.draw_quad(
+        (16.0, 16.0, 48.0, 48.0),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.5, 16.0, 16.0),
+        0.0,
+        &texture,
+    );
+    let reference = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/reference/rotated_quad.png"
+    );
+    assert_matches_reference(&renderer.render(), reference, 2);
+}
+
+#[cfg(all(feature = "testing", feature = "text"))]
+#[test]
+fn reference_text() {
+    use crate::software::SoftwareRenderer;
+    use crate::testing::assert_matches_reference;
+    use crate::text::Alignment;
+    let mut renderer = SoftwareRenderer::new(160, 32);
+    renderer.set_clear_color((1.0, 1.0, 1.0, 1.0));
+    let font = include_bytes!("../examples/res/FiraSans.ttf").to_vec();
+    renderer.set_font(font).unwrap();
+    let text = "The quick brown fox";
+    renderer.draw_text(text, (4.0, 4.0, 0.0), 16.0, Alignment::Left, None, None);
+    let reference = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/reference/text.png");
+    assert_matches_reference(&renderer.render(), reference, 2);
+}
+
+#[test]
+fn pixel_perfect_snaps_quads_to_whole_pixels() {
+    let mut renderer = Renderer::new(false);
+    let call = renderer.create_dummy_draw_call();
+    renderer.set_pixel_perfect(true);
+    renderer.draw_quad(
+        (10.4, 20.6, 26.2, 36.4),
+        (0.0, 0.0, 1.0, 1.0),
+        (1.0, 1.0, 1.0, 1.0),
+        (0.0, 0.0, 0.0),
+        0.0,
+        &call,
+    );
+    let vertices = renderer.queued_vertices(&call);
+    // The top-left and bottom-right corners: the size stays 16x16
+    assert_eq!(&vertices[0..2], &[10.0, 21.0]);
+    assert_eq!(&vertices[24..26], &[26.0, 37.0]);
+}
+
+#[test]
+fn virtual_resolution_letterboxing() {
+    // 3x fits, 4x doesn't: the rest is split evenly into bars
+    let rect = renderer::letterbox((0, 0, 1000, 600), (320, 180));
+    assert_eq!(rect, (20, 30, 960, 540));
+    // Offset by the viewport
+    let rect = renderer::letterbox((100, 50, 320, 200), (320, 180));
+    assert_eq!(rect, (100, 60, 320, 180));
+    // Smaller than the virtual resolution: scaled down to fit
+    let rect = renderer::letterbox((0, 0, 160, 120), (320, 180));
+    assert_eq!(rect, (0, 15, 160, 90));
+}
+
+#[test]
+fn srgb_to_linear_conversion() {
+    assert_eq!(renderer::srgb_to_linear(0.0), 0.0);
+    assert!((renderer::srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+    // The linear segment near black
+    assert!((renderer::srgb_to_linear(0.04) - 0.04 / 12.92).abs() < 1e-6);
+    // Middle gray in sRGB is about 21% brightness in linear space
+    assert!((renderer::srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+}
+
 #[bench]
 fn bench_draw_100k_quads(b: &mut Bencher) {
     let mut renderer = Renderer::new(false);
@@ -20,6 +1048,34 @@ fn bench_draw_100k_quads(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_draw_100k_quads_batched(b: &mut Bencher) {
+    let mut renderer = Renderer::new(false);
+    let draw_call = renderer.create_dummy_draw_call();
+    let quads: Vec<QuadDesc> = (0..100_000)
+        .map(|i| QuadDesc {
+            coords: ((i % 1000) as f32, 0.0, 10.0, 10.0),
+            texcoords: (0.3, 0.3, 0.6, 0.6),
+            color: (1.0, 1.0, 0.5, 1.0),
+            rotation: (28.0, 5.0, 5.0),
+            depth: 0.0,
+        })
+        .collect();
+    b.iter(|| {
+        renderer.draw_quads(&quads, &draw_call);
+        renderer.flush();
+    });
+}
+
+#[bench]
+fn bench_draw_ranges_100k_quads(b: &mut Bencher) {
+    b.iter(|| {
+        let unlimited = renderer::draw_ranges(600_000, None).count();
+        let limited = renderer::draw_ranges(600_000, Some(65_536)).count();
+        test::black_box((unlimited, limited))
+    });
+}
+
 #[bench]
 fn bench_draw_quad(b: &mut Bencher) {
     let mut renderer = Renderer::new(false);
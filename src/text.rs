@@ -2,14 +2,16 @@
 //!
 //! Mostly just a wrapper/integration layer for rusttype.
 
+use crate::command_buffer::{CommandBuffer, DrawCommand};
+use crate::error::FaeError;
 use crate::gl;
 use crate::gl::types::*;
 use crate::image::Image;
-use crate::renderer::{DrawCallHandle, DrawCallParameters, Renderer, Shaders};
+use crate::renderer::{BlendMode, DrawCallHandle, DrawCallParameters, Renderer, Shaders};
+use crate::window::{Event, Key, Window};
 use rusttype::gpu_cache::Cache;
 use rusttype::*;
-use std::cell::RefCell;
-use std::error::Error;
+use std::ops::Range;
 use unicode_normalization::UnicodeNormalization;
 
 pub(crate) const GLYPH_CACHE_WIDTH: u32 = 1024;
@@ -22,9 +24,80 @@ const DEFAULT_TEXT_SHADERS: Shaders = Shaders {
     fragment_shader_330: include_str!("shaders/text.frag"),
 };
 
+const SDF_TEXT_SHADERS: Shaders = Shaders {
+    vertex_shader_110: include_str!("shaders/legacy/texquad.vert"),
+    fragment_shader_110: include_str!("shaders/legacy/text_sdf.frag"),
+    vertex_shader_330: include_str!("shaders/texquad.vert"),
+    fragment_shader_330: include_str!("shaders/text_sdf.frag"),
+};
+
+/// The font size (in physical pixels) glyphs are rasterized at in
+/// [`RenderingMode::Sdf`], regardless of the size they're drawn at.
+const SDF_GLYPH_SIZE: f32 = 48.0;
+/// The maximum distance from the edges of the glyphs stored in the
+/// distance fields, in pixels of the rasterized glyphs.
+pub(crate) const SDF_SPREAD: f32 = 4.0;
+
+/// Defines how glyphs are rasterized into the glyph cache and drawn.
+/// See [`TextRenderer::set_rendering_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderingMode {
+    /// Glyphs are rasterized at the size they're drawn at. Gives the
+    /// sharpest results for text drawn at its intended size.
+    Bitmap,
+    /// Glyphs are rasterized once, at a fixed size, into signed
+    /// distance fields, which stay crisp when drawn at any
+    /// size. Useful for text that is scaled a lot, like on a
+    /// zoomable canvas. Small text is a bit blurrier than in
+    /// `Bitmap` mode.
+    Sdf,
+}
+
+/// Controls how glyphs are rasterized, for tuning the legibility of
+/// small text. See [`TextRenderer::set_rasterization_options`].
+///
+/// The rasterizer (rusttype) doesn't support the fonts' hinting
+/// instructions or auto-hinting, so glyph outlines are always
+/// rasterized as they are. Both of these options are implemented by
+/// fae on top of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RasterOptions {
+    /// How glyphs are aligned to the pixel grid. Default value:
+    /// `Hinting::None`
+    pub hinting: Hinting,
+    /// The gamma applied to the glyphs' coverage: each coverage value
+    /// `c` (from 0 to 1) becomes `c^(1 / gamma)`. Values above 1 make
+    /// the anti-aliased edges, and thin text, heavier, values below 1
+    /// make them lighter. Not applied in [`RenderingMode::Sdf`].
+    /// Default value: `1.0`
+    pub gamma: f32,
+}
+
+impl Default for RasterOptions {
+    fn default() -> RasterOptions {
+        RasterOptions {
+            hinting: Hinting::None,
+            gamma: 1.0,
+        }
+    }
+}
+
+/// How glyphs are aligned to the pixel grid. See
+/// [`RasterOptions::hinting`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hinting {
+    /// Glyphs are placed at their exact, subpixel positions.
+    None,
+    /// Glyphs are placed at whole physical pixels, so that every
+    /// glyph's edges are anti-aliased the same way. Makes small text
+    /// look more consistent and less blurry, at the cost of slightly
+    /// uneven spacing.
+    PixelGrid,
+}
+
 /// Defines the alignment of text.
 #[allow(dead_code)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Alignment {
     /// Text is aligned to the left.
     Left,
@@ -34,26 +107,200 @@ pub enum Alignment {
     Center,
 }
 
+#[derive(Clone, Copy, Debug)]
+enum TabWidth {
+    Pixels(f32),
+    Spaces(f32),
+}
+
 struct TextRender {
     glyphs: Vec<SizedGlyph>,
     clip_area: Option<(f32, f32, f32, f32)>,
     z: f32,
+    /// The text draw call the glyphs are drawn with, see
+    /// [`TextRenderer::create_text_draw_call`].
+    draw_call: Option<TextDrawCall>,
+    /// The DPI factor the glyphs were laid out with.
+    dpi_factor: f32,
+    /// The rotation of the text block: (radians, pivot x, pivot y),
+    /// where the pivot is in absolute logical pixels.
+    rotation: (f32, f32, f32),
 }
 
 #[derive(Clone)]
 struct SizedGlyph {
     glyph: PositionedGlyph<'static>,
     width: f32,
+    /// The index of the character this glyph represents.
+    index: usize,
+}
+
+#[derive(Clone)]
+struct Row {
+    glyphs: Vec<SizedGlyph>,
+    /// Where the caret is at the start of the row, in physical pixels.
+    start_x: f32,
+    /// The index of the first character on this row.
+    first_index: usize,
+}
+
+impl Row {
+    /// Returns the x coordinate of a caret placed before the
+    /// character at `index`, in physical pixels.
+    fn caret_x(&self, index: usize) -> f32 {
+        if let Some(glyph) = self.glyphs.iter().find(|glyph| glyph.index >= index) {
+            glyph.glyph.position().x
+        } else {
+            self.end_x()
+        }
+    }
+
+    /// Returns the x coordinate of the end of the row's last glyph,
+    /// in physical pixels.
+    fn end_x(&self) -> f32 {
+        if let Some(glyph) = self.glyphs.last() {
+            glyph.glyph.position().x + glyph.width
+        } else {
+            self.start_x
+        }
+    }
+}
+
+/// A piece of text rasterized into its own texture with
+/// [`TextRenderer::bake_text`], which can be drawn as a single quad
+/// with [`Renderer::draw_baked_text`], without laying the text out
+/// again.
+#[derive(Debug)]
+pub struct BakedText {
+    draw_call: DrawCallHandle,
+    /// The width of the baked text, in logical pixels.
+    pub width: f32,
+    /// The height of the baked text, in logical pixels.
+    pub height: f32,
+}
+
+impl BakedText {
+    /// Returns the draw call holding the baked texture. The draw
+    /// call can be deleted with [`Renderer::delete_draw_call`] when
+    /// the text is no longer needed.
+    pub fn draw_call(&self) -> DrawCallHandle {
+        self.draw_call
+    }
+}
+
+/// A separate batch of text, drawn in its own draw calls, so that it
+/// is ordered independently of the rest of the text. Created with
+/// [`TextRenderer::create_text_draw_call`], and used with
+/// [`TextRenderer::set_text_draw_call`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextDrawCall {
+    index: usize,
+}
+
+/// One texture of the glyph cache, and the rusttype cache tracking
+/// which glyphs are in it.
+struct GlyphCachePage {
+    cache: Cache<'static>,
+    draw_call: DrawCallHandle,
+    /// The draw calls of the [`TextDrawCall`]s, sharing the texture
+    /// of `draw_call`.
+    text_draw_calls: Vec<DrawCallHandle>,
+}
+
+impl GlyphCachePage {
+    fn create(
+        width: u32,
+        height: u32,
+        position_tolerance: f32,
+        mode: RenderingMode,
+        text_draw_call_count: usize,
+        renderer: &mut Renderer,
+    ) -> Result<GlyphCachePage, FaeError> {
+        let glyph_cache_image =
+            Image::from_color(width as i32, height as i32, &[0]).format(gl::RED);
+        let params = DrawCallParameters {
+            image: Some(glyph_cache_image),
+            ..GlyphCachePage::draw_call_parameters(mode)
+        };
+        let mut page = GlyphCachePage {
+            cache: Cache::builder()
+                .dimensions(width, height)
+                .position_tolerance(position_tolerance)
+                .build(),
+            draw_call: renderer.create_draw_call(params)?,
+            text_draw_calls: Vec::with_capacity(text_draw_call_count),
+        };
+        for _ in 0..text_draw_call_count {
+            if let Err(err) = page.add_text_draw_call(mode, renderer) {
+                page.delete(renderer);
+                return Err(err);
+            }
+        }
+        Ok(page)
+    }
+
+    fn draw_call_parameters(mode: RenderingMode) -> DrawCallParameters {
+        DrawCallParameters {
+            shaders: Some(match mode {
+                RenderingMode::Bitmap => DEFAULT_TEXT_SHADERS,
+                RenderingMode::Sdf => SDF_TEXT_SHADERS,
+            }),
+            blend_mode: BlendMode::Premultiplied,
+            magnification_smoothing: mode == RenderingMode::Sdf,
+            ..Default::default()
+        }
+    }
+
+    fn add_text_draw_call(
+        &mut self,
+        mode: RenderingMode,
+        renderer: &mut Renderer,
+    ) -> Result<(), FaeError> {
+        let params = GlyphCachePage::draw_call_parameters(mode);
+        let call = renderer.create_draw_call_sharing_texture(params, &self.draw_call)?;
+        self.text_draw_calls.push(call);
+        Ok(())
+    }
+
+    /// Returns the draw call the text of `text_draw_call` should be
+    /// drawn with.
+    fn draw_call_for(&self, text_draw_call: Option<TextDrawCall>) -> &DrawCallHandle {
+        match text_draw_call {
+            Some(TextDrawCall { index }) => &self.text_draw_calls[index],
+            None => &self.draw_call,
+        }
+    }
+
+    fn delete(self, renderer: &mut Renderer) {
+        for call in self.text_draw_calls {
+            renderer.delete_draw_call(call);
+        }
+        renderer.delete_draw_call(self.draw_call);
+    }
 }
 
 /// Holds the state required for text rendering, such as the font, and
 /// a text draw call queue.
 pub struct TextRenderer {
     font: Font<'static>,
-    cache: RefCell<Cache<'static>>,
+    /// The glyph cache textures. There's always at least one, and
+    /// more are created when the glyphs of a frame don't fit in the
+    /// existing ones.
+    pages: Vec<GlyphCachePage>,
+    rendering_mode: RenderingMode,
     cached_text: Vec<TextRender>,
     dpi_factor: f32,
-    draw_call: DrawCallHandle,
+    auto_dpi: bool,
+    tab_width: TabWidth,
+    /// Extra space between glyphs, in logical pixels.
+    letter_spacing: f32,
+    kerning: bool,
+    raster_options: RasterOptions,
+    /// The amount of [`TextDrawCall`]s created.
+    text_draw_call_count: usize,
+    /// The text draw call used by `draw_text`, `None` for the
+    /// default one.
+    text_draw_call: Option<TextDrawCall>,
 }
 
 impl TextRenderer {
@@ -62,39 +309,61 @@ impl TextRenderer {
     /// - `font_data`: The bytes that consist a .ttf file. See the `rusttype` crate's documentation for what kinds of fonts are supported.
     ///
     /// - `subpixel_accurate`: If true, glyphs will be rendered if
-    /// their subpixel position differs by very small amounts, to
-    /// render the font more accurately for that position. In
-    /// practice, I haven't seen any difference, so I'd recommend
-    /// setting this to false. (Internally this maps to `rusttype`'s
-    /// `CacheBuilder`'s position tolerance value, true = 0.1, false =
-    /// 1.0).
+    ///   their subpixel position differs by very small amounts, to
+    ///   render the font more accurately for that position. In
+    ///   practice, I haven't seen any difference, so I'd recommend
+    ///   setting this to false. (Internally this maps to `rusttype`'s
+    ///   `CacheBuilder`'s position tolerance value, true = 0.1, false =
+    ///   1.0).
     pub fn create(
         font_data: Vec<u8>,
         subpixel_accurate: bool,
         renderer: &mut Renderer,
-    ) -> Result<TextRenderer, Box<Error>> {
-        let glyph_cache_image =
-            Image::from_color(GLYPH_CACHE_WIDTH as i32, GLYPH_CACHE_HEIGHT as i32, &[0])
-                .format(gl::RED);
-        let params = DrawCallParameters {
-            image: Some(glyph_cache_image),
-            shaders: Some(DEFAULT_TEXT_SHADERS),
-            ..Default::default()
-        };
-        let draw_call = renderer.create_draw_call(params);
-        let position_tolerance = if subpixel_accurate { 0.1 } else { 1.0 };
+    ) -> Result<TextRenderer, FaeError> {
+        let position_tolerance: f32 = if subpixel_accurate { 0.1 } else { 1.0 };
+        let page = GlyphCachePage::create(
+            GLYPH_CACHE_WIDTH,
+            GLYPH_CACHE_HEIGHT,
+            position_tolerance,
+            RenderingMode::Bitmap,
+            0,
+            renderer,
+        )?;
+
+        Ok(TextRenderer {
+            font: Font::from_bytes(font_data).map_err(|err| FaeError::FontLoad(err.to_string()))?,
+            pages: vec![page],
+            rendering_mode: RenderingMode::Bitmap,
+            cached_text: Vec::new(),
+            dpi_factor: 1.0,
+            auto_dpi: false,
+            tab_width: TabWidth::Spaces(4.0),
+            letter_spacing: 0.0,
+            kerning: true,
+            raster_options: RasterOptions::default(),
+            text_draw_call_count: 0,
+            text_draw_call: None,
+        })
+    }
 
+    /// Creates a text renderer without a glyph cache, which doesn't
+    /// need an OpenGL context. Only usable for laying out and
+    /// rasterizing text on the CPU, for the
+    /// [`SoftwareRenderer`](../software/struct.SoftwareRenderer.html).
+    pub(crate) fn create_headless(font_data: Vec<u8>) -> Result<TextRenderer, FaeError> {
         Ok(TextRenderer {
-            font: Font::from_bytes(font_data)?,
-            cache: RefCell::new(
-                Cache::builder()
-                    .dimensions(GLYPH_CACHE_WIDTH, GLYPH_CACHE_HEIGHT)
-                    .position_tolerance(position_tolerance)
-                    .build(),
-            ),
+            font: Font::from_bytes(font_data).map_err(|err| FaeError::FontLoad(err.to_string()))?,
+            pages: Vec::new(),
+            rendering_mode: RenderingMode::Bitmap,
             cached_text: Vec::new(),
             dpi_factor: 1.0,
-            draw_call,
+            auto_dpi: false,
+            tab_width: TabWidth::Spaces(4.0),
+            letter_spacing: 0.0,
+            kerning: true,
+            raster_options: RasterOptions::default(),
+            text_draw_call_count: 0,
+            text_draw_call: None,
         })
     }
 
@@ -105,18 +374,227 @@ impl TextRenderer {
         self.dpi_factor = dpi_factor;
     }
 
-    /// Draws text.
+    /// Resizes the glyph cache, which is 1024x1024 by default. A
+    /// larger cache fits more glyphs (eg. for large character sets,
+    /// or large font sizes) before glyphs have to be evicted. The
+    /// size is clamped to [`Renderer::max_texture_size`]. Clears the
+    /// cache, so it's best to call this right after creating the
+    /// text renderer.
+    ///
+    /// If the glyphs queued in a frame don't fit in the cache even
+    /// after evicting the unused ones, the rest of them are cached
+    /// into additional textures ("pages") of the same size, and the
+    /// text is drawn in one draw call per page.
+    pub fn set_cache_size(&mut self, width: u32, height: u32, renderer: &mut Renderer) {
+        let max_size = renderer.max_texture_size().max(1) as u32;
+        let (width, height) = (width.min(max_size).max(1), height.min(max_size).max(1));
+        let image = Image::from_color(width as i32, height as i32, &[0]).format(gl::RED);
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        }
+        for page in &mut self.pages {
+            page.cache
+                .to_builder()
+                .dimensions(width, height)
+                .rebuild(&mut page.cache);
+            renderer.replace_draw_call_image(&page.draw_call, &image);
+        }
+    }
+
+    /// Sets how glyphs are rasterized and drawn, see
+    /// [`RenderingMode`]. The default is `Bitmap`. Changing the mode
+    /// recreates the glyph cache, so it's best to call this right
+    /// after creating the text renderer.
+    pub fn set_rendering_mode(
+        &mut self,
+        mode: RenderingMode,
+        renderer: &mut Renderer,
+    ) -> Result<(), FaeError> {
+        if mode == self.rendering_mode {
+            return Ok(());
+        }
+        let cache = &self.pages[0].cache;
+        let (width, height) = cache.dimensions();
+        let page = GlyphCachePage::create(
+            width,
+            height,
+            cache.position_tolerance(),
+            mode,
+            self.text_draw_call_count,
+            renderer,
+        )?;
+        for old_page in self.pages.drain(..) {
+            old_page.delete(renderer);
+        }
+        self.pages.push(page);
+        self.rendering_mode = mode;
+        Ok(())
+    }
+
+    /// Returns the mode set with
+    /// [`TextRenderer::set_rendering_mode`].
+    pub fn rendering_mode(&self) -> RenderingMode {
+        self.rendering_mode
+    }
+
+    /// Returns the size of the glyph cache, in pixels. Arrangement:
+    /// (width, height)
+    pub fn cache_size(&self) -> (u32, u32) {
+        self.pages[0].cache.dimensions()
+    }
+
+    /// Returns the OpenGL texture handle of the (first page of the)
+    /// glyph cache. The texture is a single-channel (`GL_RED`) atlas
+    /// of the glyphs rasterized so far.
+    pub fn glyph_cache_texture(&self, renderer: &Renderer) -> GLuint {
+        renderer.get_texture(&self.pages[0].draw_call)
+    }
+
+    /// Returns the draw call of the first page of the glyph cache,
+    /// where most text is drawn. Mostly useful for debugging the
+    /// glyph cache with [`Renderer::draw_debug_texture`], to see how
+    /// the glyphs are packed into it.
+    pub fn glyph_cache_draw_call(&self) -> &DrawCallHandle {
+        &self.pages[0].draw_call
+    }
+
+    /// Returns the draw calls of all the pages of the glyph cache.
+    /// See [`TextRenderer::set_cache_size`].
+    pub fn glyph_cache_draw_calls(&self) -> impl Iterator<Item = &DrawCallHandle> {
+        self.pages.iter().map(|page| &page.draw_call)
+    }
+
+    /// Creates a new text draw call: a separate batch of text, which
+    /// shares the glyph cache with the rest of the text, but has its
+    /// own vertex buffers, so it's ordered independently from the
+    /// other batches. Text drawn into different text draw calls can
+    /// be on different layers, eg. background labels behind sprites
+    /// and a HUD on top of them. See
+    /// [`TextRenderer::set_text_draw_call`].
+    ///
+    /// Results in the same errors as
+    /// [`Renderer::create_draw_call`].
+    pub fn create_text_draw_call(
+        &mut self,
+        renderer: &mut Renderer,
+    ) -> Result<TextDrawCall, FaeError> {
+        let mode = self.rendering_mode;
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            if let Err(err) = page.add_text_draw_call(mode, renderer) {
+                // Keep every page with the same amount of calls
+                for page in &mut self.pages[..i] {
+                    if let Some(call) = page.text_draw_calls.pop() {
+                        renderer.delete_draw_call(call);
+                    }
+                }
+                return Err(err);
+            }
+        }
+        let text_draw_call = TextDrawCall {
+            index: self.text_draw_call_count,
+        };
+        self.text_draw_call_count += 1;
+        Ok(text_draw_call)
+    }
+
+    /// Sets the text draw call which the text drawn after this is
+    /// drawn with, until this is called again. `None` sets it back to
+    /// the default one. See [`TextRenderer::create_text_draw_call`].
+    pub fn set_text_draw_call(&mut self, text_draw_call: Option<TextDrawCall>) {
+        self.text_draw_call = text_draw_call;
+    }
+
+    /// Returns the draw calls of `text_draw_call`, one for each page
+    /// of the glyph cache, or the draw calls of the default text
+    /// draw call, if `None`. The draw calls can change when the
+    /// glyph cache gets new pages, or when the rendering mode
+    /// changes.
+    pub fn text_draw_call_handles(
+        &self,
+        text_draw_call: Option<TextDrawCall>,
+    ) -> impl Iterator<Item = &DrawCallHandle> {
+        self.pages
+            .iter()
+            .map(move |page| page.draw_call_for(text_draw_call))
+    }
+
+    /// Toggles automatic DPI scaling. When enabled, the DPI factor
+    /// is taken from the [`Renderer`] (see
+    /// [`Renderer::set_dpi_factor`]) in
+    /// [`TextRenderer::compose_draw_call`], and glyphs are
+    /// rasterized at `font_size * dpi_factor` while still being laid
+    /// out at the logical `font_size`. This means
+    /// [`TextRenderer::update_dpi_factor`] doesn't need to be called.
+    ///
+    /// Text drawn before the first composition after a DPI change is
+    /// laid out with the old factor and then rescaled, so it might
+    /// be positioned very slightly differently for that one frame.
+    pub fn set_auto_dpi(&mut self, auto_dpi: bool) {
+        self.auto_dpi = auto_dpi;
+    }
+
+    /// Sets the distance between tab stops, in logical pixels. A
+    /// `\t` in drawn text advances the caret to the next multiple of
+    /// this width, measured from the start of the row. By default,
+    /// tab stops are four space-widths apart.
+    pub fn set_tab_width(&mut self, pixels: f32) {
+        self.tab_width = TabWidth::Pixels(pixels);
+    }
+
+    /// Sets the distance between tab stops as a multiple of the
+    /// width of a space in the font (at the size it's drawn at). See
+    /// [`TextRenderer::set_tab_width`].
+    pub fn set_tab_width_in_spaces(&mut self, spaces: f32) {
+        self.tab_width = TabWidth::Spaces(spaces);
+    }
+
+    /// Sets the space added between every glyph (tracking), in
+    /// logical pixels. Negative values tighten the text. Taken into
+    /// account in measuring and wrapping as well. The default is 0.
+    pub fn set_letter_spacing(&mut self, pixels: f32) {
+        self.letter_spacing = pixels;
+    }
+
+    /// Toggles the font's kerning, which adjusts the space between
+    /// specific pairs of glyphs (eg. "AV"). Enabled by default.
+    pub fn set_kerning(&mut self, kerning: bool) {
+        self.kerning = kerning;
+    }
+
+    /// Sets the options for rasterizing glyphs, see [`RasterOptions`]
+    /// for what they do. Changing the gamma clears the glyph cache,
+    /// so that the glyphs are rasterized again.
+    pub fn set_rasterization_options(&mut self, options: RasterOptions) {
+        if options.gamma != self.raster_options.gamma {
+            for page in &mut self.pages {
+                page.cache.clear();
+            }
+        }
+        self.raster_options = options;
+    }
+
+    /// Returns the options set with
+    /// [`TextRenderer::set_rasterization_options`].
+    pub fn rasterization_options(&self) -> RasterOptions {
+        self.raster_options
+    }
+
+    /// Draws text, and returns the bounding rectangle of the laid out
+    /// text, in logical pixels. Arrangement: (min x, min y, max x,
+    /// max y). The rectangle takes alignment and wrapping into
+    /// account, and is as tall as the rows of text, so it can be used
+    /// for drawing backgrounds or positioning things after the text.
     ///
     /// - `text`: The rendered text.
     /// - `(x, y, z)`: The position (top-left) of the rendered text
-    /// area.
+    ///   area.
     /// - `font_size`: The size of the font.
     /// - `max_row_width`: The width at which the text will wrap. An
-    /// effort is made to break lines at word boundaries.
+    ///   effort is made to break lines at word boundaries.
     /// - `clip_area`: The area which defines where the text will be
-    /// rendered. Text outside the area will be cut off. For an
-    /// example use case, think editable text boxes: the clip area
-    /// would be the text box.
+    ///   rendered. Text outside the area will be cut off. For an
+    ///   example use case, think editable text boxes: the clip area
+    ///   would be the text box.
     pub fn draw_text(
         &mut self,
         text: &str,
@@ -125,56 +603,356 @@ impl TextRenderer {
         alignment: Alignment,
         max_row_width: Option<f32>,
         clip_area: Option<(f32, f32, f32, f32)>,
-    ) {
-        let rows = self.collect_glyphs(x, y, max_row_width, font_size, text);
-        let dpi = self.dpi_factor;
+    ) -> (f32, f32, f32, f32) {
+        self.draw_text_rotated(
+            text,
+            (x, y, z),
+            font_size,
+            alignment,
+            max_row_width,
+            clip_area,
+            (0.0, 0.0, 0.0),
+        )
+    }
+
+    /// Draws text rotated around a pivot point. The text is laid out
+    /// exactly like in [`TextRenderer::draw_text`], and then the
+    /// whole block is rotated, so the returned bounding rectangle is
+    /// the unrotated one.
+    ///
+    /// - `rotation`: The rotation of the text, in radians, and the
+    ///   point (relative to `x` and `y`, in logical pixels) around
+    ///   which the text pivots. Arrangement: (radians, x, y)
+    ///
+    /// Note that `clip_area` is applied before the rotation, so it
+    /// clips the text in its unrotated space.
+    ///
+    /// See [`TextRenderer::draw_text`] for the rest of the
+    /// parameters' docs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text_rotated(
+        &mut self,
+        text: &str,
+        (x, y, z): (f32, f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        clip_area: Option<(f32, f32, f32, f32)>,
+        rotation: (f32, f32, f32),
+    ) -> (f32, f32, f32, f32) {
+        let rows = self.layout(text, x, y, font_size, alignment, max_row_width);
+        let bounds = self.measure_rows(&rows, x, y, font_size);
+        let (rads, pivot_x, pivot_y) = rotation;
 
         let mut final_glyphs = Vec::with_capacity(text.len());
+        for row in rows {
+            final_glyphs.extend_from_slice(&row.glyphs);
+        }
 
-        // Collect the rows and offset them according to the alignment
-        if let Some(width) = max_row_width {
-            match alignment {
-                Alignment::Right => {
-                    for row in rows {
-                        let row = if let Some((row_width, _)) = measure_text(&row, dpi) {
-                            let offset = width - row_width;
-                            offset_glyphs(row, offset, 0.0, dpi)
-                        } else {
-                            row
-                        };
-                        final_glyphs.extend_from_slice(&row);
-                    }
-                }
+        self.cached_text.push(TextRender {
+            glyphs: final_glyphs,
+            clip_area,
+            z,
+            draw_call: self.text_draw_call,
+            dpi_factor: self.dpi_factor,
+            rotation: (rads, x + pivot_x, y + pivot_y),
+        });
+        bounds
+    }
 
-                Alignment::Center => {
-                    for row in rows {
-                        let row = if let Some((row_width, _)) = measure_text(&row, dpi) {
-                            let offset = (width - row_width) / 2.0;
-                            offset_glyphs(row, offset, 0.0, dpi)
-                        } else {
-                            row
-                        };
-                        final_glyphs.extend_from_slice(&row);
-                    }
-                }
+    /// Returns the index of the character closest to `point`, if the
+    /// text was drawn with [`TextRenderer::draw_text`] using the same
+    /// parameters. The index is where a caret should be placed when
+    /// the text is clicked at `point`, so it ranges from 0 to the
+    /// amount of characters in the text (inclusive).
+    ///
+    /// Indices count `char`s in the text after NFC normalization,
+    /// which is applied to all drawn text.
+    pub fn index_at_point(
+        &self,
+        text: &str,
+        (x, y): (f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        (point_x, point_y): (f32, f32),
+    ) -> usize {
+        let rows = self.layout(text, x, y, font_size, alignment, max_row_width);
+        let (_, advance_height) = self.row_heights(font_size);
+        let row_index = ((point_y - y) / advance_height).floor().max(0.0) as usize;
+        let row = &rows[row_index.min(rows.len() - 1)];
+
+        let point_x = point_x * self.dpi_factor;
+        for glyph in &row.glyphs {
+            if point_x < glyph.glyph.position().x + glyph.width / 2.0 {
+                return glyph.index;
+            }
+        }
+        row.glyphs
+            .last()
+            .map(|glyph| glyph.index + 1)
+            .unwrap_or(row.first_index)
+    }
+
+    /// Returns the position of a caret placed before the character at
+    /// `index`, if the text was drawn with
+    /// [`TextRenderer::draw_text`] using the same parameters. The
+    /// position is the top of the caret, which should be as tall as
+    /// the rows of text. See [`TextRenderer::index_at_point`] for how
+    /// the indices are counted.
+    pub fn caret_position(
+        &self,
+        text: &str,
+        (x, y): (f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        index: usize,
+    ) -> (f32, f32) {
+        let rows = self.layout(text, x, y, font_size, alignment, max_row_width);
+        let (_, advance_height) = self.row_heights(font_size);
+        let row_index = rows
+            .iter()
+            .rposition(|row| row.first_index <= index)
+            .unwrap_or(0);
+        let row = &rows[row_index];
+        (
+            row.caret_x(index) / self.dpi_factor,
+            y + row_index as f32 * advance_height,
+        )
+    }
+
+    /// Returns the rectangles covering the characters in the
+    /// `selection` range, one per row, if the text was
+    /// drawn with [`TextRenderer::draw_text`] using the same
+    /// parameters. Useful for drawing selection highlights behind the
+    /// text. See [`TextRenderer::index_at_point`] for how the indices
+    /// are counted.
+    ///
+    /// The first and last rows of a selection spanning several rows
+    /// are covered from the start and to the end of the selection,
+    /// and the rows in between are covered fully. The rectangles are
+    /// as tall as the distance between rows, so that the rectangles
+    /// of consecutive rows don't leave gaps between them.
+    /// Arrangement: (x, y, width, height)
+    pub fn selection_rects(
+        &self,
+        text: &str,
+        (x, y): (f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        selection: Range<usize>,
+    ) -> Vec<(f32, f32, f32, f32)> {
+        let Range { start, end } = selection;
+        if start >= end {
+            return Vec::new();
+        }
+        let rows = self.layout(text, x, y, font_size, alignment, max_row_width);
+        let (_, advance_height) = self.row_heights(font_size);
+        let dpi = self.dpi_factor;
+        let mut rects = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let row_end = rows.get(i + 1).map_or(usize::MAX, |next| next.first_index);
+            if end <= row.first_index || start >= row_end {
+                continue;
+            }
+            let x0 = row.caret_x(start.max(row.first_index));
+            let x1 = if end >= row_end {
+                row.end_x()
+            } else {
+                row.caret_x(end)
+            };
+            rects.push((
+                x0 / dpi,
+                y + i as f32 * advance_height,
+                (x1 - x0) / dpi,
+                advance_height,
+            ));
+        }
+        rects
+    }
 
-                Alignment::Left => {
-                    for row in rows {
-                        final_glyphs.extend_from_slice(&row);
+    /// Rasterizes the text into a new texture, to be drawn with
+    /// [`Renderer::draw_baked_text`]. This is meant for labels that
+    /// rarely change, as the text is laid out only once, and drawing
+    /// it is just one quad. When the text does change, use
+    /// [`TextRenderer::rebake_text`].
+    ///
+    /// The text is rasterized at the current DPI factor (see
+    /// [`TextRenderer::update_dpi_factor`]), and the texture covers
+    /// the area from the top-left of the text to the bottom-right of
+    /// the bounding rectangle returned by
+    /// [`TextRenderer::draw_text`].
+    ///
+    /// See [`TextRenderer::draw_text`] for the parameters' docs.
+    pub fn bake_text(
+        &self,
+        text: &str,
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        renderer: &mut Renderer,
+    ) -> Result<BakedText, FaeError> {
+        let (image, (width, height)) = self.rasterize(text, font_size, alignment, max_row_width);
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        }
+        let params = DrawCallParameters {
+            image: Some(image),
+            shaders: Some(DEFAULT_TEXT_SHADERS),
+            blend_mode: BlendMode::Premultiplied,
+            ..Default::default()
+        };
+        Ok(BakedText {
+            draw_call: renderer.create_draw_call(params)?,
+            width,
+            height,
+        })
+    }
+
+    /// Rasterizes new text into the texture of `baked`, reusing its
+    /// draw call. See [`TextRenderer::bake_text`].
+    pub fn rebake_text(
+        &self,
+        baked: &mut BakedText,
+        text: &str,
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+        renderer: &mut Renderer,
+    ) {
+        let (image, (width, height)) = self.rasterize(text, font_size, alignment, max_row_width);
+        unsafe {
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        }
+        renderer.replace_draw_call_image(&baked.draw_call, &image);
+        baked.width = width;
+        baked.height = height;
+    }
+
+    /// Lays out the text at the origin and draws the glyphs' coverage
+    /// into a single-channel image. Returns the image and its size in
+    /// logical pixels.
+    pub(crate) fn rasterize(
+        &self,
+        text: &str,
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+    ) -> (Image, (f32, f32)) {
+        let dpi = self.dpi_factor;
+        let rows = self.layout(text, 0.0, 0.0, font_size, alignment, max_row_width);
+        let (_, _, max_x, max_y) = self.measure_rows(&rows, 0.0, 0.0, font_size);
+        let width = ((max_x * dpi).ceil() as i32).max(1);
+        let height = ((max_y * dpi).ceil() as i32).max(1);
+
+        let mut image = Image::from_color(width, height, &[0]).format(gl::RED);
+        let gamma = self.raster_options.gamma;
+        for glyph in rows.iter().flat_map(|row| &row.glyphs) {
+            let glyph = &glyph.glyph;
+            if let Some(rect) = glyph.pixel_bounding_box() {
+                let pixels = &mut image.pixels;
+                glyph.draw(|x, y, coverage| {
+                    let (x, y) = (rect.min.x + x as i32, rect.min.y + y as i32);
+                    if x >= 0 && y >= 0 && x < width && y < height {
+                        let pixel = &mut pixels[(y * width + x) as usize];
+                        let coverage = apply_gamma(coverage, gamma);
+                        *pixel = pixel.saturating_add((coverage * 255.0) as u8);
                     }
-                }
+                });
             }
+        }
+        (image, (width as f32 / dpi, height as f32 / dpi))
+    }
+
+    /// Returns the bounding rectangle of the text, as returned by
+    /// [`TextRenderer::draw_text`], without drawing it.
+    pub(crate) fn measure(
+        &self,
+        text: &str,
+        (x, y): (f32, f32),
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+    ) -> (f32, f32, f32, f32) {
+        let rows = self.layout(text, x, y, font_size, alignment, max_row_width);
+        self.measure_rows(&rows, x, y, font_size)
+    }
+
+    /// Lays out the text into rows of glyphs, offset according to the
+    /// alignment.
+    fn layout(
+        &self,
+        text: &str,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        alignment: Alignment,
+        max_row_width: Option<f32>,
+    ) -> Vec<Row> {
+        let rows = self.collect_glyphs(x, y, max_row_width, font_size, text);
+        let dpi = self.dpi_factor;
+
+        let offset_multiplier = match (max_row_width, alignment) {
+            (None, _) | (_, Alignment::Left) => None,
+            (Some(width), Alignment::Right) => Some((width, 1.0)),
+            (Some(width), Alignment::Center) => Some((width, 0.5)),
+        };
+        let mut rows: Vec<Row> = if let Some((width, offset_multiplier)) = offset_multiplier {
+            rows.into_iter()
+                .map(|row| {
+                    let row_width = measure_text(&row.glyphs, dpi).map_or(0.0, |(w, _)| w);
+                    let offset = (width - row_width) * offset_multiplier;
+                    offset_row(row, offset, dpi)
+                })
+                .collect()
         } else {
-            for row in rows {
-                final_glyphs.extend_from_slice(&row);
+            rows
+        };
+
+        if self.raster_options.hinting == Hinting::PixelGrid {
+            for glyph in rows.iter_mut().flat_map(|row| &mut row.glyphs) {
+                let position = glyph.glyph.position();
+                glyph
+                    .glyph
+                    .set_position(point(position.x.round(), position.y.round()));
             }
         }
+        rows
+    }
 
-        self.cached_text.push(TextRender {
-            glyphs: final_glyphs,
-            clip_area,
-            z,
-        });
+    /// Returns the bounding rectangle of the rows, where the width is
+    /// defined by the glyphs' advances and the height by the font's
+    /// line metrics.
+    fn measure_rows(&self, rows: &[Row], x: f32, y: f32, font_size: f32) -> (f32, f32, f32, f32) {
+        let dpi = self.dpi_factor;
+        let (row_height, advance_height) = self.row_heights(font_size);
+        let row_count = rows.len().max(1) as f32;
+        let height = row_height + advance_height * (row_count - 1.0);
+
+        let mut horizontal: Option<(f32, f32)> = None;
+        for row in rows {
+            if let (Some(first), Some(last)) = (row.glyphs.first(), row.glyphs.last()) {
+                let min_x = first.glyph.position().x / dpi;
+                let max_x = (last.glyph.position().x + last.width) / dpi;
+                horizontal = Some(match horizontal {
+                    Some((min, max)) => (min.min(min_x), max.max(max_x)),
+                    None => (min_x, max_x),
+                });
+            }
+        }
+        let (min_x, max_x) = horizontal.unwrap_or((x, x));
+        (min_x, y, max_x, y + height)
+    }
+
+    /// Returns the height of a single row of text and the distance
+    /// between the tops of consecutive rows, in logical pixels.
+    fn row_heights(&self, font_size: f32) -> (f32, f32) {
+        let dpi = self.dpi_factor;
+        let v_metrics = self.font.v_metrics(Scale::uniform(font_size * dpi));
+        let row_height = v_metrics.ascent - v_metrics.descent;
+        (row_height / dpi, (row_height + v_metrics.line_gap) / dpi)
     }
 
     fn collect_glyphs(
@@ -184,24 +962,32 @@ impl TextRenderer {
         width: Option<f32>,
         font_size: f32,
         text: &str,
-    ) -> Vec<Vec<SizedGlyph>> {
+    ) -> Vec<Row> {
         let dpi = self.dpi_factor;
         let scale = Scale::uniform(font_size * dpi);
         let x = x * dpi;
         let y = y * dpi;
 
         let mut rows = Vec::new();
-        rows.push(Vec::with_capacity(text.len()));
+        rows.push(Row {
+            glyphs: Vec::with_capacity(text.len()),
+            start_x: x,
+            first_index: 0,
+        });
         let v_metrics = self.font.v_metrics(scale);
         let advance_height = v_metrics.ascent - v_metrics.descent + v_metrics.line_gap;
         let mut caret = point(x, y + v_metrics.ascent);
         let mut last_glyph_id = None;
 
-        let next_row = |caret: &mut Point<f32>, rows: &mut Vec<Vec<SizedGlyph>>| {
+        let next_row = |caret: &mut Point<f32>, rows: &mut Vec<Row>, first_index: usize| {
             *caret = point(x, caret.y + advance_height);
             // Pre-allocate based on the last row's length
-            let len = rows.last().unwrap().len();
-            rows.push(Vec::with_capacity(len));
+            let len = rows.last().unwrap().glyphs.len();
+            rows.push(Row {
+                glyphs: Vec::with_capacity(len),
+                start_x: x,
+                first_index,
+            });
         };
 
         let chars: Vec<char> = text.nfc().collect();
@@ -210,91 +996,192 @@ impl TextRenderer {
         while i < chars.len() {
             let c = chars[i];
             i += 1;
-            if c.is_control() {
+            let is_tab = c == '\t';
+            if c.is_control() && !is_tab {
                 if c == '\n' {
-                    next_row(&mut caret, &mut rows);
+                    next_row(&mut caret, &mut rows, i);
                 }
                 continue;
             }
-            if c == ' ' {
+            if c == ' ' || is_tab {
                 current_word_length = 0;
             } else {
                 current_word_length += 1;
             }
 
-            let glyph = self.font.glyph(c);
+            // Tabs are laid out as spaces with a custom advance, so
+            // that measurement and wrapping treat them like any other
+            // whitespace.
+            let glyph = self.font.glyph(if is_tab { ' ' } else { c });
             if let Some(id) = last_glyph_id.take() {
-                caret.x += self.font.pair_kerning(scale, id, glyph.id());
+                if !is_tab && self.kerning {
+                    caret.x += self.font.pair_kerning(scale, id, glyph.id());
+                }
             }
 
-            if width.is_some() && caret.x > (x + width.unwrap()) * dpi {
+            if width.is_some() && caret.x > x + width.unwrap() * dpi {
                 if let Some(ref mut last_row) = rows.last_mut() {
-                    let len = last_row.len();
+                    let len = last_row.glyphs.len();
                     if current_word_length < len {
-                        last_row.truncate(len - current_word_length);
+                        last_row.glyphs.truncate(len - current_word_length);
                         i -= current_word_length;
                     } else {
                         i -= 1;
                     }
                     current_word_length = 0;
                 }
-                next_row(&mut caret, &mut rows);
+                next_row(&mut caret, &mut rows, i);
                 continue;
             } else {
                 last_glyph_id = Some(glyph.id());
             }
 
             let glyph = glyph.scaled(scale).positioned(caret);
-            let advance_width = glyph.unpositioned().h_metrics().advance_width;
+            let advance_width = if is_tab {
+                self.tab_advance(caret.x - x, scale)
+            } else {
+                glyph.unpositioned().h_metrics().advance_width + self.letter_spacing * dpi
+            };
             caret.x += advance_width;
 
-            rows.last_mut().unwrap().push(SizedGlyph {
+            rows.last_mut().unwrap().glyphs.push(SizedGlyph {
                 glyph,
                 width: advance_width,
+                index: i - 1,
             });
         }
         rows
     }
 
-    /// Makes the `draw_text` calls called before this function
-    /// render. Should be called every frame before rendering.
-    pub fn compose_draw_call(&mut self, renderer: &mut Renderer) {
-        let &mut TextRenderer {
-            dpi_factor,
-            ref draw_call,
-            ..
-        } = self;
-        let mut cache = self.cache.borrow_mut();
+    /// Returns the distance from `row_offset` (the caret's distance
+    /// from the start of the row, in physical pixels) to the next tab
+    /// stop.
+    fn tab_advance(&self, row_offset: f32, scale: Scale) -> f32 {
+        let tab_width = match self.tab_width {
+            TabWidth::Pixels(pixels) => pixels * self.dpi_factor,
+            TabWidth::Spaces(spaces) => {
+                let space = self.font.glyph(' ').scaled(scale);
+                spaces * space.h_metrics().advance_width
+            }
+        };
+        if tab_width <= 0.0 {
+            return 0.0;
+        }
+        let next_stop = ((row_offset / tab_width).floor() + 1.0) * tab_width;
+        next_stop - row_offset
+    }
 
-        for text in &self.cached_text {
-            for glyph in &text.glyphs {
-                cache.queue_glyph(0, glyph.glyph.clone());
+    /// Draws the text commands recorded into the command buffer, like
+    /// calling [`TextRenderer::draw_text`] for each of them. The
+    /// other commands are skipped, see [`Renderer::execute`].
+    pub fn execute(&mut self, commands: &CommandBuffer) {
+        for command in commands.commands() {
+            if let DrawCommand::Text {
+                text,
+                position,
+                font_size,
+                alignment,
+                max_row_width,
+                clip_area,
+            } = command
+            {
+                self.draw_text(
+                    text,
+                    *position,
+                    *font_size,
+                    *alignment,
+                    *max_row_width,
+                    *clip_area,
+                );
             }
         }
+    }
 
-        let tex = renderer.get_texture(draw_call);
-        unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, tex);
-            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    /// Discards the text drawn since the last
+    /// [`TextRenderer::compose_draw_call`], without drawing it. See
+    /// [`Renderer::flush`].
+    pub fn flush(&mut self) {
+        self.cached_text.clear();
+    }
+
+    /// Makes the `draw_text` calls called before this function
+    /// render. Should be called every frame before rendering.
+    pub fn compose_draw_call(&mut self, renderer: &mut Renderer) {
+        if self.auto_dpi {
+            let dpi_factor = renderer.dpi_factor();
+            for text in &mut self.cached_text {
+                if text.dpi_factor != dpi_factor {
+                    let ratio = dpi_factor / text.dpi_factor;
+                    text.glyphs = text
+                        .glyphs
+                        .drain(..)
+                        .map(|glyph| rescale_glyph(glyph, ratio))
+                        .collect();
+                    text.dpi_factor = dpi_factor;
+                }
+            }
+            self.dpi_factor = dpi_factor;
         }
 
-        let upload_new_texture = |rect: Rect<u32>, data: &[u8]| unsafe {
-            gl::TexSubImage2D(
-                gl::TEXTURE_2D,
-                0,
-                rect.min.x as GLint,
-                rect.min.y as GLint,
-                rect.width() as GLint,
-                rect.height() as GLint,
-                gl::RED as GLuint,
-                gl::UNSIGNED_BYTE,
-                data.as_ptr() as *const _,
+        let sdf = self.rendering_mode == RenderingMode::Sdf;
+        let mut queue: Vec<PositionedGlyph<'static>> = self
+            .cached_text
+            .iter()
+            .flat_map(|text| text.glyphs.iter())
+            .map(|glyph| {
+                if sdf {
+                    sdf_glyph(&glyph.glyph)
+                } else {
+                    glyph.glyph.clone()
+                }
+            })
+            .collect();
+        let mut page_index = 0;
+        while !queue.is_empty() {
+            if page_index == self.pages.len() {
+                let first_cache = &self.pages[0].cache;
+                let (width, height) = first_cache.dimensions();
+                let position_tolerance = first_cache.position_tolerance();
+                let mode = self.rendering_mode;
+                let calls = self.text_draw_call_count;
+                match GlyphCachePage::create(
+                    width,
+                    height,
+                    position_tolerance,
+                    mode,
+                    calls,
+                    renderer,
+                ) {
+                    Ok(page) => self.pages.push(page),
+                    Err(err) => {
+                        log::error!("could not create a new glyph cache page: {}", err);
+                        break;
+                    }
+                }
+            }
+            let page = &mut self.pages[page_index];
+            let cached_count = cache_glyphs(
+                &mut page.cache,
+                &queue,
+                renderer.get_texture(&page.draw_call),
+                sdf,
+                self.raster_options.gamma,
             );
-        };
-        cache.cache_queued(upload_new_texture).ok();
+            if cached_count == 0 {
+                log::warn!(
+                    "a glyph is too large for the glyph cache (see TextRenderer::set_cache_size)"
+                );
+                queue.remove(0);
+            } else {
+                queue.drain(..cached_count);
+                page_index += 1;
+            }
+        }
 
         for text in &self.cached_text {
             let z = text.z;
+            let dpi_factor = text.dpi_factor;
+            let (rads, pivot_x, pivot_y) = text.rotation;
 
             let clip_coords;
             let clipped;
@@ -306,21 +1193,57 @@ impl TextRenderer {
                 clipped = false;
             }
             for glyph in &text.glyphs {
-                if let Ok(Some((uv_rect, screen_rect))) = cache.rect_for(0, &glyph.glyph) {
+                let sdf_glyph_;
+                let cache_glyph = if sdf {
+                    sdf_glyph_ = sdf_glyph(&glyph.glyph);
+                    &sdf_glyph_
+                } else {
+                    &glyph.glyph
+                };
+                let cached =
+                    self.pages
+                        .iter()
+                        .find_map(|page| match page.cache.rect_for(0, cache_glyph) {
+                            Ok(Some(rects)) => Some((rects, page.draw_call_for(text.draw_call))),
+                            _ => None,
+                        });
+                if let Some(((uv_rect, screen_rect), draw_call)) = cached {
+                    let (x0, y0, x1, y1) = (
+                        screen_rect.min.x as f32,
+                        screen_rect.min.y as f32,
+                        screen_rect.max.x as f32,
+                        screen_rect.max.y as f32,
+                    );
+                    let (x0, y0, x1, y1) = if sdf {
+                        // The SDF glyph is at the origin, at SDF_GLYPH_SIZE
+                        let ratio = glyph.glyph.scale().y / SDF_GLYPH_SIZE;
+                        let position = glyph.glyph.position();
+                        (
+                            position.x + x0 * ratio,
+                            position.y + y0 * ratio,
+                            position.x + x1 * ratio,
+                            position.y + y1 * ratio,
+                        )
+                    } else {
+                        (x0, y0, x1, y1)
+                    };
                     let coords = (
-                        screen_rect.min.x as f32 / dpi_factor,
-                        screen_rect.min.y as f32 / dpi_factor,
-                        screen_rect.max.x as f32 / dpi_factor,
-                        screen_rect.max.y as f32 / dpi_factor,
+                        x0 / dpi_factor,
+                        y0 / dpi_factor,
+                        x1 / dpi_factor,
+                        y1 / dpi_factor,
                     );
                     let texcoords = (uv_rect.min.x, uv_rect.min.y, uv_rect.max.x, uv_rect.max.y);
+                    // Every glyph pivots around the same point, so the
+                    // block rotates as a whole.
+                    let rotation = (rads, pivot_x - coords.0, pivot_y - coords.1);
                     if clipped {
                         renderer.draw_quad_clipped(
                             clip_coords,
                             coords,
                             texcoords,
                             (0.0, 0.0, 0.0, 1.0),
-                            (0.0, 0.0, 0.0),
+                            rotation,
                             z,
                             draw_call,
                         );
@@ -329,7 +1252,7 @@ impl TextRenderer {
                             coords,
                             texcoords,
                             (0.0, 0.0, 0.0, 1.0),
-                            (0.0, 0.0, 0.0),
+                            rotation,
                             z,
                             draw_call,
                         );
@@ -342,6 +1265,150 @@ impl TextRenderer {
     }
 }
 
+/// Returns the glyph, scaled to `SDF_GLYPH_SIZE` and positioned at
+/// the origin, so that one rasterization into a distance field can
+/// be used for every size and position.
+fn sdf_glyph(glyph: &PositionedGlyph<'static>) -> PositionedGlyph<'static> {
+    let scale = glyph.scale();
+    let scale = Scale {
+        x: scale.x / scale.y * SDF_GLYPH_SIZE,
+        y: SDF_GLYPH_SIZE,
+    };
+    glyph
+        .unpositioned()
+        .unscaled()
+        .clone()
+        .scaled(scale)
+        .positioned(point(0.0, 0.0))
+}
+
+/// Applies the gamma of [`RasterOptions::gamma`] to a coverage value
+/// between 0 and 1.
+pub(crate) fn apply_gamma(coverage: f32, gamma: f32) -> f32 {
+    if gamma == 1.0 || gamma <= 0.0 {
+        coverage
+    } else {
+        coverage.max(0.0).powf(1.0 / gamma)
+    }
+}
+
+/// Converts the coverage values of a rasterized glyph into a signed
+/// distance field: 0.5 (128) is the edge of the glyph, and the
+/// values go up inside the glyph and down outside of it, reaching 1.0
+/// and 0.0 at `SDF_SPREAD` pixels away from the edge. The area
+/// outside of the bitmap is considered to be outside the glyph.
+pub(crate) fn coverage_to_sdf(coverage: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let inside = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && coverage[y as usize * width + x as usize] >= 128
+    };
+    let spread = SDF_SPREAD as isize;
+    let mut sdf = Vec::with_capacity(width * height);
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let is_inside = inside(x, y);
+            let mut nearest = SDF_SPREAD + 0.5;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    if inside(x + dx, y + dy) != is_inside {
+                        nearest = nearest.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+            // The edge is halfway between the pixel and its nearest
+            // opposite neighbor
+            let distance = nearest - 0.5;
+            let signed_distance = if is_inside { distance } else { -distance };
+            let value = 0.5 + signed_distance / (2.0 * SDF_SPREAD);
+            sdf.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    sdf
+}
+
+/// Caches as many glyphs from the start of `glyphs` as fit in
+/// `cache` at once, uploading them into `texture`, converted into
+/// distance fields if `sdf` is true, or otherwise with `gamma`
+/// applied to the coverage. Returns the amount of glyphs cached,
+/// which is 0 if the first glyph doesn't fit even in an empty cache.
+fn cache_glyphs(
+    cache: &mut Cache<'static>,
+    glyphs: &[PositionedGlyph<'static>],
+    texture: GLuint,
+    sdf: bool,
+    gamma: f32,
+) -> usize {
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    }
+    let mut gamma_table = [0; 256];
+    for (i, value) in gamma_table.iter_mut().enumerate() {
+        *value = (apply_gamma(i as f32 / 255.0, gamma) * 255.0).round() as u8;
+    }
+    let upload_new_texture = |rect: Rect<u32>, data: &[u8]| unsafe {
+        let converted_data: Vec<u8>;
+        let data = if sdf {
+            converted_data = coverage_to_sdf(data, rect.width() as usize, rect.height() as usize);
+            &converted_data
+        } else if gamma != 1.0 {
+            converted_data = data.iter().map(|&c| gamma_table[c as usize]).collect();
+            &converted_data
+        } else {
+            data
+        };
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            rect.min.x as GLint,
+            rect.min.y as GLint,
+            rect.width() as GLint,
+            rect.height() as GLint,
+            gl::RED as GLuint,
+            gl::UNSIGNED_BYTE,
+            data.as_ptr() as *const _,
+        );
+    };
+
+    // Halve the amount of glyphs until they fit, as the cache can
+    // only guarantee that the glyphs of the latest queue are cached
+    let mut count = glyphs.len();
+    while count > 0 {
+        for glyph in &glyphs[..count] {
+            cache.queue_glyph(0, glyph.clone());
+        }
+        if cache.cache_queued(upload_new_texture).is_ok() {
+            break;
+        }
+        cache.clear_queue();
+        count /= 2;
+    }
+    count
+}
+
+impl Renderer {
+    /// Draws text baked with [`TextRenderer::bake_text`], with its
+    /// top-left corner at `(x, y)`, in the given color.
+    pub fn draw_baked_text(
+        &mut self,
+        baked: &BakedText,
+        (x, y, z): (f32, f32, f32),
+        color: (f32, f32, f32, f32),
+    ) {
+        self.draw_quad(
+            (x, y, x + baked.width, y + baked.height),
+            (0.0, 0.0, 1.0, 1.0),
+            color,
+            (0.0, 0.0, 0.0),
+            z,
+            &baked.draw_call,
+        );
+    }
+}
+
 /// Will only return `None` when `index >= glyphs.len()`.
 fn measure_text_at_index(
     glyphs: &[SizedGlyph],
@@ -356,15 +1423,15 @@ fn measure_text_at_index(
     let glyph = &glyphs[index].glyph;
     let position = glyph.position();
     if let Some(rect) = glyph.pixel_bounding_box() {
-        return Some((
+        Some((
             rect.min.x as f32 / dpi,
             rect.min.y as f32 / dpi,
             rect.max.x as f32 / dpi,
             rect.max.y as f32 / dpi,
-        ));
+        ))
     } else {
         let (x, y) = (position.x / dpi, position.y / dpi);
-        return Some((x, y, x + width / dpi, y + 1.0));
+        Some((x, y, x + width / dpi, y + 1.0))
     }
 }
 
@@ -386,26 +1453,223 @@ fn measure_text(glyphs: &[SizedGlyph], dpi: f32) -> Option<(f32, f32)> {
         }
     }
 
-    if let Some(rect) = result {
-        Some((rect.2 - rect.0, rect.3 - rect.1))
-    } else {
-        None
+    result.map(|rect| (rect.2 - rect.0, rect.3 - rect.1))
+}
+
+fn offset_row(row: Row, x: f32, dpi: f32) -> Row {
+    Row {
+        glyphs: row
+            .glyphs
+            .into_iter()
+            .map(|glyph| offset_glyph(glyph, x, 0.0, dpi))
+            .collect(),
+        start_x: row.start_x + x * dpi,
+        first_index: row.first_index,
     }
 }
 
-fn offset_glyphs(glyphs: Vec<SizedGlyph>, x: f32, y: f32, dpi: f32) -> Vec<SizedGlyph> {
-    glyphs
-        .into_iter()
-        .map(|glyph| offset_glyph(glyph, x, y, dpi))
-        .collect()
+fn rescale_glyph(glyph: SizedGlyph, ratio: f32) -> SizedGlyph {
+    let SizedGlyph {
+        glyph,
+        width,
+        index,
+    } = glyph;
+    let position = glyph.position();
+    let glyph = glyph.into_unpositioned();
+    let scale = glyph.scale();
+    let glyph = glyph
+        .into_unscaled()
+        .scaled(Scale {
+            x: scale.x * ratio,
+            y: scale.y * ratio,
+        })
+        .positioned(point(position.x * ratio, position.y * ratio));
+    SizedGlyph {
+        glyph,
+        width: width * ratio,
+        index,
+    }
 }
 
 fn offset_glyph(glyph: SizedGlyph, x: f32, y: f32, dpi: f32) -> SizedGlyph {
-    let width = glyph.width;
-    let glyph = glyph.glyph;
+    let SizedGlyph {
+        glyph,
+        width,
+        index,
+    } = glyph;
     let position = glyph.position() + vector(x, y) * dpi;
     SizedGlyph {
         width,
+        index,
         glyph: glyph.into_unpositioned().positioned(position),
     }
 }
+
+/// An editing operation on a [`TextInput`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEdit {
+    /// Removes the character before the caret.
+    Backspace,
+    /// Removes the character after the caret.
+    Delete,
+    /// Moves the caret one character to the left.
+    Left,
+    /// Moves the caret one character to the right.
+    Right,
+    /// Moves the caret to the start of the text.
+    Home,
+    /// Moves the caret to the end of the text.
+    End,
+}
+
+/// The editable text and caret of a text field, updated from the
+/// window's input with [`TextInput::update`].
+///
+/// The text is kept NFC normalized, and the caret is a `char` index
+/// into it, like the indices of [`TextRenderer::caret_position`] and
+/// [`TextRenderer::index_at_point`] (which count `char`s after NFC
+/// normalization), so drawing the caret and placing it with the mouse
+/// work as is.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    caret: usize,
+}
+
+impl TextInput {
+    /// Creates an empty text input.
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    /// Returns the text being edited.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, and moves the caret to its end. The text
+    /// is NFC normalized.
+    pub fn set_text<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into().nfc().collect();
+        self.caret = self.text.chars().count();
+    }
+
+    /// Returns the caret's position, in `char`s from the start of
+    /// the text.
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Moves the caret before the `char` at `caret`, or to the end of
+    /// the text if `caret` is past it.
+    pub fn set_caret(&mut self, caret: usize) {
+        self.caret = caret.min(self.text.chars().count());
+    }
+
+    /// Inserts the character at the caret, and moves the caret past
+    /// it. Control characters (like the backspace and return
+    /// characters some platforms report as typed) are ignored.
+    ///
+    /// The text is normalized again after the insertion, so eg. a
+    /// combining accent typed after a letter is composed with it,
+    /// and the caret stays after the composed character.
+    pub fn insert(&mut self, c: char) {
+        if !c.is_control() {
+            let (before, after) = self.text.split_at(self.byte_index(self.caret));
+            let mut before = before.to_owned();
+            before.push(c);
+            self.caret = before.nfc().count();
+            self.text = before.chars().chain(after.chars()).nfc().collect();
+        }
+    }
+
+    /// Applies the editing operation on the text.
+    pub fn edit(&mut self, edit: TextEdit) {
+        let length = self.text.chars().count();
+        match edit {
+            TextEdit::Backspace if self.caret > 0 => {
+                self.caret -= 1;
+                let index = self.byte_index(self.caret);
+                self.text.remove(index);
+            }
+            TextEdit::Delete if self.caret < length => {
+                let index = self.byte_index(self.caret);
+                self.text.remove(index);
+            }
+            TextEdit::Left => self.caret = self.caret.saturating_sub(1),
+            TextEdit::Right => self.caret = (self.caret + 1).min(length),
+            TextEdit::Home => self.caret = 0,
+            TextEdit::End => self.caret = length,
+            TextEdit::Backspace | TextEdit::Delete => {}
+        }
+    }
+
+    /// Applies this frame's input from the window's
+    /// [`Window::events`], in the order it happened: inserts the
+    /// typed characters, and applies the editing operations of the
+    /// pressed and repeated backspace, delete, arrow, home and end
+    /// keys. Returns whether the text changed.
+    ///
+    /// Only call this for the focused text field, as all of the
+    /// frame's typing goes to it.
+    pub fn update(&mut self, window: &Window) -> bool {
+        self.apply_events(window.events())
+    }
+
+    /// Applies the typing and editing events in order, see
+    /// [`TextInput::update`].
+    pub(crate) fn apply_events(&mut self, events: &[Event]) -> bool {
+        let previous_text = self.text.clone();
+        for event in events {
+            match *event {
+                Event::CharTyped(c) => self.insert(c),
+                Event::KeyPressed(key) | Event::KeyRepeated(key) => {
+                    if let Some(edit) = text_edit_for_key(key) {
+                        self.edit(edit);
+                    }
+                }
+                _ => {}
+            }
+        }
+        previous_text != self.text
+    }
+
+    /// Returns the byte index of the `char` index in the text.
+    fn byte_index(&self, index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+}
+
+#[cfg(feature = "glutin")]
+fn text_edit_for_key(key: Key) -> Option<TextEdit> {
+    match key {
+        Key::Back => Some(TextEdit::Backspace),
+        Key::Delete => Some(TextEdit::Delete),
+        Key::Left => Some(TextEdit::Left),
+        Key::Right => Some(TextEdit::Right),
+        Key::Home => Some(TextEdit::Home),
+        Key::End => Some(TextEdit::End),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "glfw")]
+fn text_edit_for_key(key: Key) -> Option<TextEdit> {
+    match key {
+        Key::Backspace => Some(TextEdit::Backspace),
+        Key::Delete => Some(TextEdit::Delete),
+        Key::Left => Some(TextEdit::Left),
+        Key::Right => Some(TextEdit::Right),
+        Key::Home => Some(TextEdit::Home),
+        Key::End => Some(TextEdit::End),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(feature = "glutin", feature = "glfw")))]
+fn text_edit_for_key(_key: Key) -> Option<TextEdit> {
+    None
+}
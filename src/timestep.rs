@@ -0,0 +1,81 @@
+//! A fixed timestep accumulator, for running simulations at a
+//! constant rate regardless of the frame rate.
+
+use std::time::Duration;
+
+/// Splits the variable time between frames into fixed-length steps.
+///
+/// Every frame, pass the time since the last frame to
+/// [`Timestep::advance`], run the simulation as many times as it
+/// returns, and then render with the state interpolated by
+/// [`Timestep::alpha`] between the last two simulated states.
+///
+/// # Example
+/// ```
+/// use fae::Timestep;
+/// use std::time::Duration;
+///
+/// let mut timestep = Timestep::new(Duration::from_millis(10));
+/// // A 25ms frame runs two steps, leaving half a step for the next frame
+/// assert_eq!(timestep.advance(Duration::from_millis(25)), 2);
+/// assert_eq!(timestep.alpha(), 0.5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Timestep {
+    step: Duration,
+    accumulator: Duration,
+    max_steps: u32,
+}
+
+impl Timestep {
+    /// Creates a new timestep helper which runs steps of length
+    /// `step`.
+    pub fn new(step: Duration) -> Timestep {
+        Timestep {
+            step: step.max(Duration::from_nanos(1)),
+            accumulator: Duration::from_secs(0),
+            max_steps: 8,
+        }
+    }
+
+    /// Returns the length of a single step.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Sets the most steps a single [`Timestep::advance`] can
+    /// return. If the simulation falls further behind than this (eg.
+    /// after the application was paused, or if steps take longer to
+    /// simulate than they last), the extra time is dropped instead of
+    /// trying to catch up. 8 by default.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Adds the time since the last frame to the accumulator, and
+    /// returns how many fixed steps should be simulated this frame.
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // Fell too far behind, drop the rest of the time
+                self.accumulator = Duration::from_secs(0);
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Returns how far the accumulated time is into the next step,
+    /// from 0.0 to 1.0. Use this to interpolate between the previous
+    /// and the current simulation state when rendering.
+    pub fn alpha(&self) -> f32 {
+        let nanos = |duration: Duration| {
+            duration.as_secs() as f64 * 1e9 + f64::from(duration.subsec_nanos())
+        };
+        (nanos(self.accumulator) / nanos(self.step)) as f32
+    }
+}
@@ -0,0 +1,42 @@
+use crate::window::{Key, Mouse};
+use std::path::PathBuf;
+
+/// Describes something that happened to the window. See
+/// [`Window::poll_events`](struct.Window.html#method.poll_events).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A key was pressed down. Not repeated while the key is held.
+    KeyPressed(Key),
+    /// A held key was repeated. See
+    /// [`Window::set_key_repeat`](struct.Window.html#method.set_key_repeat).
+    KeyRepeated(Key),
+    /// A key was released.
+    KeyReleased(Key),
+    /// A character was typed.
+    CharTyped(char),
+    /// The mouse moved to the position, in logical pixels.
+    /// Arrangement: (x, y)
+    MouseMoved(f32, f32),
+    /// A mouse button was pressed (`pressed` is true) or released.
+    MouseButton {
+        /// The mouse button.
+        button: Mouse,
+        /// Whether the button was pressed or released.
+        pressed: bool,
+    },
+    /// A mouse button was double clicked. Sent after the
+    /// `MouseButton` event of the second press.
+    MouseDoubleClick(Mouse),
+    /// The mouse wheel was scrolled, in pixels. See
+    /// `Window::mouse_scroll` for how the amount is calculated.
+    /// Arrangement: (x, y)
+    Scroll(f32, f32),
+    /// The window was resized to the size, in logical
+    /// pixels. Arrangement: (width, height)
+    Resized(f32, f32),
+    /// A file was dropped on the window.
+    FileDropped(PathBuf),
+    /// The user requested the window to be closed. Whether the
+    /// window actually closes depends on `WindowSettings::auto_close`.
+    CloseRequested,
+}
@@ -0,0 +1,69 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Caps the frame rate of a window's event loop, by waiting in
+/// `refresh` until the next frame is due.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(any(feature = "glutin", feature = "glfw")), allow(dead_code))]
+pub(crate) struct FrameLimiter {
+    /// The minimum time between frames, or `None` if unlimited.
+    interval: Option<Duration>,
+    previous_frame: Option<Instant>,
+}
+
+#[cfg_attr(not(any(feature = "glutin", feature = "glfw")), allow(dead_code))]
+impl FrameLimiter {
+    pub(crate) fn new() -> FrameLimiter {
+        FrameLimiter {
+            interval: None,
+            previous_frame: None,
+        }
+    }
+
+    /// Sets the maximum frames per second. `None`, or a rate that
+    /// isn't above 0, removes the limit.
+    pub(crate) fn set_frame_rate(&mut self, frame_rate: Option<f32>) {
+        self.interval = frame_rate
+            .filter(|&fps| fps > 0.0)
+            .map(|fps| Duration::from_secs_f32(1.0 / fps));
+    }
+
+    /// Starts a new frame at `time`, and returns when the frame
+    /// should actually start, if that's later than `time`. Frames
+    /// that start late don't make the following ones start early, so
+    /// that the rate stays capped after a slow frame.
+    pub(crate) fn next_frame(&mut self, time: Instant) -> Option<Instant> {
+        let interval = self.interval?;
+        let deadline = self.previous_frame.map(|previous| previous + interval);
+        match deadline {
+            Some(deadline) if deadline > time => {
+                self.previous_frame = Some(deadline);
+                Some(deadline)
+            }
+            _ => {
+                self.previous_frame = Some(time);
+                None
+            }
+        }
+    }
+
+    /// Waits until the next frame is due. Sleeps for most of the
+    /// wait, and yields for the last couple of milliseconds, as
+    /// sleeps tend to overshoot.
+    pub(crate) fn wait(&mut self) {
+        if let Some(deadline) = self.next_frame(Instant::now()) {
+            loop {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                let remaining = deadline - now;
+                if remaining > Duration::from_millis(2) {
+                    thread::sleep(remaining - Duration::from_millis(2));
+                } else {
+                    thread::yield_now();
+                }
+            }
+        }
+    }
+}
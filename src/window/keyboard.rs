@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+/// Generates key repeats for held keys, for backends which don't
+/// report them.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "glutin"), allow(dead_code))]
+pub(crate) struct KeyRepeatTimer<K> {
+    /// How long a key needs to be held before it starts repeating.
+    pub(crate) delay: Duration,
+    /// The time between repeats, after the initial delay.
+    pub(crate) interval: Duration,
+    next_repeats: Vec<(K, Instant)>,
+}
+
+#[cfg_attr(not(feature = "glutin"), allow(dead_code))]
+impl<K: PartialEq + Copy> KeyRepeatTimer<K> {
+    pub(crate) fn new() -> KeyRepeatTimer<K> {
+        KeyRepeatTimer {
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(33),
+            next_repeats: Vec::new(),
+        }
+    }
+
+    /// Starts the repeat timer of the key.
+    pub(crate) fn press(&mut self, key: K, time: Instant) {
+        self.release(key);
+        self.next_repeats.push((key, time + self.delay));
+    }
+
+    /// Stops the key from repeating.
+    pub(crate) fn release(&mut self, key: K) {
+        self.next_repeats.retain(|(repeating, _)| *repeating != key);
+    }
+
+    /// Pushes the keys which should repeat by `time` into `repeats`,
+    /// once for each repeat.
+    pub(crate) fn update(&mut self, time: Instant, repeats: &mut Vec<K>) {
+        let interval = self.interval.max(Duration::from_millis(1));
+        for (key, next_repeat) in &mut self.next_repeats {
+            while *next_repeat <= time {
+                repeats.push(*key);
+                *next_repeat += interval;
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
 //! Quick window creation utilities.
 
+mod event;
+mod frame_limit;
+mod keyboard;
 mod mouse;
 mod util;
 #[cfg(not(any(feature = "glfw", feature = "glutin")))]
@@ -9,6 +12,14 @@ mod window_glfw;
 #[cfg(feature = "glutin")]
 mod window_glutin;
 
+pub use event::*;
+#[cfg_attr(
+    not(any(test, feature = "glutin", feature = "glfw")),
+    allow(unused_imports)
+)]
+pub(crate) use frame_limit::FrameLimiter;
+#[cfg_attr(not(any(test, feature = "glutin")), allow(unused_imports))]
+pub(crate) use keyboard::KeyRepeatTimer;
 pub use mouse::*;
 pub use util::*;
 #[cfg(not(any(feature = "glfw", feature = "glutin")))]
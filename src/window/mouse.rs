@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 /// Describes a mouse button.
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum Mouse {
@@ -7,7 +9,94 @@ pub enum Mouse {
     Right,
     /// The middle mouse button (scroll).
     Middle,
-    /// Other mouse buttons, identified by a relatively arbitrary
-    /// number.
+    /// The "back" side button, usually the fourth button of the
+    /// mouse.
+    Back,
+    /// The "forward" side button, usually the fifth button of the
+    /// mouse.
+    Forward,
+    /// Other mouse buttons, identified by the raw button number of
+    /// the backend (and the platform, with glutin), so the numbers
+    /// aren't portable.
     Other(u8),
 }
+
+/// The amount of one scroll event, in the units the backend reported
+/// it in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(not(any(feature = "glfw", feature = "glutin")), allow(dead_code))]
+pub(crate) enum ScrollDelta {
+    /// Notches of a scroll wheel, from mouse wheels and tilt wheels.
+    /// Arrangement: (x, y)
+    Notches(f32, f32),
+    /// Pixels, from trackpads and other precise scrolling devices.
+    /// Arrangement: (x, y)
+    // glfw only reports scrolling in (possibly fractional) notches
+    #[cfg_attr(not(feature = "glutin"), allow(dead_code))]
+    Pixels(f32, f32),
+}
+
+#[cfg_attr(not(any(feature = "glfw", feature = "glutin")), allow(dead_code))]
+impl ScrollDelta {
+    /// Adds the amount to a frame's scroll amounts in `pixels` and
+    /// in `notches`, converting between the two with
+    /// `scroll_length`, and returns the amount in pixels. Both axes
+    /// are converted the same way.
+    pub(crate) fn accumulate(
+        self,
+        pixels: &mut (f32, f32),
+        notches: &mut (f32, f32),
+        scroll_length: f32,
+    ) -> (f32, f32) {
+        let (delta_pixels, delta_notches) = match self {
+            ScrollDelta::Notches(x, y) => ((x * scroll_length, y * scroll_length), (x, y)),
+            ScrollDelta::Pixels(x, y) => ((x, y), (x / scroll_length, y / scroll_length)),
+        };
+        pixels.0 += delta_pixels.0;
+        pixels.1 += delta_pixels.1;
+        notches.0 += delta_notches.0;
+        notches.1 += delta_notches.1;
+        delta_pixels
+    }
+}
+
+/// Detects double clicks from a stream of mouse button presses.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(any(feature = "glfw", feature = "glutin")), allow(dead_code))]
+pub(crate) struct DoubleClickTracker {
+    /// The longest time between two presses that are still
+    /// considered a double click.
+    pub(crate) interval: Duration,
+    /// The furthest distance (in logical pixels) between two
+    /// presses that are still considered a double click.
+    pub(crate) distance: f32,
+    last_press: Option<(Mouse, Instant, (f32, f32))>,
+}
+
+#[cfg_attr(not(any(feature = "glfw", feature = "glutin")), allow(dead_code))]
+impl DoubleClickTracker {
+    pub(crate) fn new() -> DoubleClickTracker {
+        DoubleClickTracker {
+            interval: Duration::from_millis(500),
+            distance: 4.0,
+            last_press: None,
+        }
+    }
+
+    /// Registers a press, and returns whether it completed a double
+    /// click. The press that completes a double click can't start a
+    /// new one, so triple clicks only count as one double click.
+    pub(crate) fn press(&mut self, button: Mouse, position: (f32, f32), time: Instant) -> bool {
+        if let Some((last_button, last_time, last_position)) = self.last_press.take() {
+            let (dx, dy) = (position.0 - last_position.0, position.1 - last_position.1);
+            if last_button == button
+                && time.duration_since(last_time) <= self.interval
+                && (dx * dx + dy * dy).sqrt() <= self.distance
+            {
+                return true;
+            }
+        }
+        self.last_press = Some((button, time, position));
+        false
+    }
+}
@@ -1,5 +1,6 @@
 use std::default::Default;
 use std::env;
+use std::os::raw::{c_ulong, c_void};
 
 #[allow(dead_code)]
 pub(crate) fn get_env_dpi() -> f32 {
@@ -24,6 +25,111 @@ pub(crate) fn get_env_dpi() -> f32 {
     1.0
 }
 
+/// The platform-specific handles of a window, for interoperating
+/// with libraries which need to access the native window (eg. to
+/// create their own surfaces). See `Window::raw_window_handle`. With
+/// the `raw-window-handle` feature, the window also implements the
+/// `raw-window-handle` crate's traits, using these handles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawWindowHandle {
+    /// An X11 window.
+    Xlib {
+        /// The X11 window id.
+        window: c_ulong,
+        /// The X11 `Display` pointer.
+        display: *mut c_void,
+    },
+    /// A Wayland surface.
+    Wayland {
+        /// The `wl_surface` pointer.
+        surface: *mut c_void,
+        /// The `wl_display` pointer.
+        display: *mut c_void,
+    },
+    /// A Win32 window.
+    Win32 {
+        /// The `HWND` of the window.
+        hwnd: *mut c_void,
+    },
+    /// A macOS window.
+    AppKit {
+        /// The `NSWindow` pointer.
+        ns_window: *mut c_void,
+        /// The `NSView` pointer, or null if the backend doesn't
+        /// expose it.
+        ns_view: *mut c_void,
+    },
+    /// The window backend doesn't expose native handles on this
+    /// platform, or there is no window.
+    Unavailable,
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl RawWindowHandle {
+    /// Converts the handles into the `raw-window-handle` crate's
+    /// window and display handles. Panics if the handles are
+    /// `Unavailable`, as the crate has no way to represent that.
+    #[allow(dead_code)]
+    pub(crate) fn into_raw_handles(
+        self,
+    ) -> (
+        raw_window_handle::RawWindowHandle,
+        raw_window_handle::RawDisplayHandle,
+    ) {
+        use raw_window_handle::{
+            AppKitDisplayHandle, AppKitWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+            Win32WindowHandle, WindowsDisplayHandle, XlibDisplayHandle, XlibWindowHandle,
+        };
+        match self {
+            RawWindowHandle::Xlib { window, display } => {
+                let mut window_handle = XlibWindowHandle::empty();
+                window_handle.window = window;
+                let mut display_handle = XlibDisplayHandle::empty();
+                display_handle.display = display;
+                (window_handle.into(), display_handle.into())
+            }
+            RawWindowHandle::Wayland { surface, display } => {
+                let mut window_handle = WaylandWindowHandle::empty();
+                window_handle.surface = surface;
+                let mut display_handle = WaylandDisplayHandle::empty();
+                display_handle.display = display;
+                (window_handle.into(), display_handle.into())
+            }
+            RawWindowHandle::Win32 { hwnd } => {
+                let mut window_handle = Win32WindowHandle::empty();
+                window_handle.hwnd = hwnd;
+                (window_handle.into(), WindowsDisplayHandle::empty().into())
+            }
+            RawWindowHandle::AppKit { ns_window, ns_view } => {
+                let mut window_handle = AppKitWindowHandle::empty();
+                window_handle.ns_window = ns_window;
+                window_handle.ns_view = ns_view;
+                (window_handle.into(), AppKitDisplayHandle::empty().into())
+            }
+            RawWindowHandle::Unavailable => {
+                panic!("the window backend doesn't expose native handles on this platform")
+            }
+        }
+    }
+}
+
+/// How the buffer swaps of a window are synchronized to the
+/// display's refresh rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VsyncMode {
+    /// Buffers are swapped as soon as possible, which can cause
+    /// tearing. Only recommended for benchmarks.
+    Off,
+    /// Buffer swaps wait for the next vertical blank.
+    On,
+    /// Like `On`, except that a swap which misses the vertical blank
+    /// happens right away (and tears) instead of waiting for the next
+    /// one, avoiding a stutter. Requires the `EXT_swap_control_tear`
+    /// extension, and falls back to `On` if it's not available or the
+    /// window backend can't use it (like glutin).
+    Adaptive,
+}
+
 /// Defines a window.
 pub struct WindowSettings {
     /// Title of the window. Default value: Name of the executable file
@@ -42,8 +148,69 @@ pub struct WindowSettings {
     /// applications that are supposed to be opened for very short
     /// amounts of time.
     pub is_dialog: bool,
-    /// This should always be true for everything except benchmarks.
-    pub vsync: bool,
+    /// Whether the window has the title bar and borders provided by
+    /// the platform. Undecorated windows are useful for splash
+    /// screens and custom window chrome, see `Window::set_position`.
+    /// Default value: `true`
+    pub decorated: bool,
+    /// Whether the window stays on top of other windows, eg. for
+    /// floating tool palettes. Default value: `false`
+    pub always_on_top: bool,
+    /// Whether the window's framebuffer is composited over the
+    /// desktop using its alpha channel. Use a transparent clear color
+    /// (see
+    /// [`Renderer::set_clear_color`](../renderer/struct.Renderer.html#method.set_clear_color))
+    /// to see through the window. Not supported by the glfw backend.
+    /// Default value: `false`
+    pub transparent: bool,
+    /// Whether the window is centered on the primary monitor when
+    /// it's created, instead of letting the platform decide where it
+    /// goes. See also `Window::center`. Default value: `false`
+    pub center: bool,
+    /// The vsync mode of the window. See
+    /// [`Window::vsync_mode`](struct.Window.html#method.vsync_mode)
+    /// for the mode that actually ended up being used. Default value:
+    /// `VsyncMode::On`
+    pub vsync: VsyncMode,
+    /// The exact OpenGL version to create the context with, for
+    /// testing against specific drivers. Arrangement: (major, minor,
+    /// is OpenGL ES). Default value: `None`
+    ///
+    /// By default, fae tries OpenGL 3.3, OpenGL ES 3.0, OpenGL 2.1
+    /// and OpenGL ES 2.0, in that order, and only the 2.x versions if
+    /// `force_legacy` is set. If a
+    /// version is requested, only that version is tried, and window
+    /// creation fails if it isn't available. Versions below 3.0 use
+    /// the legacy rendering path.
+    pub requested_gl_version: Option<(u8, u8, bool)>,
+    /// Whether to only try the OpenGL 2.1 and OpenGL ES 2.0 contexts,
+    /// which use the legacy rendering path. Default value: `false`
+    ///
+    /// Useful for making sure the application works in both modes.
+    /// Setting the `FAE_OPENGL_LEGACY` environment variable forces
+    /// the legacy path as well, regardless of this setting, for
+    /// debugging without recompiling.
+    pub force_legacy: bool,
+    /// Whether the window closes as soon as the user tries to close
+    /// it, eg. with the window's close button. Default value: `true`
+    ///
+    /// If disabled, the close attempt is only reported (see
+    /// `Window::close_requested`), and the window stays open until
+    /// the application calls `Window::request_close`, so it can ask
+    /// the user to save their work first, for example.
+    pub auto_close: bool,
+    /// The amount of bits per pixel in the depth buffer requested for
+    /// the context. Default value: `24`
+    ///
+    /// If a context with the requested depth and stencil bits can't
+    /// be created, the window is created with whatever buffers the
+    /// platform provides instead, and a warning is logged.
+    pub depth_bits: u8,
+    /// The amount of bits per pixel in the stencil buffer requested
+    /// for the context. Stencil masks (see
+    /// [`Renderer::begin_mask`](../renderer/struct.Renderer.html#method.begin_mask))
+    /// need at least 1. Default value: `8`
+    pub stencil_bits: u8,
 }
 
 impl Default for WindowSettings {
@@ -57,7 +224,16 @@ impl Default for WindowSettings {
             width: 640.0,
             height: 480.0,
             is_dialog: false,
-            vsync: true,
+            decorated: true,
+            always_on_top: false,
+            transparent: false,
+            center: false,
+            vsync: VsyncMode::On,
+            requested_gl_version: None,
+            force_legacy: false,
+            auto_close: true,
+            depth_bits: 24,
+            stencil_bits: 8,
         }
     }
 }
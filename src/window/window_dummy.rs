@@ -37,13 +37,18 @@
 //! - `GDK_SCALE`
 //! - `ELM_SCALE`
 
+use crate::error::FaeError;
+use crate::image::Image;
 use crate::renderer::Renderer;
-use crate::window::Mouse;
-use std::error::Error;
+use crate::window::{DoubleClickTracker, Event, Mouse, RawWindowHandle, VsyncMode};
 use std::path::PathBuf;
+use std::time::Duration;
 
 pub use crate::window::WindowSettings;
 
+/// The keycode type used by the current window backend.
+pub type Key = u32;
+
 /// Wrapper for a Glutin/Glfw window.
 pub struct Window {
     /// The width of the window.
@@ -54,26 +59,66 @@ pub struct Window {
     pub dpi_factor: f32,
     /// The opengl legacy status for Renderer.
     pub opengl21: bool,
+    vsync_mode: VsyncMode,
 
     /// The keys which are currently held down. Different type for
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
-    pub held_keys: Vec<u32>,
+    pub held_keys: Vec<Key>,
     /// The keys which were pressed this frame. Different type for
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
-    pub pressed_keys: Vec<u32>,
+    pub pressed_keys: Vec<Key>,
+    /// The held keys which were repeated this frame, possibly
+    /// several times each. Newly pressed keys are only in
+    /// `pressed_keys`, not here.
+    pub repeated_keys: Vec<Key>,
     /// The keys which were released this frame. Different type for
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
-    pub released_keys: Vec<u32>,
+    pub released_keys: Vec<Key>,
     /// The characters typed this frame, in chronological order.
     pub typed_chars: Vec<char>,
+    /// The text currently being composed with an input method
+    /// (preedit text), if any. IME composition is not reported by
+    /// the current window backends (glutin only sends the final
+    /// characters, with `typed_chars`, and glfw has no IME support),
+    /// so this is always `None` for now.
+    pub composition: Option<String>,
+
+    /// Whether the window gained or lost focus during this frame.
+    /// See [`Window::is_focused`].
+    pub focus_changed: bool,
+    /// Whether the window was minimized or restored during this
+    /// frame. See [`Window::is_minimized`].
+    pub minimize_changed: bool,
+    /// Whether the window's DPI factor changed during this frame, eg.
+    /// because the window was moved to a monitor with a different
+    /// scale.
+    pub dpi_changed: bool,
+    /// Whether the user tried to close the window during this frame,
+    /// eg. with the window's close button. If
+    /// `WindowSettings::auto_close` is disabled, the window stays
+    /// open until [`Window::request_close`] is called. See
+    /// [`Event::CloseRequested`].
+    pub close_requested: bool,
+    focused: bool,
+    minimized: bool,
 
     /// Whether the mouse is inside the window.
     pub mouse_inside: bool,
     /// The mouse position inside the window. Arrangement: (x, y)
     pub mouse_coords: (f32, f32),
+    /// The relative mouse motion during this frame. Arrangement: (x,
+    /// y)
+    ///
+    /// With the glutin backend, this is accumulated from the raw
+    /// device motion events, so it isn't affected by the cursor
+    /// hitting the edges of the screen, but the units depend on the
+    /// device (usually they're roughly pixels, without mouse
+    /// acceleration). With the glfw backend, this is the difference
+    /// between cursor positions, in logical pixels.
+    pub mouse_delta: (f32, f32),
     /// The mouse scroll amount during this frame, in pixels. If the
     /// user supports pixel-perfect scrolling, this will be equal to
     /// those pixel-perfect deltas. Otherwise, the polled scrolling
@@ -81,7 +126,23 @@ pub struct Window {
     /// the default settings, this will usually result in bursts of
     /// (0, -36) and (0, 36) during normal scrolling. Arrangement: (x,
     /// y)
+    ///
+    /// The x axis is horizontal scrolling, from trackpads and tilt
+    /// wheels. Trackpads usually scroll in pixels already, so check
+    /// `mouse_scroll_precise` before applying your own scaling to
+    /// either axis.
     pub mouse_scroll: (f32, f32),
+    /// The mouse scroll amount during this frame, in notches of the
+    /// scroll wheel, without `mouse_scroll_length` applied. Useful
+    /// for things like stepping through menu items. Pixel-perfect
+    /// scrolling is converted to notches by dividing with
+    /// `mouse_scroll_length`. Arrangement: (x, y)
+    pub mouse_scroll_notches: (f32, f32),
+    /// Whether the scrolling during this frame was reported in
+    /// pixels, eg. by a trackpad, instead of notches of a scroll
+    /// wheel. Pixel-perfect scrolling is passed through to
+    /// `mouse_scroll` as-is, without `mouse_scroll_length`.
+    pub mouse_scroll_precise: bool,
     /// How many pixels one "notch" on the mouse scroll wheel should
     /// scroll. (36 by default)
     pub mouse_scroll_length: f32,
@@ -91,12 +152,17 @@ pub struct Window {
     pub mouse_pressed: Vec<Mouse>,
     /// The mouse buttons which were released this frame.
     pub mouse_released: Vec<Mouse>,
+    /// The mouse buttons which were double clicked this frame. See
+    /// [`Window::set_double_click_time`].
+    pub mouse_double_clicked: Vec<Mouse>,
+    double_click: DoubleClickTracker,
 
     /// A list of files dropped on the window during this frame.
     pub dropped_files: Vec<PathBuf>,
     /// A list of files being currently hovered on the window. Does
     /// not work if using the GLFW backend.
     pub hovered_files: Vec<PathBuf>,
+    queued_events: Vec<Event>,
 }
 
 impl Window {
@@ -105,8 +171,10 @@ impl Window {
     /// Can result in an error if window creation fails or OpenGL
     /// context creation fails.
     #[allow(unused_variables)]
-    pub fn create(settings: &WindowSettings) -> Result<Window, Box<Error>> {
-        Err(Box::new(WindowCreationError))
+    pub fn create(settings: &WindowSettings) -> Result<Window, FaeError> {
+        Err(FaeError::Unsupported(
+            "cannot create window without glutin or glfw".to_owned(),
+        ))
     }
 
     /// Sets the cursor graphic to the provided one. NOTE: This
@@ -117,35 +185,203 @@ impl Window {
     #[allow(unused_variables)]
     pub fn set_cursor(&mut self, cursor: u32) {}
 
+    /// Sets the cursor graphic to a custom image, with its hotspot
+    /// (the point which clicks, in pixels from the top-left of the
+    /// image) at `hotspot`. Only supported by the glfw backend.
+    #[allow(unused_variables)]
+    pub fn set_cursor_image(&mut self, image: &Image, hotspot: (u32, u32)) -> Result<(), FaeError> {
+        Err(FaeError::Unsupported(
+            "cannot set the cursor without glutin or glfw".to_owned(),
+        ))
+    }
+
+    /// Returns the native handles of the window. There's no window
+    /// without glutin or glfw, so this is always
+    /// `RawWindowHandle::Unavailable`.
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Unavailable
+    }
+
     /// Updates the window (swaps the front and back buffers). The
-    /// renderer handle is used for a CPU/GPU synchronization call, so
-    /// while it is optional, it's definitely recommended. If vsync is
-    /// enabled, this function will hang until the next frame.
+    /// renderer handle is used for a CPU/GPU synchronization call
+    /// (see [`Renderer::synchronize`]), so while it is optional,
+    /// it's definitely recommended. If vsync is enabled, this
+    /// function will hang until the next frame.
     #[allow(unused_variables)]
     pub fn swap_buffers(&mut self, renderer: Option<&Renderer>) {}
 
-    /// Polls for new events. Returns whether the user has requested
-    /// the window to be closed.
-    pub fn refresh(&mut self) -> bool {
-        false
+    /// Sets the vsync mode of the window.
+    pub fn set_vsync_mode(&mut self, mode: VsyncMode) -> Result<(), FaeError> {
+        self.vsync_mode = mode;
+        Ok(())
+    }
+
+    /// Returns the vsync mode that is actually in use, which can
+    /// differ from the requested one, see [`VsyncMode::Adaptive`].
+    pub fn vsync_mode(&self) -> VsyncMode {
+        self.vsync_mode
     }
-}
 
-#[derive(Debug, Clone)]
-struct WindowCreationError;
+    /// Caps the frame rate to `frame_rate` frames per second, by
+    /// waiting in [`Window::refresh`] until the next frame is due.
+    /// `None` removes the limit, which is the default.
+    ///
+    /// Useful when vsync is off (see [`Window::set_vsync_mode`]), to
+    /// avoid spinning the CPU and GPU at thousands of frames per
+    /// second, or for lowering the power usage of mostly idle
+    /// applications. Not supported by this
+    /// backend.
+    #[allow(unused_variables)]
+    pub fn set_frame_rate_limit(&mut self, frame_rate: Option<f32>) {}
 
-impl std::fmt::Display for WindowCreationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "cannot create window without glutin or glfw")
+    /// Sets how long a key needs to be held before it starts
+    /// repeating, and the time between repeats after that. Only
+    /// affects the glutin backend, other backends use the repeat
+    /// settings of the platform.
+    #[allow(unused_variables)]
+    pub fn set_key_repeat(&mut self, delay: Duration, interval: Duration) {}
+
+    /// Returns whether the window has the keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Returns whether the window is minimized (iconified).
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Returns whether `key` was pressed down during this frame. See
+    /// `pressed_keys`.
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    /// Returns whether `key` is currently held down. See
+    /// `held_keys`.
+    pub fn key_held(&self, key: Key) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    /// Returns whether `key` was released during this frame. See
+    /// `released_keys`.
+    pub fn key_released(&self, key: Key) -> bool {
+        self.released_keys.contains(&key)
     }
-}
 
-impl Error for WindowCreationError {
-    fn description(&self) -> &str {
-        "cannot create window without glutin or glfw"
+    /// Returns whether `button` was pressed down during this
+    /// frame. See the `mouse_pressed` field.
+    pub fn mouse_pressed(&self, button: Mouse) -> bool {
+        self.mouse_pressed.contains(&button)
     }
 
-    fn cause(&self) -> Option<&Error> {
-        None
+    /// Returns whether `button` is currently held down. See the
+    /// `mouse_held` field.
+    pub fn mouse_held(&self, button: Mouse) -> bool {
+        self.mouse_held.contains(&button)
+    }
+
+    /// Returns whether `button` was released during this frame. See
+    /// the `mouse_released` field.
+    pub fn mouse_released(&self, button: Mouse) -> bool {
+        self.mouse_released.contains(&button)
+    }
+
+    /// Sets the longest time between two presses of a mouse button
+    /// which still counts as a double click. 500 milliseconds by
+    /// default.
+    pub fn set_double_click_time(&mut self, interval: Duration) {
+        self.double_click.interval = interval;
+    }
+
+    /// Sets the furthest distance (in logical pixels) the mouse can
+    /// move between two presses which still count as a double
+    /// click. 4 pixels by default.
+    pub fn set_double_click_distance(&mut self, distance: f32) {
+        self.double_click.distance = distance;
+    }
+
+    /// Toggles whether the input method editor (IME) of the
+    /// platform can be used for typing into the window. Not supported by
+    /// this backend.
+    #[allow(unused_variables)]
+    pub fn set_ime_allowed(&mut self, allowed: bool) {}
+
+    /// Moves the IME candidate window near the given position (in
+    /// logical pixels), eg. the caret of the focused text field. Not
+    /// supported by this backend.
+    #[allow(unused_variables)]
+    pub fn set_ime_position(&mut self, x: f32, y: f32) {}
+
+    /// Moves the window so that its top-left corner is at `position`
+    /// (in logical pixels, from the top-left corner of the desktop).
+    /// Along with the mouse events, this can be used to implement
+    /// title bar dragging for undecorated windows (see
+    /// `WindowSettings::decorated`). Not
+    /// supported by this backend.
+    #[allow(unused_variables)]
+    pub fn set_position(&mut self, position: (f32, f32)) {}
+
+    /// Moves the window to the center of the primary monitor. See
+    /// also `WindowSettings::center`. Not
+    /// supported by this backend.
+    pub fn center(&mut self) {}
+
+    /// Returns the position of the window's top-left corner, in
+    /// logical pixels from the top-left corner of the desktop. See
+    /// [`Window::set_position`]. Not supported by
+    /// this backend.
+    pub fn position(&self) -> (f32, f32) {
+        (0.0, 0.0)
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, and removes
+    /// them from the window. Events that aren't polled are discarded
+    /// in the next refresh. The `pressed_keys`, `mouse_coords`, etc.
+    /// fields are updated from the same events, so they can be used
+    /// alongside this.
+    pub fn poll_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.queued_events.drain(..)
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, without
+    /// removing them like [`Window::poll_events`] does.
+    pub fn events(&self) -> &[Event] {
+        &self.queued_events
+    }
+
+    /// Requests the window to be closed, eg. from a "Quit" menu
+    /// item: the next [`Window::refresh`] returns `false`, like when
+    /// the user closes the window.
+    pub fn request_close(&mut self) {}
+
+    /// Cancels a close request, either from [`Window::request_close`]
+    /// or the user closing the window, so that the next
+    /// [`Window::refresh`] returns `true` again. Useful for asking
+    /// the user to save their work before quitting, for example.
+    pub fn cancel_close(&mut self) {}
+
+    /// Renders the queued draws of the renderer to the window, and
+    /// then swaps the buffers and synchronizes, like
+    /// [`Window::swap_buffers`]. The renderer's DPI factor is also
+    /// updated to the window's, see [`Renderer::set_dpi_factor`].
+    ///
+    /// This is equivalent to calling `renderer.set_dpi_factor`,
+    /// `renderer.render` with the window's size, and
+    /// `window.swap_buffers` in order, which can still be done
+    /// manually if more control is needed.
+    pub fn present(&mut self, renderer: &mut Renderer) {
+        renderer.set_dpi_factor(self.dpi_factor);
+        renderer.render(self.width, self.height);
+        self.swap_buffers(Some(renderer));
+    }
+
+    /// Polls for new events. Returns `false` when the window should
+    /// be closed, see [`Window::request_close`] and
+    /// `WindowSettings::auto_close`.
+    pub fn refresh(&mut self) -> bool {
+        false
     }
 }
@@ -37,18 +37,26 @@
 //! - `GDK_SCALE`
 //! - `ELM_SCALE`
 
+use crate::error::FaeError;
 use crate::gl;
+use crate::image::Image;
 use crate::renderer::Renderer;
-use crate::window::{get_env_dpi, Mouse};
+use crate::window::{
+    get_env_dpi, DoubleClickTracker, Event, FrameLimiter, Mouse, RawWindowHandle, ScrollDelta,
+    VsyncMode,
+};
 use glfw::*;
 use std::env;
-use std::error::Error;
 use std::path::PathBuf;
 use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
 pub use crate::window::WindowSettings;
 pub use glfw;
 
+/// The keycode type used by the current window backend.
+pub type Key = glfw::Key;
+
 const HIDPI_AUTO: bool = cfg!(any(target_os = "windows", target_os = "macos"));
 
 /// Wrapper for a Glutin/Glfw window.
@@ -64,6 +72,7 @@ pub struct Window {
     events: Receiver<(f64, WindowEvent)>,
     fb_width: f32,
     fb_height: f32,
+    vsync_mode: VsyncMode,
     /// The opengl legacy status for Renderer.
     pub opengl21: bool,
     /// The keys which are currently held down. Different type for
@@ -74,17 +83,64 @@ pub struct Window {
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
     pub pressed_keys: Vec<Key>,
+    /// The held keys which were repeated this frame, possibly
+    /// several times each. Newly pressed keys are only in
+    /// `pressed_keys`, not here.
+    pub repeated_keys: Vec<Key>,
     /// The keys which were released this frame. Different type for
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
     pub released_keys: Vec<Key>,
     /// The characters typed this frame, in chronological order.
     pub typed_chars: Vec<char>,
+    /// The text currently being composed with an input method
+    /// (preedit text), if any. IME composition is not reported by
+    /// the current window backends (glutin only sends the final
+    /// characters, with `typed_chars`, and glfw has no IME support),
+    /// so this is always `None` for now.
+    pub composition: Option<String>,
+
+    /// Whether the window gained or lost focus during this frame.
+    /// See [`Window::is_focused`].
+    pub focus_changed: bool,
+    /// Whether the window was minimized or restored during this
+    /// frame. See [`Window::is_minimized`].
+    pub minimize_changed: bool,
+    /// Whether the window's DPI factor changed during this frame, eg.
+    /// because the window was moved to a monitor with a different
+    /// scale. The viewport and the logical size are updated
+    /// automatically, and text rasterized by fae follows the
+    /// renderer's DPI factor (see [`Window::present`]), but
+    /// the application may want to re-rasterize its own caches.
+    ///
+    /// The DPI factor is derived from the framebuffer and window
+    /// sizes, so this is only ever set on platforms where GLFW scales
+    /// windows itself (Windows and macOS).
+    pub dpi_changed: bool,
+    /// Whether the user tried to close the window during this frame,
+    /// eg. with the window's close button. If
+    /// `WindowSettings::auto_close` is disabled, the window stays
+    /// open until [`Window::request_close`] is called. See
+    /// [`Event::CloseRequested`].
+    pub close_requested: bool,
+    focused: bool,
+    minimized: bool,
+    auto_close: bool,
 
     /// Whether the mouse is inside the window.
     pub mouse_inside: bool,
     /// The mouse position inside the window. Arrangement: (x, y)
     pub mouse_coords: (f32, f32),
+    /// The relative mouse motion during this frame. Arrangement: (x,
+    /// y)
+    ///
+    /// With the glutin backend, this is accumulated from the raw
+    /// device motion events, so it isn't affected by the cursor
+    /// hitting the edges of the screen, but the units depend on the
+    /// device (usually they're roughly pixels, without mouse
+    /// acceleration). With the glfw backend, this is the difference
+    /// between cursor positions, in logical pixels.
+    pub mouse_delta: (f32, f32),
     /// The mouse scroll amount during this frame, in pixels. If the
     /// user supports pixel-perfect scrolling, this will be equal to
     /// those pixel-perfect deltas. Otherwise, the polled scrolling
@@ -92,7 +148,23 @@ pub struct Window {
     /// the default settings, this will usually result in bursts of
     /// (0, -36) and (0, 36) during normal scrolling. Arrangement: (x,
     /// y)
+    ///
+    /// The x axis is horizontal scrolling, from trackpads and tilt
+    /// wheels. Trackpads usually scroll in pixels already, so check
+    /// `mouse_scroll_precise` before applying your own scaling to
+    /// either axis.
     pub mouse_scroll: (f32, f32),
+    /// The mouse scroll amount during this frame, in notches of the
+    /// scroll wheel, without `mouse_scroll_length` applied. Useful
+    /// for things like stepping through menu items. Pixel-perfect
+    /// scrolling is converted to notches by dividing with
+    /// `mouse_scroll_length`. Arrangement: (x, y)
+    pub mouse_scroll_notches: (f32, f32),
+    /// Whether the scrolling during this frame was reported in
+    /// pixels, eg. by a trackpad, instead of notches of a scroll
+    /// wheel. GLFW always reports scrolling in notches, so this is
+    /// always false with this backend.
+    pub mouse_scroll_precise: bool,
     /// How many pixels one "notch" on the mouse scroll wheel should
     /// scroll. (36 by default)
     pub mouse_scroll_length: f32,
@@ -102,12 +174,22 @@ pub struct Window {
     pub mouse_pressed: Vec<Mouse>,
     /// The mouse buttons which were released this frame.
     pub mouse_released: Vec<Mouse>,
+    /// The mouse buttons which were double clicked this frame. See
+    /// [`Window::set_double_click_time`].
+    pub mouse_double_clicked: Vec<Mouse>,
+    double_click: DoubleClickTracker,
+    frame_limiter: FrameLimiter,
 
     /// A list of files dropped on the window during this frame.
     pub dropped_files: Vec<PathBuf>,
     /// A list of files being currently hovered on the window. Does
     /// not work if using the GLFW backend.
     pub hovered_files: Vec<PathBuf>,
+    /// The glfw events of this frame which fae doesn't handle
+    /// itself. Only events which are polled end up here, see
+    /// [`Window::set_all_polling`].
+    pub other_events: Vec<WindowEvent>,
+    queued_events: Vec<Event>,
 }
 
 impl Window {
@@ -115,81 +197,88 @@ impl Window {
     ///
     /// Can result in an error if window creation fails or OpenGL
     /// context creation fails.
-    pub fn create(settings: &WindowSettings) -> Result<Window, Box<Error>> {
-        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS)?;
-
-        let mut width = settings.width;
-        let mut height = settings.height;
-        let dpi_factor;
-        if !HIDPI_AUTO {
-            dpi_factor = get_env_dpi();
-            width /= dpi_factor;
-            height /= dpi_factor;
-        } else {
-            dpi_factor = 1.0;
-        }
-
-        let opengl21 = false;
-        let (mut glfw_window, events) = {
+    pub fn create(settings: &WindowSettings) -> Result<Window, FaeError> {
+        let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS)
+            .map_err(|err| FaeError::WindowCreation(err.to_string()))?;
+
+        // On platforms where GLFW handles HiDPI automatically, window
+        // sizes are in logical pixels, otherwise they're in physical
+        // pixels, and the scale comes from the environment.
+        let width = settings.width;
+        let height = settings.height;
+        let dpi_factor = if HIDPI_AUTO { 1.0 } else { get_env_dpi() };
+
+        let ((mut glfw_window, events), opengl21) = {
             // Forward compatibility flag needed for mac:
             // https://www.khronos.org/opengl/wiki/OpenGL_Context#Forward_compatibility
             if cfg!(target_os = "macos") {
                 glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
             }
             glfw.window_hint(WindowHint::SRgbCapable(true));
+            glfw.window_hint(WindowHint::Decorated(settings.decorated));
+            glfw.window_hint(WindowHint::Floating(settings.always_on_top));
+            if settings.transparent {
+                log::warn!("Transparent windows are not supported by the glfw backend");
+            }
+            glfw.window_hint(WindowHint::DepthBits(Some(u32::from(settings.depth_bits))));
+            glfw.window_hint(WindowHint::StencilBits(Some(u32::from(
+                settings.stencil_bits,
+            ))));
 
-            let width = width as u32;
-            let height = height as u32;
+            let width = (width * dpi_factor) as u32;
+            let height = (height * dpi_factor) as u32;
             let title = &settings.title;
             let window_mode = glfw::WindowMode::Windowed;
-
-            if env::var_os("FAE_OPENGL_LEGACY").is_some() {
-                if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGl));
-                    glfw.window_hint(WindowHint::ContextVersion(2, 1));
-                    glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
-                } else if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGlEs));
-                    glfw.window_hint(WindowHint::ContextVersion(2, 0));
+            let legacy = settings.force_legacy || env::var_os("FAE_OPENGL_LEGACY").is_some();
+
+            // Returns the window and whether its context is OpenGL (ES)
+            // 2.x, from the first context version that could be created
+            let create_window = |glfw: &mut glfw::Glfw| {
+                let mut create_context = |api, major, minor| {
+                    glfw.window_hint(WindowHint::ClientApi(api));
+                    glfw.window_hint(WindowHint::ContextVersion(major, minor));
                     glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
+                        .map(|window| (window, major < 3))
+                };
+                if let Some((major, minor, es)) = settings.requested_gl_version {
+                    let api = if es {
+                        ClientApiHint::OpenGlEs
+                    } else {
+                        ClientApiHint::OpenGl
+                    };
+                    create_context(api, u32::from(major), u32::from(minor))
+                } else if legacy {
+                    create_context(ClientApiHint::OpenGl, 2, 1)
+                        .or_else(|| create_context(ClientApiHint::OpenGlEs, 2, 0))
                 } else {
-                    return Err(Box::new(WindowCreationError));
+                    create_context(ClientApiHint::OpenGl, 3, 3)
+                        .or_else(|| create_context(ClientApiHint::OpenGlEs, 3, 0))
+                        .or_else(|| create_context(ClientApiHint::OpenGl, 2, 1))
+                        .or_else(|| create_context(ClientApiHint::OpenGlEs, 2, 0))
                 }
+            };
+
+            if let Some(result) = create_window(&mut glfw) {
+                result
+            } else if let Some(result) = {
+                log::warn!(
+                    "Could not create a window with {} depth bits and {} stencil bits, retrying without depth and stencil requirements",
+                    settings.depth_bits,
+                    settings.stencil_bits,
+                );
+                glfw.window_hint(WindowHint::DepthBits(None));
+                glfw.window_hint(WindowHint::StencilBits(None));
+                create_window(&mut glfw)
+            } {
+                result
             } else {
-                if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGl));
-                    glfw.window_hint(WindowHint::ContextVersion(3, 3));
-                    glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
-                } else if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGlEs));
-                    glfw.window_hint(WindowHint::ContextVersion(3, 0));
-                    glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
-                } else if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGl));
-                    glfw.window_hint(WindowHint::ContextVersion(2, 1));
-                    glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
-                } else if let Some(result) = {
-                    glfw.window_hint(WindowHint::ClientApi(ClientApiHint::OpenGlEs));
-                    glfw.window_hint(WindowHint::ContextVersion(2, 0));
-                    glfw.create_window(width, height, title, window_mode)
-                } {
-                    result
-                } else {
-                    return Err(Box::new(WindowCreationError));
-                }
+                return Err(window_creation_error());
             }
         };
 
+        if settings.center {
+            center_window(&mut glfw, &mut glfw_window);
+        }
         glfw_window.make_current();
         gl::load_with(|symbol| glfw_window.get_proc_address(symbol) as *const _);
         /* use std::ffi::CStr;
@@ -202,46 +291,69 @@ impl Window {
             println!("OpenGL version: {}", opengl_version_string);
         }*/
 
-        if settings.vsync {
-            if glfw.extension_supported("WGL_EXT_swap_control_tear")
-                || glfw.extension_supported("GLX_EXT_swap_control_tear")
-            {
-                glfw.set_swap_interval(glfw::SwapInterval::Adaptive);
-            } else {
-                glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-            }
+        let vsync_mode = set_swap_interval(&mut glfw, settings.vsync);
+
+        set_polling(&mut glfw_window, false);
+
+        // Match the viewport to the framebuffer from the start,
+        // instead of waiting for the first resize event
+        let (fb_width, fb_height) = glfw_window.get_framebuffer_size();
+        let (fb_width, fb_height) = (fb_width as f32, fb_height as f32);
+        let dpi_factor = if HIDPI_AUTO {
+            (fb_width / width).max(fb_height / height)
         } else {
-            glfw.set_swap_interval(glfw::SwapInterval::None);
+            dpi_factor
+        };
+        unsafe {
+            gl::Viewport(0, 0, fb_width as i32, fb_height as i32);
         }
 
-        glfw_window.set_all_polling(true);
-
+        let focused = glfw_window.is_focused();
         Ok(Window {
-            width: width,
-            height: height,
+            width,
+            height,
             dpi_factor,
             glfw,
             glfw_window,
             events,
-            fb_width: width,
-            fb_height: height,
+            fb_width,
+            fb_height,
+            vsync_mode,
             opengl21,
 
             held_keys: Vec::new(),
             pressed_keys: Vec::new(),
+            repeated_keys: Vec::new(),
             released_keys: Vec::new(),
             typed_chars: Vec::new(),
+            composition: None,
+
+            focus_changed: false,
+            minimize_changed: false,
+            dpi_changed: false,
+            close_requested: false,
+            focused,
+            minimized: false,
+            auto_close: settings.auto_close,
 
             mouse_inside: false,
             mouse_coords: (0.0, 0.0),
+            mouse_delta: (0.0, 0.0),
             mouse_scroll: (0.0, 0.0),
+            mouse_scroll_notches: (0.0, 0.0),
+            mouse_scroll_precise: false,
             mouse_scroll_length: 36.0,
             mouse_held: Vec::new(),
             mouse_pressed: Vec::new(),
             mouse_released: Vec::new(),
+            mouse_double_clicked: Vec::new(),
+            double_click: DoubleClickTracker::new(),
+            frame_limiter: FrameLimiter::new(),
 
             dropped_files: Vec::new(),
             hovered_files: Vec::new(),
+            other_events: Vec::new(),
+            queued_events: Vec::new(),
         })
     }
 
@@ -252,10 +364,46 @@ impl Window {
         self.glfw_window.set_cursor(Some(Cursor::standard(cursor)));
     }
 
+    /// Returns the native handles of the window, for interoperating
+    /// with libraries which need access to the native window. On
+    /// Linux, this is always an X11 window, and on macOS the
+    /// `ns_view` is null, as glfw doesn't expose them. With the
+    /// `raw-window-handle` feature, the window also implements
+    /// `HasRawWindowHandle` and `HasRawDisplayHandle`.
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        raw_window_handle(&self.glfw, &self.glfw_window)
+    }
+
+    /// Sets the cursor graphic to a custom image, with its hotspot
+    /// (the point which clicks, in pixels from the top-left of the
+    /// image) at `hotspot`. The image must be RGBA. The cursor is
+    /// displayed at the image's size, in physical pixels.
+    pub fn set_cursor_image(&mut self, image: &Image, hotspot: (u32, u32)) -> Result<(), FaeError> {
+        if image.format != gl::RGBA {
+            return Err(FaeError::Unsupported(
+                "cursor images must be in the RGBA format".to_owned(),
+            ));
+        }
+        let pixels = image
+            .pixels
+            .chunks(4)
+            .map(|rgba| u32::from_ne_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+            .collect();
+        let image = PixelImage {
+            width: image.width as u32,
+            height: image.height as u32,
+            pixels,
+        };
+        let cursor = Cursor::create_from_pixels(image, hotspot.0, hotspot.1);
+        self.glfw_window.set_cursor(Some(cursor));
+        Ok(())
+    }
+
     /// Updates the window (swaps the front and back buffers). The
-    /// renderer handle is used for a CPU/GPU synchronization call, so
-    /// while it is optional, it's definitely recommended. If vsync is
-    /// enabled, this function will hang until the next frame.
+    /// renderer handle is used for a CPU/GPU synchronization call
+    /// (see [`Renderer::synchronize`]), so while it is optional,
+    /// it's definitely recommended. If vsync is enabled, this
+    /// function will hang until the next frame.
     pub fn swap_buffers(&mut self, renderer: Option<&Renderer>) {
         self.glfw_window.swap_buffers();
         if let Some(renderer) = renderer {
@@ -263,16 +411,217 @@ impl Window {
         }
     }
 
-    /// Polls for new events. Returns whether the user has requested
-    /// the window to be closed.
+    /// Sets the vsync mode of the window. If `VsyncMode::Adaptive` is
+    /// requested, but the swap control tear extension isn't
+    /// available, `VsyncMode::On` is used instead.
+    pub fn set_vsync_mode(&mut self, mode: VsyncMode) -> Result<(), FaeError> {
+        self.vsync_mode = set_swap_interval(&mut self.glfw, mode);
+        Ok(())
+    }
+
+    /// Returns the vsync mode that is actually in use, which can
+    /// differ from the requested one, see [`VsyncMode::Adaptive`].
+    pub fn vsync_mode(&self) -> VsyncMode {
+        self.vsync_mode
+    }
+
+    /// Caps the frame rate to `frame_rate` frames per second, by
+    /// waiting in [`Window::refresh`] until the next frame is due.
+    /// `None` removes the limit, which is the default.
+    ///
+    /// Useful when vsync is off (see [`Window::set_vsync_mode`]), to
+    /// avoid spinning the CPU and GPU at thousands of frames per
+    /// second, or for lowering the power usage of mostly idle
+    /// applications.
+    pub fn set_frame_rate_limit(&mut self, frame_rate: Option<f32>) {
+        self.frame_limiter.set_frame_rate(frame_rate);
+    }
+
+    /// Sets how long a key needs to be held before it starts
+    /// repeating, and the time between repeats after that. Only
+    /// affects the glutin backend, other backends use the repeat
+    /// settings of the platform.
+    #[allow(unused_variables)]
+    pub fn set_key_repeat(&mut self, delay: Duration, interval: Duration) {}
+
+    /// Toggles polling of all glfw events, including the ones fae
+    /// doesn't handle, like window focus and iconification. The
+    /// unhandled events are collected into `other_events` every
+    /// refresh. Disabled by default, as it adds some overhead for
+    /// events that usually aren't needed.
+    pub fn set_all_polling(&mut self, all: bool) {
+        set_polling(&mut self.glfw_window, all);
+    }
+
+    /// Returns whether the window has the keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Returns whether the window is minimized (iconified).
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Returns whether `key` was pressed down during this frame. See
+    /// `pressed_keys`.
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    /// Returns whether `key` is currently held down. See
+    /// `held_keys`.
+    pub fn key_held(&self, key: Key) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    /// Returns whether `key` was released during this frame. See
+    /// `released_keys`.
+    pub fn key_released(&self, key: Key) -> bool {
+        self.released_keys.contains(&key)
+    }
+
+    /// Returns whether `button` was pressed down during this
+    /// frame. See the `mouse_pressed` field.
+    pub fn mouse_pressed(&self, button: Mouse) -> bool {
+        self.mouse_pressed.contains(&button)
+    }
+
+    /// Returns whether `button` is currently held down. See the
+    /// `mouse_held` field.
+    pub fn mouse_held(&self, button: Mouse) -> bool {
+        self.mouse_held.contains(&button)
+    }
+
+    /// Returns whether `button` was released during this frame. See
+    /// the `mouse_released` field.
+    pub fn mouse_released(&self, button: Mouse) -> bool {
+        self.mouse_released.contains(&button)
+    }
+
+    /// Sets the longest time between two presses of a mouse button
+    /// which still counts as a double click. 500 milliseconds by
+    /// default.
+    pub fn set_double_click_time(&mut self, interval: Duration) {
+        self.double_click.interval = interval;
+    }
+
+    /// Sets the furthest distance (in logical pixels) the mouse can
+    /// move between two presses which still count as a double
+    /// click. 4 pixels by default.
+    pub fn set_double_click_distance(&mut self, distance: f32) {
+        self.double_click.distance = distance;
+    }
+
+    /// Toggles whether the input method editor (IME) of the
+    /// platform can be used for typing into the window. GLFW has no
+    /// IME support, so this does nothing with the glfw backend.
+    #[allow(unused_variables)]
+    pub fn set_ime_allowed(&mut self, allowed: bool) {}
+
+    /// Moves the IME candidate window near the given position (in
+    /// logical pixels), eg. the caret of the focused text field. GLFW
+    /// has no IME support, so this does nothing with the glfw
+    /// backend.
+    #[allow(unused_variables)]
+    pub fn set_ime_position(&mut self, x: f32, y: f32) {}
+
+    /// Moves the window so that its top-left corner is at `position`
+    /// (in logical pixels, from the top-left corner of the desktop).
+    /// Along with the mouse events, this can be used to implement
+    /// title bar dragging for undecorated windows (see
+    /// `WindowSettings::decorated`).
+    pub fn set_position(&mut self, (x, y): (f32, f32)) {
+        let scale = if HIDPI_AUTO { 1.0 } else { self.dpi_factor };
+        self.glfw_window
+            .set_pos((x * scale) as i32, (y * scale) as i32);
+    }
+
+    /// Moves the window to the center of the primary monitor. See
+    /// also `WindowSettings::center`.
+    pub fn center(&mut self) {
+        center_window(&mut self.glfw, &mut self.glfw_window);
+    }
+
+    /// Returns the position of the window's top-left corner, in
+    /// logical pixels from the top-left corner of the desktop. See
+    /// [`Window::set_position`].
+    pub fn position(&self) -> (f32, f32) {
+        let scale = if HIDPI_AUTO { 1.0 } else { self.dpi_factor };
+        let (x, y) = self.glfw_window.get_pos();
+        (x as f32 / scale, y as f32 / scale)
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, and removes
+    /// them from the window. Events that aren't polled are discarded
+    /// in the next refresh. The `pressed_keys`, `mouse_coords`, etc.
+    /// fields are updated from the same events, so they can be used
+    /// alongside this.
+    pub fn poll_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.queued_events.drain(..)
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, without
+    /// removing them like [`Window::poll_events`] does.
+    pub fn events(&self) -> &[Event] {
+        &self.queued_events
+    }
+
+    /// Requests the window to be closed, eg. from a "Quit" menu
+    /// item: the next [`Window::refresh`] returns `false`, like when
+    /// the user closes the window.
+    pub fn request_close(&mut self) {
+        self.glfw_window.set_should_close(true);
+    }
+
+    /// Cancels a close request, either from [`Window::request_close`]
+    /// or the user closing the window, so that the next
+    /// [`Window::refresh`] returns `true` again. Useful for asking
+    /// the user to save their work before quitting, for example.
+    pub fn cancel_close(&mut self) {
+        self.glfw_window.set_should_close(false);
+    }
+
+    /// Renders the queued draws of the renderer to the window, and
+    /// then swaps the buffers and synchronizes, like
+    /// [`Window::swap_buffers`]. The renderer's DPI factor is also
+    /// updated to the window's, see [`Renderer::set_dpi_factor`].
+    ///
+    /// This is equivalent to calling `renderer.set_dpi_factor`,
+    /// `renderer.render` with the window's size, and
+    /// `window.swap_buffers` in order, which can still be done
+    /// manually if more control is needed.
+    pub fn present(&mut self, renderer: &mut Renderer) {
+        renderer.set_dpi_factor(self.dpi_factor);
+        renderer.render(self.width, self.height);
+        self.swap_buffers(Some(renderer));
+    }
+
+    /// Polls for new events. Returns `false` when the window should
+    /// be closed, see [`Window::request_close`] and
+    /// `WindowSettings::auto_close`.
     pub fn refresh(&mut self) -> bool {
+        self.frame_limiter.wait();
         let mut resize = false;
 
+        self.queued_events.clear();
         self.pressed_keys.clear();
+        self.repeated_keys.clear();
         self.released_keys.clear();
+        self.mouse_pressed.clear();
+        self.mouse_released.clear();
+        self.mouse_double_clicked.clear();
         self.typed_chars.clear();
         self.mouse_scroll = (0.0, 0.0);
+        self.mouse_scroll_notches = (0.0, 0.0);
+        self.mouse_delta = (0.0, 0.0);
         self.dropped_files.clear();
+        self.other_events.clear();
+        self.close_requested = false;
+        let (was_focused, was_minimized) = (self.focused, self.minimized);
+        let previous_dpi_factor = self.dpi_factor;
 
         self.glfw.poll_events();
         for (_, event) in glfw::flush_messages(&self.events) {
@@ -280,6 +629,11 @@ impl Window {
                 WindowEvent::Key(key, _, Action::Press, _) => {
                     self.pressed_keys.push(key);
                     self.held_keys.push(key);
+                    self.queued_events.push(Event::KeyPressed(key));
+                }
+                WindowEvent::Key(key, _, Action::Repeat, _) => {
+                    self.repeated_keys.push(key);
+                    self.queued_events.push(Event::KeyRepeated(key));
                 }
                 WindowEvent::Key(key, _, Action::Release, _) => {
                     self.released_keys.push(key);
@@ -289,17 +643,21 @@ impl Window {
                             break;
                         }
                     }
+                    self.queued_events.push(Event::KeyReleased(key));
                 }
 
-                WindowEvent::Char(c) => self.typed_chars.push(c),
+                WindowEvent::Char(c) => {
+                    self.typed_chars.push(c);
+                    self.queued_events.push(Event::CharTyped(c));
+                }
 
                 WindowEvent::MouseButton(button, action, _) => {
                     let button = match button {
                         MouseButton::Button1 => Mouse::Left,
                         MouseButton::Button2 => Mouse::Right,
                         MouseButton::Button3 => Mouse::Middle,
-                        MouseButton::Button4 => Mouse::Other(4),
-                        MouseButton::Button5 => Mouse::Other(5),
+                        MouseButton::Button4 => Mouse::Back,
+                        MouseButton::Button5 => Mouse::Forward,
                         MouseButton::Button6 => Mouse::Other(6),
                         MouseButton::Button7 => Mouse::Other(7),
                         MouseButton::Button8 => Mouse::Other(8),
@@ -321,21 +679,55 @@ impl Window {
                         }
                         _ => {}
                     }
+                    if action != Action::Repeat {
+                        let pressed = action == Action::Press;
+                        self.queued_events
+                            .push(Event::MouseButton { button, pressed });
+                    }
+                    if action == Action::Press
+                        && self
+                            .double_click
+                            .press(button, self.mouse_coords, Instant::now())
+                    {
+                        self.mouse_double_clicked.push(button);
+                        self.queued_events.push(Event::MouseDoubleClick(button));
+                    }
                 }
 
                 WindowEvent::CursorPos(x, y) => {
+                    let (old_x, old_y) = self.mouse_coords;
                     self.mouse_coords = (x as f32 / self.dpi_factor, y as f32 / self.dpi_factor);
+                    let (x, y) = self.mouse_coords;
+                    self.mouse_delta.0 += x - old_x;
+                    self.mouse_delta.1 += y - old_y;
+                    self.queued_events.push(Event::MouseMoved(x, y));
                 }
                 WindowEvent::CursorEnter(entered) => self.mouse_inside = entered,
+                WindowEvent::Focus(focused) => self.focused = focused,
+                WindowEvent::Iconify(minimized) => self.minimized = minimized,
 
                 WindowEvent::Scroll(x, y) => {
-                    self.mouse_scroll = (
-                        self.mouse_scroll_length * x as f32,
-                        self.mouse_scroll_length * y as f32,
-                    )
+                    let (x, y) = ScrollDelta::Notches(x as f32, y as f32).accumulate(
+                        &mut self.mouse_scroll,
+                        &mut self.mouse_scroll_notches,
+                        self.mouse_scroll_length,
+                    );
+                    self.queued_events.push(Event::Scroll(x, y));
                 }
 
-                WindowEvent::FileDrop(paths) => self.dropped_files = paths,
+                WindowEvent::FileDrop(paths) => {
+                    for path in &paths {
+                        self.queued_events.push(Event::FileDropped(path.clone()));
+                    }
+                    self.dropped_files = paths;
+                }
+                WindowEvent::Close => {
+                    if !self.auto_close {
+                        self.glfw_window.set_should_close(false);
+                    }
+                    self.close_requested = true;
+                    self.queued_events.push(Event::CloseRequested);
+                }
 
                 WindowEvent::Size(width, height) => {
                     if HIDPI_AUTO {
@@ -345,6 +737,8 @@ impl Window {
                         self.width = width as f32 / self.dpi_factor;
                         self.height = height as f32 / self.dpi_factor;
                     }
+                    self.queued_events
+                        .push(Event::Resized(self.width, self.height));
                     resize = true;
                 }
                 WindowEvent::FramebufferSize(width, height) => {
@@ -353,10 +747,13 @@ impl Window {
                     resize = true;
                 }
 
-                _ => {}
+                event => self.other_events.push(event),
             }
         }
 
+        self.focus_changed = self.focused != was_focused;
+        self.minimize_changed = self.minimized != was_minimized;
+
         if resize {
             unsafe {
                 gl::Viewport(0, 0, self.fb_width as i32, self.fb_height as i32);
@@ -369,26 +766,118 @@ impl Window {
                 self.dpi_factor = dpi_factor_horizontal.max(dpi_factor_vertical);
             }
         }
+        self.dpi_changed = self.dpi_factor != previous_dpi_factor;
 
         !self.glfw_window.should_close()
     }
 }
 
-#[derive(Debug, Clone)]
-struct WindowCreationError;
+// Safety: the handles are the ones of the window, which stays open
+// (and the handles valid) until it's dropped.
+#[cfg(feature = "raw-window-handle")]
+unsafe impl ::raw_window_handle::HasRawWindowHandle for Window {
+    /// Panics if the handles are
+    /// [`RawWindowHandle::Unavailable`].
+    fn raw_window_handle(&self) -> ::raw_window_handle::RawWindowHandle {
+        Window::raw_window_handle(self).into_raw_handles().0
+    }
+}
 
-impl std::fmt::Display for WindowCreationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "could not create a glfw window")
+#[cfg(feature = "raw-window-handle")]
+unsafe impl ::raw_window_handle::HasRawDisplayHandle for Window {
+    /// Panics if the handles are
+    /// [`RawWindowHandle::Unavailable`].
+    fn raw_display_handle(&self) -> ::raw_window_handle::RawDisplayHandle {
+        Window::raw_window_handle(self).into_raw_handles().1
     }
 }
 
-impl Error for WindowCreationError {
-    fn description(&self) -> &str {
-        "could not create a glfw window"
+/// Enables polling for the events handled in [`Window::refresh`], or
+/// all of them if `all` is true.
+fn set_polling(window: &mut glfw::Window, all: bool) {
+    window.set_all_polling(all);
+    if !all {
+        window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_scroll_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_close_polling(true);
+        window.set_size_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
     }
+}
 
-    fn cause(&self) -> Option<&Error> {
-        None
+/// Moves the window to the center of the primary monitor.
+fn center_window(glfw: &mut Glfw, window: &mut glfw::Window) {
+    let monitor_area = glfw.with_primary_monitor(|_, monitor| {
+        let monitor = monitor?;
+        let (x, y) = monitor.get_pos();
+        let mode = monitor.get_video_mode()?;
+        Some((x, y, mode.width as i32, mode.height as i32))
+    });
+    if let Some((x, y, width, height)) = monitor_area {
+        let (window_width, window_height) = window.get_size();
+        window.set_pos(
+            x + (width - window_width) / 2,
+            y + (height - window_height) / 2,
+        );
     }
 }
+
+fn window_creation_error() -> FaeError {
+    FaeError::WindowCreation("could not create a glfw window".to_owned())
+}
+
+/// Sets the swap interval of the current context, and returns the
+/// mode that was actually set.
+fn set_swap_interval(glfw: &mut Glfw, mode: VsyncMode) -> VsyncMode {
+    let tear_supported = glfw.extension_supported("WGL_EXT_swap_control_tear")
+        || glfw.extension_supported("GLX_EXT_swap_control_tear");
+    match mode {
+        VsyncMode::Off => {
+            glfw.set_swap_interval(SwapInterval::None);
+            VsyncMode::Off
+        }
+        VsyncMode::Adaptive if tear_supported => {
+            glfw.set_swap_interval(SwapInterval::Adaptive);
+            VsyncMode::Adaptive
+        }
+        VsyncMode::On | VsyncMode::Adaptive => {
+            glfw.set_swap_interval(SwapInterval::Sync(1));
+            VsyncMode::On
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn raw_window_handle(glfw: &Glfw, window: &glfw::Window) -> RawWindowHandle {
+    RawWindowHandle::Xlib {
+        window: window.get_x11_window() as std::os::raw::c_ulong,
+        display: glfw.get_x11_display(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn raw_window_handle(_: &Glfw, window: &glfw::Window) -> RawWindowHandle {
+    RawWindowHandle::Win32 {
+        hwnd: window.get_win32_window(),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn raw_window_handle(_: &Glfw, window: &glfw::Window) -> RawWindowHandle {
+    RawWindowHandle::AppKit {
+        ns_window: window.get_cocoa_window(),
+        ns_view: std::ptr::null_mut(),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn raw_window_handle(_: &Glfw, _: &glfw::Window) -> RawWindowHandle {
+    RawWindowHandle::Unavailable
+}
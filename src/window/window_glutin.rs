@@ -37,18 +37,26 @@
 //! - `GDK_SCALE`
 //! - `ELM_SCALE`
 
+use crate::error::FaeError;
 use crate::gl;
+use crate::image::Image;
 use crate::renderer::Renderer;
-use crate::window::{get_env_dpi, Mouse};
+use crate::window::{
+    get_env_dpi, DoubleClickTracker, Event, FrameLimiter, KeyRepeatTimer, Mouse, RawWindowHandle,
+    ScrollDelta, VsyncMode,
+};
 use glutin::dpi::*;
 use glutin::*;
 use std::env;
-use std::error::Error;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 pub use crate::window::WindowSettings;
 pub use glutin;
 
+/// The keycode type used by the current window backend.
+pub type Key = VirtualKeyCode;
+
 /// Wrapper for a Glutin/Glfw window.
 pub struct Window {
     /// The width of the window.
@@ -58,6 +66,8 @@ pub struct Window {
     /// The dpi of the window.
     pub dpi_factor: f32,
     env_dpi_factor: f32,
+    ime_allowed: bool,
+    vsync_mode: VsyncMode,
     gl_window: GlWindow,
     events_loop: EventsLoop,
     /// The opengl legacy status for Renderer.
@@ -70,17 +80,61 @@ pub struct Window {
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
     pub pressed_keys: Vec<VirtualKeyCode>,
+    /// The held keys which were repeated this frame, possibly
+    /// several times each. Newly pressed keys are only in
+    /// `pressed_keys`, not here.
+    pub repeated_keys: Vec<Key>,
     /// The keys which were released this frame. Different type for
     /// each window backend, because there's no unified way of
     /// speaking in keycodes!
     pub released_keys: Vec<VirtualKeyCode>,
     /// The characters typed this frame, in chronological order.
     pub typed_chars: Vec<char>,
+    /// The text currently being composed with an input method
+    /// (preedit text), if any. IME composition is not reported by
+    /// the current window backends (glutin only sends the final
+    /// characters, with `typed_chars`, and glfw has no IME support),
+    /// so this is always `None` for now.
+    pub composition: Option<String>,
+
+    /// Whether the window gained or lost focus during this frame.
+    /// See [`Window::is_focused`].
+    pub focus_changed: bool,
+    /// Whether the window was minimized or restored during this
+    /// frame. See [`Window::is_minimized`].
+    pub minimize_changed: bool,
+    /// Whether the window's DPI factor changed during this frame, eg.
+    /// because the window was moved to a monitor with a different
+    /// scale. The viewport and the logical size are updated
+    /// automatically, and text rasterized by fae follows the
+    /// renderer's DPI factor (see [`Window::present`]), but
+    /// the application may want to re-rasterize its own caches.
+    pub dpi_changed: bool,
+    /// Whether the user tried to close the window during this frame,
+    /// eg. with the window's close button. If
+    /// `WindowSettings::auto_close` is disabled, the window stays
+    /// open until [`Window::request_close`] is called. See
+    /// [`Event::CloseRequested`].
+    pub close_requested: bool,
+    focused: bool,
+    minimized: bool,
+    closing: bool,
+    auto_close: bool,
 
     /// Whether the mouse is inside the window.
     pub mouse_inside: bool,
     /// The mouse position inside the window. Arrangement: (x, y)
     pub mouse_coords: (f32, f32),
+    /// The relative mouse motion during this frame. Arrangement: (x,
+    /// y)
+    ///
+    /// With the glutin backend, this is accumulated from the raw
+    /// device motion events, so it isn't affected by the cursor
+    /// hitting the edges of the screen, but the units depend on the
+    /// device (usually they're roughly pixels, without mouse
+    /// acceleration). With the glfw backend, this is the difference
+    /// between cursor positions, in logical pixels.
+    pub mouse_delta: (f32, f32),
     /// The mouse scroll amount during this frame, in pixels. If the
     /// user supports pixel-perfect scrolling, this will be equal to
     /// those pixel-perfect deltas. Otherwise, the polled scrolling
@@ -88,7 +142,23 @@ pub struct Window {
     /// the default settings, this will usually result in bursts of
     /// (0, -36) and (0, 36) during normal scrolling. Arrangement: (x,
     /// y)
+    ///
+    /// The x axis is horizontal scrolling, from trackpads and tilt
+    /// wheels. Trackpads usually scroll in pixels already, so check
+    /// `mouse_scroll_precise` before applying your own scaling to
+    /// either axis.
     pub mouse_scroll: (f32, f32),
+    /// The mouse scroll amount during this frame, in notches of the
+    /// scroll wheel, without `mouse_scroll_length` applied. Useful
+    /// for things like stepping through menu items. Pixel-perfect
+    /// scrolling is converted to notches by dividing with
+    /// `mouse_scroll_length`. Arrangement: (x, y)
+    pub mouse_scroll_notches: (f32, f32),
+    /// Whether the scrolling during this frame was reported in
+    /// pixels, eg. by a trackpad, instead of notches of a scroll
+    /// wheel. Pixel-perfect scrolling is passed through to
+    /// `mouse_scroll` as-is, without `mouse_scroll_length`.
+    pub mouse_scroll_precise: bool,
     /// How many pixels one "notch" on the mouse scroll wheel should
     /// scroll. (36 by default)
     pub mouse_scroll_length: f32,
@@ -98,12 +168,19 @@ pub struct Window {
     pub mouse_pressed: Vec<Mouse>,
     /// The mouse buttons which were released this frame.
     pub mouse_released: Vec<Mouse>,
+    /// The mouse buttons which were double clicked this frame. See
+    /// [`Window::set_double_click_time`].
+    pub mouse_double_clicked: Vec<Mouse>,
+    double_click: DoubleClickTracker,
+    frame_limiter: FrameLimiter,
+    key_repeat: KeyRepeatTimer<Key>,
 
     /// A list of files dropped on the window during this frame.
     pub dropped_files: Vec<PathBuf>,
     /// A list of files being currently hovered on the window. Does
     /// not work if using the GLFW backend.
     pub hovered_files: Vec<PathBuf>,
+    queued_events: Vec<Event>,
 }
 
 impl Window {
@@ -111,7 +188,7 @@ impl Window {
     ///
     /// Can result in an error if window creation fails or OpenGL
     /// context creation fails.
-    pub fn create(settings: &WindowSettings) -> Result<Window, Box<Error>> {
+    pub fn create(settings: &WindowSettings) -> Result<Window, FaeError> {
         let events_loop = EventsLoop::new();
         let opengl21;
         let gl_window = {
@@ -122,19 +199,59 @@ impl Window {
                         f64::from(settings.width),
                         f64::from(settings.height),
                     ))
+                    .with_decorations(settings.decorated)
+                    .with_always_on_top(settings.always_on_top)
+                    .with_transparency(settings.transparent)
                     .with_visibility(false);
                 if settings.is_dialog {
                     window = window_as_dialog(window);
                 }
-                let context = ContextBuilder::new()
-                    .with_vsync(settings.vsync)
-                    .with_srgb(true)
-                    .with_gl(gl_request)
-                    .with_gl_profile(gl_profile);
-                GlWindow::new(window, context, &events_loop)
+                let context = |buffers: bool| {
+                    let context = ContextBuilder::new()
+                        .with_vsync(settings.vsync != VsyncMode::Off)
+                        .with_srgb(true)
+                        .with_gl(gl_request)
+                        .with_gl_profile(gl_profile);
+                    if buffers {
+                        context
+                            .with_depth_buffer(settings.depth_bits)
+                            .with_stencil_buffer(settings.stencil_bits)
+                    } else {
+                        context
+                    }
+                };
+                GlWindow::new(window.clone(), context(true), &events_loop)
+                    .or_else(|err| match err {
+                        CreationError::NoAvailablePixelFormat => {
+                            log::warn!(
+                                "Could not create a window with {} depth bits and {} stencil bits, retrying without depth and stencil requirements",
+                                settings.depth_bits,
+                                settings.stencil_bits,
+                            );
+                            GlWindow::new(window, context(false), &events_loop)
+                        }
+                        err => Err(err),
+                    })
+                    .map_err(|err| match err {
+                        CreationError::Window(err) => FaeError::WindowCreation(err.to_string()),
+                        err => FaeError::ContextCreation(err.to_string()),
+                    })
             };
 
-            if env::var_os("FAE_OPENGL_LEGACY").is_some() {
+            if let Some((major, minor, es)) = settings.requested_gl_version {
+                opengl21 = major < 3;
+                let (api, core_version) = if es {
+                    (Api::OpenGlEs, (3, 0))
+                } else {
+                    (Api::OpenGl, (3, 2))
+                };
+                let profile = if (major, minor) >= core_version {
+                    GlProfile::Core
+                } else {
+                    GlProfile::Compatibility
+                };
+                create_window(GlRequest::Specific(api, (major, minor)), profile)?
+            } else if settings.force_legacy || env::var_os("FAE_OPENGL_LEGACY").is_some() {
                 opengl21 = true;
                 create_window(
                     GlRequest::GlThenGles {
@@ -176,7 +293,9 @@ impl Window {
         };
 
         unsafe {
-            gl_window.make_current()?;
+            gl_window
+                .make_current()
+                .map_err(|err| FaeError::ContextCreation(err.to_string()))?;
             gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
             /* use std::ffi::CStr;
 
@@ -190,32 +309,69 @@ impl Window {
             }*/
         }
 
+        // Match the viewport and DPI factor to the window from the
+        // start, instead of waiting for the first resize event
+        let hidpi_factor = gl_window.get_hidpi_factor();
+        if let Some(logical_size) = gl_window.get_inner_size() {
+            let (width, height): (u32, u32) = logical_size.to_physical(hidpi_factor).into();
+            unsafe {
+                gl::Viewport(0, 0, width as i32, height as i32);
+            }
+        }
+
+        if settings.center {
+            center_window(&gl_window);
+        }
         gl_window.show();
 
         Ok(Window {
             width: settings.width,
             height: settings.height,
-            dpi_factor: 1.0,
+            dpi_factor: hidpi_factor as f32 * env_dpi_factor,
             env_dpi_factor,
+            ime_allowed: true,
+            vsync_mode: match settings.vsync {
+                VsyncMode::Off => VsyncMode::Off,
+                VsyncMode::On | VsyncMode::Adaptive => VsyncMode::On,
+            },
             gl_window,
             events_loop,
             opengl21,
 
             held_keys: Vec::new(),
             pressed_keys: Vec::new(),
+            repeated_keys: Vec::new(),
             released_keys: Vec::new(),
             typed_chars: Vec::new(),
+            composition: None,
+
+            focus_changed: false,
+            minimize_changed: false,
+            dpi_changed: false,
+            close_requested: false,
+            focused: true,
+            minimized: false,
+            closing: false,
+            auto_close: settings.auto_close,
 
             mouse_inside: false,
             mouse_coords: (0.0, 0.0),
+            mouse_delta: (0.0, 0.0),
             mouse_scroll: (0.0, 0.0),
+            mouse_scroll_notches: (0.0, 0.0),
+            mouse_scroll_precise: false,
             mouse_scroll_length: 36.0,
             mouse_held: Vec::new(),
             mouse_pressed: Vec::new(),
             mouse_released: Vec::new(),
+            mouse_double_clicked: Vec::new(),
+            double_click: DoubleClickTracker::new(),
+            frame_limiter: FrameLimiter::new(),
+            key_repeat: KeyRepeatTimer::new(),
 
             dropped_files: Vec::new(),
             hovered_files: Vec::new(),
+            queued_events: Vec::new(),
         })
     }
 
@@ -226,10 +382,29 @@ impl Window {
         self.gl_window.set_cursor(cursor);
     }
 
+    /// Returns the native handles of the window, for interoperating
+    /// with libraries which need access to the native window. With
+    /// the `raw-window-handle` feature, the window also implements
+    /// `HasRawWindowHandle` and `HasRawDisplayHandle`.
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        raw_window_handle(&self.gl_window)
+    }
+
+    /// Sets the cursor graphic to a custom image. Not supported by
+    /// glutin, which only has the standard cursors of
+    /// [`Window::set_cursor`], so this always returns an error.
+    #[allow(unused_variables)]
+    pub fn set_cursor_image(&mut self, image: &Image, hotspot: (u32, u32)) -> Result<(), FaeError> {
+        Err(FaeError::Unsupported(
+            "glutin does not support custom cursor images".to_owned(),
+        ))
+    }
+
     /// Updates the window (swaps the front and back buffers). The
-    /// renderer handle is used for a CPU/GPU synchronization call, so
-    /// while it is optional, it's definitely recommended. If vsync is
-    /// enabled, this function will hang until the next frame.
+    /// renderer handle is used for a CPU/GPU synchronization call
+    /// (see [`Renderer::synchronize`]), so while it is optional,
+    /// it's definitely recommended. If vsync is enabled, this
+    /// function will hang until the next frame.
     pub fn swap_buffers(&mut self, renderer: Option<&Renderer>) {
         let _ = self.gl_window.swap_buffers();
         if let Some(renderer) = renderer {
@@ -237,58 +412,367 @@ impl Window {
         }
     }
 
-    /// Polls for new events. Returns whether the user has requested
-    /// the window to be closed.
+    /// Sets the vsync mode of the window. Glutin can only set vsync
+    /// when creating the window (see [`WindowSettings`]), so this
+    /// returns an error if the mode would change. Adaptive vsync is
+    /// not supported, and is treated as `VsyncMode::On`.
+    pub fn set_vsync_mode(&mut self, mode: VsyncMode) -> Result<(), FaeError> {
+        let mode = if mode == VsyncMode::Adaptive {
+            VsyncMode::On
+        } else {
+            mode
+        };
+        if mode == self.vsync_mode {
+            Ok(())
+        } else {
+            Err(FaeError::Unsupported(
+                "glutin can only set vsync when creating the window".to_owned(),
+            ))
+        }
+    }
+
+    /// Returns the vsync mode that is actually in use, which can
+    /// differ from the requested one, see [`VsyncMode::Adaptive`].
+    pub fn vsync_mode(&self) -> VsyncMode {
+        self.vsync_mode
+    }
+
+    /// Caps the frame rate to `frame_rate` frames per second, by
+    /// waiting in [`Window::refresh`] until the next frame is due.
+    /// `None` removes the limit, which is the default.
+    ///
+    /// Useful when vsync is off (see [`Window::set_vsync_mode`]), to
+    /// avoid spinning the CPU and GPU at thousands of frames per
+    /// second, or for lowering the power usage of mostly idle
+    /// applications.
+    pub fn set_frame_rate_limit(&mut self, frame_rate: Option<f32>) {
+        self.frame_limiter.set_frame_rate(frame_rate);
+    }
+
+    /// Sets how long a key needs to be held before it starts
+    /// repeating, and the time between repeats after that. 500 and
+    /// 33 milliseconds by default. The repeats are reported in
+    /// `repeated_keys` and as [`Event::KeyRepeated`] events.
+    pub fn set_key_repeat(&mut self, delay: Duration, interval: Duration) {
+        self.key_repeat.delay = delay;
+        self.key_repeat.interval = interval;
+    }
+
+    /// Returns whether the window has the keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Returns whether the window is minimized (iconified).
+    ///
+    /// Winit doesn't report minimization directly, so this is
+    /// inferred from the window being resized to zero size, or moved
+    /// to (-32000, -32000) like minimized windows are on Windows.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Returns whether `key` was pressed down during this frame. See
+    /// `pressed_keys`.
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    /// Returns whether `key` is currently held down. See
+    /// `held_keys`.
+    pub fn key_held(&self, key: Key) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    /// Returns whether `key` was released during this frame. See
+    /// `released_keys`.
+    pub fn key_released(&self, key: Key) -> bool {
+        self.released_keys.contains(&key)
+    }
+
+    /// Returns whether `button` was pressed down during this
+    /// frame. See the `mouse_pressed` field.
+    pub fn mouse_pressed(&self, button: Mouse) -> bool {
+        self.mouse_pressed.contains(&button)
+    }
+
+    /// Returns whether `button` is currently held down. See the
+    /// `mouse_held` field.
+    pub fn mouse_held(&self, button: Mouse) -> bool {
+        self.mouse_held.contains(&button)
+    }
+
+    /// Returns whether `button` was released during this frame. See
+    /// the `mouse_released` field.
+    pub fn mouse_released(&self, button: Mouse) -> bool {
+        self.mouse_released.contains(&button)
+    }
+
+    /// Sets the longest time between two presses of a mouse button
+    /// which still counts as a double click. 500 milliseconds by
+    /// default.
+    pub fn set_double_click_time(&mut self, interval: Duration) {
+        self.double_click.interval = interval;
+    }
+
+    /// Sets the furthest distance (in logical pixels) the mouse can
+    /// move between two presses which still count as a double
+    /// click. 4 pixels by default.
+    pub fn set_double_click_distance(&mut self, distance: f32) {
+        self.double_click.distance = distance;
+    }
+
+    /// Toggles whether the input method editor (IME) of the
+    /// platform can be used for typing into the window. Glutin
+    /// always keeps the IME enabled where it is supported, so this
+    /// only controls whether [`Window::set_ime_position`] moves the
+    /// IME candidate window.
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        self.ime_allowed = allowed;
+    }
+
+    /// Moves the IME candidate window near the given position (in
+    /// logical pixels), eg. the caret of the focused text field.
+    pub fn set_ime_position(&mut self, x: f32, y: f32) {
+        if self.ime_allowed {
+            let position = LogicalPosition::new(
+                f64::from(x * self.env_dpi_factor),
+                f64::from(y * self.env_dpi_factor),
+            );
+            self.gl_window.set_ime_spot(position);
+        }
+    }
+
+    /// Moves the window so that its top-left corner is at `position`
+    /// (in logical pixels, from the top-left corner of the desktop).
+    /// Along with the mouse events, this can be used to implement
+    /// title bar dragging for undecorated windows (see
+    /// `WindowSettings::decorated`).
+    pub fn set_position(&mut self, (x, y): (f32, f32)) {
+        let position = LogicalPosition::new(
+            f64::from(x * self.env_dpi_factor),
+            f64::from(y * self.env_dpi_factor),
+        );
+        self.gl_window.set_position(position);
+    }
+
+    /// Moves the window to the center of the primary monitor. See
+    /// also `WindowSettings::center`.
+    pub fn center(&mut self) {
+        center_window(&self.gl_window);
+    }
+
+    /// Returns the position of the window's top-left corner, in
+    /// logical pixels from the top-left corner of the desktop. See
+    /// [`Window::set_position`]. Returns `(0.0, 0.0)` if the position isn't
+    /// available, like on Wayland.
+    pub fn position(&self) -> (f32, f32) {
+        match self.gl_window.get_position() {
+            Some(position) => (
+                position.x as f32 / self.env_dpi_factor,
+                position.y as f32 / self.env_dpi_factor,
+            ),
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, and removes
+    /// them from the window. Events that aren't polled are discarded
+    /// in the next refresh. The `pressed_keys`, `mouse_coords`, etc.
+    /// fields are updated from the same events, so they can be used
+    /// alongside this.
+    pub fn poll_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.queued_events.drain(..)
+    }
+
+    /// Returns the events that happened during the last
+    /// [`Window::refresh`], in the order they happened, without
+    /// removing them like [`Window::poll_events`] does.
+    pub fn events(&self) -> &[Event] {
+        &self.queued_events
+    }
+
+    /// Requests the window to be closed, eg. from a "Quit" menu
+    /// item: the next [`Window::refresh`] returns `false`, like when
+    /// the user closes the window.
+    pub fn request_close(&mut self) {
+        self.closing = true;
+    }
+
+    /// Cancels a close request, either from [`Window::request_close`]
+    /// or the user closing the window, so that the next
+    /// [`Window::refresh`] returns `true` again. Useful for asking
+    /// the user to save their work before quitting, for example.
+    pub fn cancel_close(&mut self) {
+        self.closing = false;
+    }
+
+    /// Renders the queued draws of the renderer to the window, and
+    /// then swaps the buffers and synchronizes, like
+    /// [`Window::swap_buffers`]. The renderer's DPI factor is also
+    /// updated to the window's, see [`Renderer::set_dpi_factor`].
+    ///
+    /// This is equivalent to calling `renderer.set_dpi_factor`,
+    /// `renderer.render` with the window's size, and
+    /// `window.swap_buffers` in order, which can still be done
+    /// manually if more control is needed.
+    pub fn present(&mut self, renderer: &mut Renderer) {
+        renderer.set_dpi_factor(self.dpi_factor);
+        renderer.render(self.width, self.height);
+        self.swap_buffers(Some(renderer));
+    }
+
+    /// Polls for new events. Returns `false` when the window should
+    /// be closed, see [`Window::request_close`] and
+    /// `WindowSettings::auto_close`.
     pub fn refresh(&mut self) -> bool {
-        let mut running = true;
+        self.frame_limiter.wait();
+        let closing = &mut self.closing;
+        let auto_close = self.auto_close;
+        let close_requested = &mut self.close_requested;
+        *close_requested = false;
         let mut resized_logical_size = None;
         let mut updated_dpi_factor = None;
-        let mut key_inputs = Vec::new();
-        let mut mouse_inputs = Vec::new();
+        let previous_dpi_factor = self.dpi_factor;
+        let env_dpi_factor = self.env_dpi_factor;
+        let events = &mut self.queued_events;
+        let held_keys = &mut self.held_keys;
+        let pressed_keys = &mut self.pressed_keys;
+        let repeated_keys = &mut self.repeated_keys;
+        let key_repeat = &mut self.key_repeat;
+        let released_keys = &mut self.released_keys;
+        let mouse_held = &mut self.mouse_held;
+        let mouse_pressed = &mut self.mouse_pressed;
+        let mouse_released = &mut self.mouse_released;
+        let mouse_double_clicked = &mut self.mouse_double_clicked;
+        let double_click = &mut self.double_click;
         let typed_chars = &mut self.typed_chars;
         let mouse_coords = &mut self.mouse_coords;
         let mouse_inside = &mut self.mouse_inside;
+        let (was_focused, was_minimized) = (self.focused, self.minimized);
+        let focused = &mut self.focused;
+        let minimized = &mut self.minimized;
+        let mouse_delta = &mut self.mouse_delta;
         let scroll = &mut self.mouse_scroll;
+        let scroll_notches = &mut self.mouse_scroll_notches;
+        let scroll_precise = &mut self.mouse_scroll_precise;
         let scroll_length = self.mouse_scroll_length;
         let dropped_files = &mut self.dropped_files;
         let hovered_files = &mut self.hovered_files;
 
         *scroll = (0.0, 0.0);
+        *scroll_notches = (0.0, 0.0);
+        *scroll_precise = false;
+        *mouse_delta = (0.0, 0.0);
+        events.clear();
+        pressed_keys.clear();
+        repeated_keys.clear();
+        released_keys.clear();
+        mouse_pressed.clear();
+        mouse_released.clear();
+        mouse_double_clicked.clear();
         typed_chars.clear();
         dropped_files.clear();
 
         self.events_loop.poll_events(|event| {
-            if let Event::WindowEvent { event, .. } = event {
+            if let glutin::Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } = event
+            {
+                mouse_delta.0 += x as f32;
+                mouse_delta.1 += y as f32;
+            } else if let glutin::Event::WindowEvent { event, .. } = event {
                 match event {
-                    WindowEvent::CloseRequested | WindowEvent::Destroyed => running = false,
-                    WindowEvent::Resized(logical_size) => resized_logical_size = Some(logical_size),
+                    WindowEvent::CloseRequested => {
+                        *close_requested = true;
+                        *closing |= auto_close;
+                        events.push(Event::CloseRequested);
+                    }
+                    WindowEvent::Destroyed => {
+                        *closing = true;
+                        events.push(Event::CloseRequested);
+                    }
+                    WindowEvent::Resized(logical_size) => {
+                        *minimized = logical_size.width == 0.0 || logical_size.height == 0.0;
+                        resized_logical_size = Some(logical_size);
+                        events.push(Event::Resized(
+                            logical_size.width as f32 / env_dpi_factor,
+                            logical_size.height as f32 / env_dpi_factor,
+                        ));
+                    }
                     WindowEvent::HiDpiFactorChanged(factor) => updated_dpi_factor = Some(factor),
+                    WindowEvent::Focused(is_focused) => *focused = is_focused,
+                    WindowEvent::Moved(position) => {
+                        *minimized = position.x == -32000.0 && position.y == -32000.0;
+                    }
 
                     WindowEvent::KeyboardInput { input, .. } => {
-                        let state = input.state;
                         if let Some(key) = input.virtual_keycode {
-                            key_inputs.push((key, state));
+                            match input.state {
+                                ElementState::Pressed => {
+                                    if !held_keys.contains(&key) {
+                                        pressed_keys.push(key);
+                                        held_keys.push(key);
+                                        key_repeat.press(key, Instant::now());
+                                        events.push(Event::KeyPressed(key));
+                                    }
+                                }
+                                ElementState::Released => {
+                                    released_keys.push(key);
+                                    held_keys.retain(|held_key| held_key != &key);
+                                    key_repeat.release(key);
+                                    events.push(Event::KeyReleased(key));
+                                }
+                            }
                         }
                     }
-                    WindowEvent::ReceivedCharacter(c) => typed_chars.push(c),
+                    WindowEvent::ReceivedCharacter(c) => {
+                        typed_chars.push(c);
+                        events.push(Event::CharTyped(c));
+                    }
 
-                    WindowEvent::MouseInput { state, button, .. } => match button {
-                        MouseButton::Left => mouse_inputs.push((Mouse::Left, state)),
-                        MouseButton::Right => mouse_inputs.push((Mouse::Right, state)),
-                        MouseButton::Middle => mouse_inputs.push((Mouse::Middle, state)),
-                        MouseButton::Other(n) => mouse_inputs.push((Mouse::Other(n + 3), state)),
-                    },
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        let button = match button {
+                            MouseButton::Left => Mouse::Left,
+                            MouseButton::Right => Mouse::Right,
+                            MouseButton::Middle => Mouse::Middle,
+                            MouseButton::Other(n) => other_mouse_button(n),
+                        };
+                        let pressed = state == ElementState::Pressed;
+                        if pressed {
+                            mouse_pressed.push(button);
+                            mouse_held.push(button);
+                        } else {
+                            mouse_released.push(button);
+                            if let Some(i) = mouse_held.iter().position(|held| held == &button) {
+                                mouse_held.remove(i);
+                            }
+                        }
+                        events.push(Event::MouseButton { button, pressed });
+                        if pressed && double_click.press(button, *mouse_coords, Instant::now()) {
+                            mouse_double_clicked.push(button);
+                            events.push(Event::MouseDoubleClick(button));
+                        }
+                    }
                     WindowEvent::CursorMoved { position, .. } => {
                         *mouse_coords = (position.x as f32, position.y as f32);
+                        events.push(Event::MouseMoved(mouse_coords.0, mouse_coords.1));
                     }
                     WindowEvent::CursorEntered { .. } => *mouse_inside = true,
                     WindowEvent::CursorLeft { .. } => *mouse_inside = false,
-                    WindowEvent::MouseWheel { delta, .. } => match delta {
-                        MouseScrollDelta::LineDelta(x, y) => {
-                            *scroll = (scroll_length * x, scroll_length * y)
-                        }
-                        MouseScrollDelta::PixelDelta(pos) => *scroll = (pos.x as f32, pos.y as f32),
-                    },
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        let delta = match delta {
+                            MouseScrollDelta::LineDelta(x, y) => ScrollDelta::Notches(x, y),
+                            MouseScrollDelta::PixelDelta(pos) => {
+                                *scroll_precise = true;
+                                ScrollDelta::Pixels(pos.x as f32, pos.y as f32)
+                            }
+                        };
+                        let (x, y) = delta.accumulate(scroll, scroll_notches, scroll_length);
+                        events.push(Event::Scroll(x, y));
+                    }
 
                     WindowEvent::DroppedFile(path) => {
                         for (i, hovered_path) in hovered_files.iter().enumerate() {
@@ -297,6 +781,7 @@ impl Window {
                                 break;
                             }
                         }
+                        events.push(Event::FileDropped(path.clone()));
                         dropped_files.push(path);
                     }
                     WindowEvent::HoveredFile(path) => {
@@ -311,56 +796,14 @@ impl Window {
             }
         });
 
-        /* Keyboard event handling */
-        self.pressed_keys.clear();
-        self.released_keys.clear();
-        for (key, state) in key_inputs {
-            match state {
-                ElementState::Pressed => {
-                    let mut already_pressed = false;
-                    for previously_pressed_key in &self.held_keys {
-                        if previously_pressed_key == &key {
-                            already_pressed = true;
-                            break;
-                        }
-                    }
+        self.focus_changed = self.focused != was_focused;
+        self.minimize_changed = self.minimized != was_minimized;
 
-                    if !already_pressed {
-                        self.pressed_keys.push(key);
-                        self.held_keys.push(key);
-                    }
-                }
-                ElementState::Released => {
-                    self.released_keys.push(key);
-                    for (i, held_key) in self.held_keys.iter().enumerate() {
-                        if held_key == &key {
-                            self.held_keys.remove(i);
-                            break;
-                        }
-                    }
-                }
-            }
-        }
-
-        /* Mouse event handling */
-        self.mouse_pressed.clear();
-        self.mouse_released.clear();
-        for (button, state) in mouse_inputs {
-            match state {
-                ElementState::Pressed => {
-                    self.mouse_pressed.push(button);
-                    self.mouse_held.push(button);
-                }
-                ElementState::Released => {
-                    self.mouse_released.push(button);
-                    for (i, held_button) in self.mouse_held.iter().enumerate() {
-                        if held_button == &button {
-                            self.mouse_held.remove(i);
-                            break;
-                        }
-                    }
-                }
-            }
+        /* Key repeats */
+        self.key_repeat
+            .update(Instant::now(), &mut self.repeated_keys);
+        for key in &self.repeated_keys {
+            self.queued_events.push(Event::KeyRepeated(*key));
         }
 
         /* Resize event handling */
@@ -393,8 +836,64 @@ impl Window {
                 self.dpi_factor = dpi_factor as f32 * self.env_dpi_factor;
             }
         }
+        self.dpi_changed = self.dpi_factor != previous_dpi_factor;
 
-        running
+        !self.closing
+    }
+}
+
+// Safety: the handles are the ones of the window, which stays open
+// (and the handles valid) until it's dropped.
+#[cfg(feature = "raw-window-handle")]
+unsafe impl ::raw_window_handle::HasRawWindowHandle for Window {
+    /// Panics if the handles are
+    /// [`RawWindowHandle::Unavailable`].
+    fn raw_window_handle(&self) -> ::raw_window_handle::RawWindowHandle {
+        Window::raw_window_handle(self).into_raw_handles().0
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+unsafe impl ::raw_window_handle::HasRawDisplayHandle for Window {
+    /// Panics if the handles are
+    /// [`RawWindowHandle::Unavailable`].
+    fn raw_display_handle(&self) -> ::raw_window_handle::RawDisplayHandle {
+        Window::raw_window_handle(self).into_raw_handles().1
+    }
+}
+
+/// Maps winit's platform-specific numbers for the extra mouse
+/// buttons to [`Mouse::Back`] and [`Mouse::Forward`]: Windows'
+/// XBUTTON1 and XBUTTON2, X11's buttons 8 and 9, and the DOM's
+/// buttons 3 and 4. Other buttons keep their raw number.
+fn other_mouse_button(n: u8) -> Mouse {
+    let (back, forward) = if cfg!(target_os = "windows") {
+        (1, 2)
+    } else if cfg!(target_os = "emscripten") {
+        (3, 4)
+    } else {
+        (8, 9)
+    };
+    if n == back {
+        Mouse::Back
+    } else if n == forward {
+        Mouse::Forward
+    } else {
+        Mouse::Other(n)
+    }
+}
+
+/// Moves the window to the center of the primary monitor.
+fn center_window(gl_window: &GlWindow) {
+    let monitor = gl_window.get_primary_monitor();
+    let monitor_dpi_factor = monitor.get_hidpi_factor();
+    let monitor_position = monitor.get_position().to_logical(monitor_dpi_factor);
+    let monitor_size = monitor.get_dimensions().to_logical(monitor_dpi_factor);
+    if let Some(window_size) = gl_window.get_outer_size() {
+        gl_window.set_position(LogicalPosition::new(
+            monitor_position.x + (monitor_size.width - window_size.width) / 2.0,
+            monitor_position.y + (monitor_size.height - window_size.height) / 2.0,
+        ));
     }
 }
 
@@ -439,3 +938,53 @@ fn is_wayland(window: &GlWindow) -> bool {
 fn is_wayland(_: &GlWindow) -> bool {
     false
 }
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd"
+))]
+fn raw_window_handle(window: &GlWindow) -> RawWindowHandle {
+    use glutin::os::unix::WindowExt;
+    if let (Some(surface), Some(display)) =
+        (window.get_wayland_surface(), window.get_wayland_display())
+    {
+        RawWindowHandle::Wayland { surface, display }
+    } else if let (Some(window), Some(display)) =
+        (window.get_xlib_window(), window.get_xlib_display())
+    {
+        RawWindowHandle::Xlib { window, display }
+    } else {
+        RawWindowHandle::Unavailable
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn raw_window_handle(window: &GlWindow) -> RawWindowHandle {
+    use glutin::os::windows::WindowExt;
+    RawWindowHandle::Win32 {
+        hwnd: window.get_hwnd() as *mut _,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn raw_window_handle(window: &GlWindow) -> RawWindowHandle {
+    use glutin::os::macos::WindowExt;
+    RawWindowHandle::AppKit {
+        ns_window: window.get_nswindow(),
+        ns_view: window.get_nsview(),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "windows",
+    target_os = "macos"
+)))]
+fn raw_window_handle(_: &GlWindow) -> RawWindowHandle {
+    RawWindowHandle::Unavailable
+}